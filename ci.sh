@@ -5,3 +5,9 @@ set -xe
 cargo fmt --all -- --check
 cargo clippy --all --locked -- -D warnings
 cargo test --all --locked
+# The optional features, e.g. the GeoIP lookups, are only compiled and tested with them enabled.
+cargo clippy --all --all-targets --all-features --locked -- -D warnings
+cargo test --all --all-features --locked
+# The OTLP export path on its own, so the opentelemetry dependencies are checked without the
+# other features.
+cargo clippy -p quilclient --all-targets --features opentelemetry --locked -- -D warnings
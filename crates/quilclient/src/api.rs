@@ -0,0 +1,655 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tonic::transport::Uri;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::validate_request::ValidateRequestHeaderLayer;
+
+use quilibrium::node::{ClockFrame, FrameFilter, FramesOptions, NodeClient, NodeClientError};
+
+/// The maximum number of cached responses. The entry closest to expiring is evicted first.
+const MAX_CACHE_ENTRIES: usize = 1024;
+
+/// The maximum number of frames `/frames` returns per request.
+const MAX_FRAMES_PER_REQUEST: u64 = 100;
+
+/// Options of the API server.
+#[derive(Debug, Clone)]
+pub struct ApiOptions {
+    /// How long successful responses are cached. Zero disables the cache.
+    pub cache_ttl: Duration,
+    /// The origins allowed to call the API from a browser. "*" allows any origin.
+    pub cors_origins: Vec<String>,
+    /// The token requests must carry in an `Authorization: Bearer <token>` header.
+    pub bearer_token: Option<String>,
+    /// How long to wait for the node on each request, including connecting to it.
+    pub timeout: Duration,
+}
+
+/// An error response with a JSON body of the form `{"error": "<message>"}`.
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<NodeClientError> for ApiError {
+    fn from(error: NodeClientError) -> Self {
+        let status = match error {
+            NodeClientError::InvalidFrameFilter => StatusCode::BAD_REQUEST,
+            _ => StatusCode::BAD_GATEWAY,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        #[derive(Serialize)]
+        struct Body {
+            error: String,
+        }
+        (
+            self.status,
+            Json(Body {
+                error: self.message,
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// A successful response body kept until it expires.
+struct CachedResponse {
+    expires_at: Instant,
+    body: Bytes,
+}
+
+struct Api {
+    uri: Uri,
+    client: Mutex<Option<NodeClient>>,
+    cache: Mutex<HashMap<String, CachedResponse>>,
+    cache_ttl: Duration,
+    timeout: Duration,
+    openapi: serde_json::Value,
+}
+
+impl Api {
+    /// Connect to the node if it isn't connected yet. The client is cloned so that requests don't
+    /// wait on each other, and the lock isn't held while connecting so that a slow connect doesn't
+    /// hold up requests past their timeout. Concurrent first requests connect in parallel and the
+    /// first connected client is kept.
+    async fn client(&self) -> Result<NodeClient, ApiError> {
+        if let Some(client) = self.client.lock().await.as_ref() {
+            return Ok(client.clone());
+        }
+        let connected = NodeClient::new(self.uri.clone()).await?;
+        Ok(self.client.lock().await.get_or_insert(connected).clone())
+    }
+
+    /// Respond with the cached body for the key if it hasn't expired, otherwise fetch, cache and
+    /// respond with a fresh one. Errors aren't cached.
+    async fn cached<T, F, Fut>(&self, key: String, fetch: F) -> Result<Response, ApiError>
+    where
+        T: Serialize,
+        F: FnOnce(NodeClient) -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let now = Instant::now();
+        {
+            let mut cache = self.cache.lock().await;
+            match cache.get(&key) {
+                Some(cached) if cached.expires_at > now => {
+                    return Ok(self.json_response(cached.body.clone()))
+                }
+                Some(_) => {
+                    cache.remove(&key);
+                }
+                None => {}
+            }
+        }
+
+        let value = tokio::time::timeout(self.timeout, async { fetch(self.client().await?).await })
+            .await
+            .map_err(|_| ApiError::new(StatusCode::GATEWAY_TIMEOUT, "The node timed out"))??;
+        let body = Bytes::from(serde_json::to_vec(&value).map_err(|error| {
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        })?);
+        if !self.cache_ttl.is_zero() {
+            let mut cache = self.cache.lock().await;
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.retain(|_, cached| cached.expires_at > now);
+            }
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                let closest_to_expiring = cache
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.expires_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(closest_to_expiring) = closest_to_expiring {
+                    cache.remove(&closest_to_expiring);
+                }
+            }
+            cache.insert(
+                key,
+                CachedResponse {
+                    expires_at: now + self.cache_ttl,
+                    body: body.clone(),
+                },
+            );
+        }
+        Ok(self.json_response(body))
+    }
+
+    fn json_response(&self, body: Bytes) -> Response {
+        let cache_control = format!("max-age={}", self.cache_ttl.as_secs());
+        (
+            [
+                (header::CONTENT_TYPE, "application/json".to_string()),
+                (header::CACHE_CONTROL, cache_control),
+            ],
+            body,
+        )
+            .into_response()
+    }
+}
+
+/// Serve the REST/JSON API for the node at `uri` until the process is stopped.
+pub async fn serve(listen: SocketAddr, uri: Uri, options: ApiOptions) -> Result<()> {
+    axum::Server::bind(&listen)
+        .serve(router(uri, options)?.into_make_service())
+        .await?;
+    Ok(())
+}
+
+fn router(uri: Uri, options: ApiOptions) -> Result<Router> {
+    let api = Arc::new(Api {
+        uri,
+        client: Mutex::new(None),
+        cache: Mutex::new(HashMap::new()),
+        cache_ttl: options.cache_ttl,
+        timeout: options.timeout,
+        openapi: openapi_document(options.bearer_token.is_some()),
+    });
+
+    let mut node_routes = Router::new()
+        .route("/frames", get(frames))
+        .route("/frame/:filter/:frame_number", get(frame))
+        .route("/peers", get(peers))
+        .route("/network", get(network))
+        .route("/manifests", get(manifests))
+        .route("/token", get(token));
+    if let Some(token) = &options.bearer_token {
+        node_routes = node_routes.route_layer(ValidateRequestHeaderLayer::bearer(token));
+    }
+    let mut app = Router::new()
+        .route("/openapi.json", get(openapi))
+        .merge(node_routes)
+        .with_state(api);
+    if !options.cors_origins.is_empty() {
+        app = app.layer(cors_layer(&options.cors_origins)?);
+    }
+    Ok(app)
+}
+
+fn cors_layer(origins: &[String]) -> Result<CorsLayer> {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|origin| HeaderValue::from_str(origin))
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(origins)
+    };
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET])
+        .allow_headers([header::AUTHORIZATION]))
+}
+
+#[derive(Debug, Deserialize)]
+struct FramesQuery {
+    filter: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+    include_candidates: Option<bool>,
+}
+
+async fn frames(
+    State(api): State<Arc<Api>>,
+    Query(query): Query<FramesQuery>,
+) -> Result<Response, ApiError> {
+    let filter: FrameFilter = match &query.filter {
+        Some(filter) => filter.parse()?,
+        None => FrameFilter::CeremonyApplication,
+    };
+    let from = query.from.unwrap_or(1);
+    let to = query.to.unwrap_or(from.saturating_add(10));
+    if to.saturating_sub(from) > MAX_FRAMES_PER_REQUEST {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("At most {MAX_FRAMES_PER_REQUEST} frames can be requested at once"),
+        ));
+    }
+    let include_candidates = query.include_candidates.unwrap_or_default();
+    // Keyed by the options rather than the query string, so unknown parameters share an entry.
+    let key =
+        format!("/frames?filter={filter}&from={from}&to={to}&candidates={include_candidates}");
+    let options = FramesOptions::new()
+        .filter(filter)
+        .from_frame_number(from)
+        .to_frame_number(to)
+        .include_candidates(include_candidates);
+    api.cached(key, |mut client| async move {
+        Ok(client.frames(options).await?)
+    })
+    .await
+}
+
+async fn frame(
+    State(api): State<Arc<Api>>,
+    Path((filter, frame_number)): Path<(String, u64)>,
+) -> Result<Response, ApiError> {
+    let filter: FrameFilter = filter.parse()?;
+    let key = format!("/frame/{filter}/{frame_number}");
+    api.cached(key, |mut client| async move {
+        let frame = client
+            .frame_info(filter, frame_number)
+            .await?
+            .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "The frame was not found"))?;
+        Ok(ClockFrame::try_from(frame)?)
+    })
+    .await
+}
+
+async fn peers(State(api): State<Arc<Api>>) -> Result<Response, ApiError> {
+    api.cached("/peers".to_string(), |mut client| async move {
+        Ok(client.peer_info().await?)
+    })
+    .await
+}
+
+async fn network(State(api): State<Arc<Api>>) -> Result<Response, ApiError> {
+    api.cached("/network".to_string(), |mut client| async move {
+        Ok(client.network_info().await?)
+    })
+    .await
+}
+
+async fn manifests(State(api): State<Arc<Api>>) -> Result<Response, ApiError> {
+    api.cached("/manifests".to_string(), |mut client| async move {
+        Ok(client.peer_manifests().await?)
+    })
+    .await
+}
+
+async fn token(State(api): State<Arc<Api>>) -> Result<Response, ApiError> {
+    api.cached("/token".to_string(), |mut client| async move {
+        Ok(client.token_info().await?)
+    })
+    .await
+}
+
+async fn openapi(State(api): State<Arc<Api>>) -> impl IntoResponse {
+    Json(api.openapi.clone())
+}
+
+/// The OpenAPI 3.0 document describing the API. The node routes require a bearer token if
+/// `bearer_auth` is set.
+fn openapi_document(bearer_auth: bool) -> serde_json::Value {
+    use serde_json::json;
+
+    let json_response = |description: &str, schema: &str| {
+        let mut responses = json!({
+            "200": {
+                "description": description,
+                "content": {
+                    "application/json": {"schema": {"$ref": format!("#/components/schemas/{schema}")}}
+                }
+            },
+            "400": {"$ref": "#/components/responses/NodeError"},
+            "502": {"$ref": "#/components/responses/NodeError"},
+            "504": {"$ref": "#/components/responses/NodeError"}
+        });
+        if bearer_auth {
+            responses["401"] = json!({"description": "The bearer token is missing or invalid."});
+        }
+        responses
+    };
+    let mut frame_responses = json_response("The frame metadata.", "ClockFrame");
+    frame_responses["404"] = json!({"$ref": "#/components/responses/NodeError"});
+    let filter_description = "The frame filter: \"ceremony-application\", \"master-clock\" or 32 \
+        hex encoded bytes.";
+    let amount = json!({
+        "type": "string",
+        "description": "An amount of oblivious transfer units, as hex unless the server was \
+            started with another amount format."
+    });
+
+    let mut document = json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Quilibrium node API",
+            "description": "A REST/JSON proxy of the gRPC API of a Quilibrium node.",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": {
+            "/frames": {"get": {
+                "summary": "Get the metadata of a range of frames.",
+                "parameters": [
+                    {"name": "filter", "in": "query", "description": filter_description,
+                        "schema": {"type": "string", "default": "ceremony-application"}},
+                    {"name": "from", "in": "query", "description": "The first frame number, inclusive.",
+                        "schema": {"type": "integer", "format": "uint64", "default": 1}},
+                    {"name": "to", "in": "query",
+                        "description": "The last frame number, exclusive. Defaults to `from` + 10. \
+                            At most 100 frames can be requested at once.",
+                        "schema": {"type": "integer", "format": "uint64"}},
+                    {"name": "include_candidates", "in": "query",
+                        "schema": {"type": "boolean", "default": false}}
+                ],
+                "responses": json_response("The frames in the range.", "FramesResponse")
+            }},
+            "/frame/{filter}/{frame_number}": {"get": {
+                "summary": "Get the metadata of a frame.",
+                "parameters": [
+                    {"name": "filter", "in": "path", "required": true,
+                        "description": filter_description, "schema": {"type": "string"}},
+                    {"name": "frame_number", "in": "path", "required": true,
+                        "schema": {"type": "integer", "format": "uint64"}}
+                ],
+                "responses": frame_responses
+            }},
+            "/peers": {"get": {
+                "summary": "Get the broadcasted sync info of the peers.",
+                "responses": json_response("The peer info.", "PeerInfoResponse")
+            }},
+            "/network": {"get": {
+                "summary": "Get the peers from the peer store of the node.",
+                "responses": json_response("The network info.", "NetworkInfoResponse")
+            }},
+            "/manifests": {"get": {
+                "summary": "Get the self-reported peer manifests.",
+                "responses": json_response("The peer manifests.", "PeerManifestsResponse")
+            }},
+            "/token": {"get": {
+                "summary": "Get the token info of the node.",
+                "responses": json_response("The token info.", "TokenInfo")
+            }}
+        },
+        "components": {
+            "securitySchemes": {"bearer": {"type": "http", "scheme": "bearer"}},
+            "responses": {
+                "NodeError": {
+                    "description": "The request failed, see the error message.",
+                    "content": {"application/json": {"schema": {
+                        "type": "object",
+                        "properties": {"error": {"type": "string"}},
+                        "required": ["error"]
+                    }}}
+                }
+            },
+            "schemas": {
+                "ClockFrame": {
+                    "type": "object",
+                    "properties": {
+                        "filter": {"description": "\"CeremonyApplication\", \"MasterClock\" or {\"Unknown\": [bytes]}."},
+                        "frame_number": {"type": "integer", "format": "uint64"},
+                        "timestamp": {"type": "integer", "description": "Unix timestamp in milliseconds."},
+                        "difficulty": {"type": "integer", "format": "uint32"}
+                    }
+                },
+                "FramesResponse": {
+                    "type": "object",
+                    "properties": {
+                        "truncated_clock_frames": {"type": "array", "items": {"$ref": "#/components/schemas/ClockFrame"}}
+                    }
+                },
+                "PeerInfoResponse": {
+                    "type": "object",
+                    "properties": {
+                        "peers": {"type": "array", "items": {"type": "object"}},
+                        "uncooperative_peers": {"type": "array", "items": {"type": "object"}}
+                    }
+                },
+                "NetworkInfoResponse": {
+                    "type": "object",
+                    "properties": {
+                        "network_info": {"type": "array", "items": {"type": "object"}}
+                    }
+                },
+                "PeerManifestsResponse": {
+                    "type": "object",
+                    "properties": {
+                        "peer_manifests": {"type": "array", "items": {"type": "object"}}
+                    }
+                },
+                "TokenInfo": {
+                    "type": "object",
+                    "properties": {
+                        "confirmed_token_supply": amount,
+                        "unconfirmed_token_supply": amount,
+                        "owned_tokens": amount
+                    }
+                }
+            }
+        }
+    });
+    if bearer_auth {
+        document["security"] = json!([{"bearer": []}]);
+    }
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use axum::body::Body;
+    use axum::http::Request;
+    use quilibrium::test_utils::FakeNode;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn options() -> ApiOptions {
+        ApiOptions {
+            cache_ttl: Duration::from_secs(60),
+            cors_origins: vec![],
+            bearer_token: None,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    async fn get(app: &Router, path: &str) -> Result<(StatusCode, serde_json::Value)> {
+        let response = app
+            .clone()
+            .oneshot(Request::get(path).body(Body::empty())?)
+            .await?;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let json = if body.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_slice(&body)?
+        };
+        Ok((status, json))
+    }
+
+    #[tokio::test]
+    async fn responses() -> Result<()> {
+        let uri = FakeNode::new()
+            .with_head_frames(Some(20), None)
+            .with_token_info(8_000_000_000, 8_000_000_000, 1)
+            .serve()
+            .await;
+        let app = router(uri, options())?;
+
+        let (status, token) = get(&app, "/token").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(token["owned_tokens"], "0x1");
+
+        let (status, frames) = get(&app, "/frames?from=5&to=8").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            frames["truncated_clock_frames"].as_array().unwrap().len(),
+            3
+        );
+
+        let (status, frame) = get(&app, "/frame/ceremony-application/20").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(frame["frame_number"], 20);
+
+        let (status, error) = get(&app, "/frame/ceremony-application/21").await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error["error"], "The frame was not found");
+
+        for path in [
+            "/frames?filter=bogus",
+            "/frame/bogus/1",
+            "/frames?from=0&to=1000",
+        ] {
+            let (status, error) = get(&app, path).await?;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{path}");
+            assert!(error["error"].is_string());
+        }
+
+        let (status, openapi) = get(&app, "/openapi.json").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(openapi["openapi"], "3.0.3");
+        assert!(openapi["paths"]["/frames"].is_object());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn node_errors() -> Result<()> {
+        let failing = router(FakeNode::new().failing().serve().await, options())?;
+        let (status, error) = get(&failing, "/peers").await?;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert!(error["error"].is_string());
+
+        let slow = FakeNode::new()
+            .with_delay(Duration::from_secs(10))
+            .serve()
+            .await;
+        let options = ApiOptions {
+            timeout: Duration::from_millis(100),
+            ..options()
+        };
+        let (status, error) = get(&router(slow, options)?, "/peers").await?;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(error["error"], "The node timed out");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn connect_timeout() -> Result<()> {
+        // A listener that never accepts, with its backlog filled, so that connects to it hang.
+        let socket = tokio::net::TcpSocket::new_v4()?;
+        socket.bind("127.0.0.1:0".parse()?)?;
+        let listener = socket.listen(0)?;
+        let address = listener.local_addr()?;
+        let _queued = tokio::net::TcpStream::connect(address).await?;
+        let uri = format!("http://{address}").parse()?;
+        let options = ApiOptions {
+            timeout: Duration::from_millis(200),
+            ..options()
+        };
+        let app = router(uri, options)?;
+
+        let requests = async { tokio::join!(get(&app, "/peers"), get(&app, "/token")) };
+        let (peers, token) = tokio::time::timeout(Duration::from_secs(5), requests).await?;
+        for (status, error) in [peers?, token?] {
+            assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+            assert_eq!(error["error"], "The node timed out");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cache() -> Result<()> {
+        let node = FakeNode::new().with_head_frames(Some(20), None);
+        let calls = node.calls();
+        let options = ApiOptions {
+            cache_ttl: Duration::from_millis(300),
+            ..options()
+        };
+        let app = router(node.serve().await, options)?;
+
+        get(&app, "/network").await?;
+        get(&app, "/network").await?;
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        // Unknown parameters and explicit defaults share the cache entry.
+        get(&app, "/frames?from=1&x=1").await?;
+        get(&app, "/frames?x=2").await?;
+        get(&app, "/frames?from=1&to=11&include_candidates=false").await?;
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        get(&app, "/network").await?;
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bearer_auth_and_cors() -> Result<()> {
+        let options = ApiOptions {
+            cors_origins: vec!["https://example.com".to_string()],
+            bearer_token: Some("secret".to_string()),
+            ..options()
+        };
+        let app = router(FakeNode::new().serve().await, options)?;
+
+        let (status, _) = get(&app, "/token").await?;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, openapi) = get(&app, "/openapi.json").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(openapi["security"][0]["bearer"], serde_json::json!([]));
+
+        let authorized = Request::get("/token")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .body(Body::empty())?;
+        let response = app.clone().oneshot(authorized).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Browsers send the preflight without the token.
+        let preflight = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/token")
+            .header(header::ORIGIN, "https://example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+            .body(Body::empty())?;
+        let response = app.clone().oneshot(preflight).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://example.com"
+        );
+        assert!(response.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()?
+            .contains("authorization"));
+        Ok(())
+    }
+}
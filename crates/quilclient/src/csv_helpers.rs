@@ -1,4 +1,20 @@
-use quilibrium::node::{ClockFrame, NetworkInfo, PeerId, PeerInfo};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use quilibrium::capacity::{Capacity, CapacityEstimate};
+use quilibrium::manifest_stats::{MetricStats, PeerPerformance};
+use quilibrium::multiaddrs::Transport;
+use quilibrium::node::{ClockFrame, NetworkInfo, PeerId, PeerInfo, PeerManifest, TokenInfo};
+use quilibrium::peer_directory::PeerRecord;
+use quilibrium::peer_scores::{ScoreStats, ScoreTrend};
+use quilibrium::probe::{PeerReachability, ProbeOutcome, ProbeResult};
+use quilibrium::rewards::RewardReport;
+use quilibrium::snapshot::SnapshotDiff;
+use quilibrium::supply::SupplyGrowth;
+use quilibrium::sync_status::SyncStatus;
+use quilibrium::units::{ByteSize, HumanDuration};
+use quilibrium::version_distribution::VersionDistribution;
+use quilibrium::ObliviousTransferUnits;
 
 pub fn clock_frames_to_rows(
     clock_frames: impl IntoIterator<Item = ClockFrame>,
@@ -23,11 +39,15 @@ pub fn clock_frames_to_rows(
         .collect()
 }
 
-/// Flatten peer info into a list of peer info rows where each row has a single multiaddr.
-pub fn peer_infos_to_rows(peer_infos: impl IntoIterator<Item = PeerInfo>) -> Vec<PeerInfoRow> {
+/// Flatten peer infos into a row per multiaddr with the age at `age_at` and cooperation, if set.
+pub fn peer_infos_to_rows(
+    peer_infos: impl IntoIterator<Item = (PeerInfo, Option<bool>)>,
+    age_at: Option<DateTime<Utc>>,
+) -> Vec<PeerInfoRow> {
     peer_infos
         .into_iter()
-        .flat_map(|peer_info| {
+        .flat_map(|(peer_info, cooperative)| {
+            let age_secs = age_at.map(|now| peer_info.age(now).num_seconds());
             let PeerInfo {
                 peer_id,
                 multiaddrs,
@@ -40,8 +60,10 @@ pub fn peer_infos_to_rows(peer_infos: impl IntoIterator<Item = PeerInfo>) -> Vec
                 peer_id,
                 multiaddr,
                 max_frame,
-                version: display_version(&version),
+                version: version.to_string(),
                 timestamp: timestamp.to_string(),
+                cooperative,
+                age_secs,
             })
         })
         .collect()
@@ -68,6 +90,396 @@ pub fn network_infos_to_rows(
         .collect()
 }
 
+/// Flatten sync status into a row per frame filter. The catch up time is only estimated if an
+/// earlier sync status is provided.
+pub fn sync_status_to_rows(
+    sync_status: &SyncStatus,
+    earlier: Option<&SyncStatus>,
+) -> Vec<SyncStatusRow> {
+    sync_status
+        .filters
+        .iter()
+        .map(|status| SyncStatusRow {
+            filter: status.filter.to_string(),
+            local_head: status.local_head,
+            network_max: status.network_max,
+            network_median: status.network_median,
+            reporting_peers: status.reporting_peers,
+            lag: status.lag(),
+            catch_up_estimate: earlier
+                .and_then(|earlier| sync_status.estimate_catch_up(earlier, &status.filter))
+                .map(|estimate| {
+                    // Sub-second precision is just noise for an estimate.
+                    let estimate = std::time::Duration::from_secs(estimate.as_secs());
+                    humantime::format_duration(estimate).to_string()
+                }),
+        })
+        .collect()
+}
+
+/// Flatten a version distribution into a row per version where the shares are percentages.
+pub fn version_distribution_to_rows(distribution: VersionDistribution) -> Vec<VersionShareRow> {
+    distribution
+        .versions
+        .into_iter()
+        .map(|share| VersionShareRow {
+            version: share.version.to_string(),
+            cooperative_peers: share.cooperative_peers,
+            uncooperative_peers: share.uncooperative_peers,
+            weight: share.weight,
+            percentage: share.share * 100.0,
+            at_least_percentage: share.cumulative_share * 100.0,
+        })
+        .collect()
+}
+
+/// Flatten peer records into a row per peer where the multiaddrs are joined by spaces.
+pub fn peer_records_to_rows(records: impl IntoIterator<Item = PeerRecord>) -> Vec<PeerRecordRow> {
+    records
+        .into_iter()
+        .map(|record| PeerRecordRow {
+            peer_id: record.peer_id,
+            peer_score: record.peer_score(),
+            cooperative: record.cooperative,
+            version: record.version().map(|version| version.to_string()),
+            max_frame: record.max_frame(),
+            timestamp: record.timestamp().map(|timestamp| timestamp.to_string()),
+            cores: record.manifest.as_ref().map(|manifest| manifest.cores),
+            memory: record.manifest.as_ref().map(|manifest| manifest.memory),
+            storage: record.manifest.as_ref().map(|manifest| manifest.storage),
+            master_head_frame: record
+                .manifest
+                .as_ref()
+                .map(|manifest| manifest.master_head_frame),
+            multiaddrs: record
+                .multiaddrs()
+                .iter()
+                .map(|multiaddr| multiaddr.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+        .collect()
+}
+
+/// Flatten a snapshot diff into a row per added or removed peer and a row per changed field.
+pub fn snapshot_diff_to_rows(diff: SnapshotDiff) -> Vec<SnapshotDiffRow> {
+    let added = diff.added.into_iter().map(|peer_id| SnapshotDiffRow {
+        peer_id,
+        change: "added".to_string(),
+        field: None,
+        before: None,
+        after: None,
+    });
+    let removed = diff.removed.into_iter().map(|peer_id| SnapshotDiffRow {
+        peer_id,
+        change: "removed".to_string(),
+        field: None,
+        before: None,
+        after: None,
+    });
+    let changed = diff.changed.into_iter().flat_map(|peer_change| {
+        let peer_id = peer_change.peer_id;
+        peer_change
+            .changes
+            .into_iter()
+            .map(move |field_change| SnapshotDiffRow {
+                peer_id,
+                change: "changed".to_string(),
+                field: Some(field_change.field),
+                before: field_change.before,
+                after: field_change.after,
+            })
+    });
+    added.chain(removed).chain(changed).collect()
+}
+
+/// Flatten score statistics into a row per statistic.
+pub fn score_stats_to_rows(stats: &ScoreStats) -> Vec<ScoreStatRow> {
+    let mut rows = vec![
+        ScoreStatRow::new("peers", Some(stats.peers as f64)),
+        ScoreStatRow::new("min", stats.min),
+        ScoreStatRow::new("max", stats.max),
+        ScoreStatRow::new("mean", stats.mean),
+    ];
+    rows.extend(
+        stats
+            .percentiles
+            .iter()
+            .map(|&(p, score)| ScoreStatRow::new(&format!("p{p}"), Some(score))),
+    );
+    rows.push(ScoreStatRow::new(
+        "penalized",
+        Some(stats.penalized.len() as f64),
+    ));
+    rows
+}
+
+/// Flatten score trends into a row per peer with the first and last samples.
+pub fn score_trends_to_rows(
+    trends: impl IntoIterator<Item = ScoreTrend>,
+    threshold: f64,
+) -> Vec<ScoreTrendRow> {
+    trends
+        .into_iter()
+        .map(|trend| ScoreTrendRow {
+            peer_id: trend.peer_id,
+            samples: trend.samples.len(),
+            first_at: trend.first().map(|sample| sample.captured_at.to_string()),
+            first_score: trend.first().map(|sample| sample.score),
+            last_at: trend.last().map(|sample| sample.captured_at.to_string()),
+            last_score: trend.last().map(|sample| sample.score),
+            change: trend.change(),
+            change_per_hour: trend.change_per_hour(),
+            crossed_below_threshold: trend.crossed_below(threshold),
+        })
+        .collect()
+}
+
+/// Flatten the metric statistics of a manifest summary into a row per metric with a column per
+/// percentile.
+pub fn metric_stats_to_rows(metrics: impl IntoIterator<Item = MetricStats>) -> Vec<MetricStatsRow> {
+    metrics
+        .into_iter()
+        .map(|stats| {
+            let percentile = |p: f64| {
+                stats
+                    .percentiles
+                    .iter()
+                    .find(|(percentile, _)| *percentile == p)
+                    .map(|(_, value)| *value)
+            };
+            MetricStatsRow {
+                metric: stats.metric.to_string(),
+                peers: stats.peers,
+                min: stats.min,
+                p10: percentile(10.0),
+                p25: percentile(25.0),
+                p50: percentile(50.0),
+                p75: percentile(75.0),
+                p90: percentile(90.0),
+                max: stats.max,
+                mean: stats.mean,
+            }
+        })
+        .collect()
+}
+
+/// Flatten the peers of a manifest summary into a row per peer where the anomalies are joined.
+pub fn peer_performances_to_rows<'a>(
+    peers: impl IntoIterator<Item = &'a PeerPerformance>,
+) -> Vec<PeerPerformanceRow> {
+    peers
+        .into_iter()
+        .map(|peer| PeerPerformanceRow {
+            peer_id: peer.peer_id,
+            performance_index: peer.performance_index,
+            anomalies: peer
+                .anomalies
+                .iter()
+                .map(|anomaly| anomaly.to_string())
+                .collect::<Vec<_>>()
+                .join("; "),
+        })
+        .collect()
+}
+
+/// Convert peer manifests to rows where the timings and sizes are human readable strings, e.g.
+/// "340 ms" and "1.2 TiB".
+pub fn peer_manifests_to_human_rows(
+    peer_manifests: impl IntoIterator<Item = PeerManifest>,
+) -> Vec<PeerManifestHumanRow> {
+    peer_manifests
+        .into_iter()
+        .map(|manifest| {
+            let timing = |duration: Option<Duration>, millis: i64| {
+                duration.map_or_else(
+                    || format!("{millis} ms"),
+                    |duration| HumanDuration(duration).to_string(),
+                )
+            };
+            PeerManifestHumanRow {
+                peer_id: manifest.peer_id,
+                difficulty: manifest.difficulty,
+                difficulty_metric: timing(
+                    manifest.difficulty_duration(),
+                    manifest.difficulty_metric,
+                ),
+                commit_16_metric: timing(manifest.commit_16_duration(), manifest.commit_16_metric),
+                commit_128_metric: timing(
+                    manifest.commit_128_duration(),
+                    manifest.commit_128_metric,
+                ),
+                commit_1024_metric: timing(
+                    manifest.commit_1024_duration(),
+                    manifest.commit_1024_metric,
+                ),
+                commit_65536_metric: timing(
+                    manifest.commit_65536_duration(),
+                    manifest.commit_65536_metric,
+                ),
+                proof_16_metric: timing(manifest.proof_16_duration(), manifest.proof_16_metric),
+                proof_128_metric: timing(manifest.proof_128_duration(), manifest.proof_128_metric),
+                proof_1024_metric: timing(
+                    manifest.proof_1024_duration(),
+                    manifest.proof_1024_metric,
+                ),
+                proof_65536_metric: timing(
+                    manifest.proof_65536_duration(),
+                    manifest.proof_65536_metric,
+                ),
+                cores: manifest.cores,
+                memory: manifest.memory_size().to_string(),
+                storage: manifest.storage_size().to_string(),
+                master_head_frame: manifest.master_head_frame,
+            }
+        })
+        .collect()
+}
+
+/// Flatten a capacity estimate into a total row followed by a row per protocol version. Memory and
+/// storage are in bytes unless `human` is set.
+pub fn capacity_estimate_to_rows(estimate: CapacityEstimate, human: bool) -> Vec<CapacityRow> {
+    let row = |version: String, capacity: Capacity| {
+        let size = |bytes: u64| {
+            if human {
+                ByteSize(bytes).to_string()
+            } else {
+                bytes.to_string()
+            }
+        };
+        CapacityRow {
+            version,
+            peers: capacity.peers,
+            cores: capacity.cores,
+            memory: size(capacity.memory),
+            storage: size(capacity.storage),
+        }
+    };
+    let total = row("total".to_string(), estimate.total);
+    let by_version = estimate.by_version.into_iter().map(|version| {
+        let name = version
+            .version
+            .map_or_else(|| "unknown".to_string(), |version| version.to_string());
+        row(name, version.capacity)
+    });
+    std::iter::once(total).chain(by_version).collect()
+}
+
+/// Convert token info to a row where the amounts are formatted with `format`.
+pub fn token_info_to_row(
+    token_info: &TokenInfo,
+    format: impl Fn(ObliviousTransferUnits) -> String,
+) -> TokenInfoRow {
+    TokenInfoRow {
+        confirmed_token_supply: format(token_info.confirmed_token_supply),
+        unconfirmed_token_supply: format(token_info.unconfirmed_token_supply),
+        owned_tokens: format(token_info.owned_tokens),
+    }
+}
+
+/// Flatten probe results into a row per multiaddr with the peer score reported by the node.
+pub fn probe_results_to_rows(
+    results: impl IntoIterator<Item = ProbeResult>,
+    peer_score: impl Fn(&PeerId) -> Option<f64>,
+) -> Vec<ProbeResultRow> {
+    results
+        .into_iter()
+        .map(|result| {
+            let (status, error) = match result.outcome {
+                ProbeOutcome::Reachable => ("reachable", None),
+                ProbeOutcome::Unreachable(error) => ("unreachable", Some(error)),
+                ProbeOutcome::Timeout => ("timeout", None),
+                ProbeOutcome::Unsupported => ("unsupported", None),
+            };
+            ProbeResultRow {
+                peer_score: peer_score(&result.peer_id),
+                peer_id: result.peer_id,
+                multiaddr: result.multiaddr,
+                transport: display_transport(result.transport).to_string(),
+                status: status.to_string(),
+                latency_ms: result.latency.map(|latency| latency.as_secs_f64() * 1000.0),
+                error,
+            }
+        })
+        .collect()
+}
+
+/// Aggregate probe results into a row per peer with the peer score reported by the node.
+pub fn peer_reachability_to_rows(
+    results: &[ProbeResult],
+    peer_score: impl Fn(&PeerId) -> Option<f64>,
+) -> Vec<PeerReachabilityRow> {
+    PeerReachability::from_results(results)
+        .into_iter()
+        .map(|peer| PeerReachabilityRow {
+            peer_score: peer_score(&peer.peer_id),
+            reachability: peer.score(),
+            peer_id: peer.peer_id,
+            probed: peer.probed,
+            reachable: peer.reachable,
+            min_latency_ms: peer
+                .min_latency
+                .map(|latency| latency.as_secs_f64() * 1000.0),
+        })
+        .collect()
+}
+
+/// Convert the reward reports of time windows to rows with the amounts in QUIL.
+pub fn reward_reports_to_rows(
+    reports: impl IntoIterator<Item = (Duration, RewardReport)>,
+) -> Vec<RewardReportRow> {
+    let format = |amount: Option<ObliviousTransferUnits>| amount.map(|amount| amount.to_string());
+    reports
+        .into_iter()
+        .map(|(window, report)| RewardReportRow {
+            window: humantime::format_duration(window).to_string(),
+            first_sample_at: report.first_sample_at.to_rfc3339(),
+            last_sample_at: report.last_sample_at.to_rfc3339(),
+            samples: report.samples,
+            earned: report.earned.to_string(),
+            earned_per_hour: format(report.earned_per_hour()),
+            projected_daily: format(report.projected_daily()),
+            projected_monthly: format(report.projected_monthly()),
+            supply_growth: report.supply_growth.to_string(),
+            supply_share: report.supply_share(),
+            gaps: report.gaps.len(),
+            offline_secs: report.offline_duration().as_secs(),
+        })
+        .collect()
+}
+
+/// Convert supply growth between samples to rows with the amounts in QUIL and the supply and
+/// pending amounts of the later sample.
+pub fn supply_growth_to_rows(
+    growth: impl IntoIterator<Item = SupplyGrowth>,
+) -> Vec<SupplyGrowthRow> {
+    growth
+        .into_iter()
+        .map(|growth| SupplyGrowthRow {
+            from: growth.from.sampled_at.to_rfc3339(),
+            to: growth.to.sampled_at.to_rfc3339(),
+            from_frame: growth.from.frame_number,
+            to_frame: growth.to.frame_number,
+            frames: growth.frames(),
+            confirmed_token_supply: growth.to.confirmed_token_supply.to_string(),
+            issued: growth.issued().to_string(),
+            issued_per_frame: growth.issued_per_frame().map(|amount| amount.to_string()),
+            issued_per_day: growth.issued_per_day().map(|amount| amount.to_string()),
+            annualized_inflation: growth.annualized_inflation(),
+            pending: growth.to.pending().to_string(),
+        })
+        .collect()
+}
+
+fn display_transport(transport: Transport) -> &'static str {
+    match transport {
+        Transport::Tcp => "tcp",
+        Transport::Udp => "udp",
+        Transport::Quic => "quic",
+        Transport::Other => "other",
+    }
+}
+
 /// Clock frame where the filter and the timestamp are human readable strings.
 /// Useful for CSV output.
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -96,8 +508,213 @@ pub struct PeerInfoRow {
     pub max_frame: u64,
     pub version: String,
     pub timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooperative: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_secs: Option<i64>,
+}
+
+/// Sync status for a frame filter where the filter and the catch up estimate are human readable
+/// strings. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SyncStatusRow {
+    pub filter: String,
+    pub local_head: Option<u64>,
+    pub network_max: Option<u64>,
+    pub network_median: Option<u64>,
+    pub reporting_peers: usize,
+    pub lag: Option<u64>,
+    pub catch_up_estimate: Option<String>,
+}
+
+/// Version share where the shares are percentages and the percentage of the peers running at least
+/// the version is included. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VersionShareRow {
+    pub version: String,
+    pub cooperative_peers: usize,
+    pub uncooperative_peers: usize,
+    pub weight: f64,
+    pub percentage: f64,
+    pub at_least_percentage: f64,
+}
+
+/// Peer record where the data from all sources is in a single row. Columns are empty if the source
+/// doesn't have data about the peer. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerRecordRow {
+    pub peer_id: PeerId,
+    pub peer_score: Option<f64>,
+    pub cooperative: Option<bool>,
+    pub version: Option<String>,
+    pub max_frame: Option<u64>,
+    pub timestamp: Option<String>,
+    pub cores: Option<u32>,
+    pub memory: Option<u64>,
+    pub storage: Option<u64>,
+    pub master_head_frame: Option<u64>,
+    pub multiaddrs: String,
+}
+
+/// A peer that was added or removed or a field of a peer that changed between two snapshots.
+/// Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotDiffRow {
+    pub peer_id: PeerId,
+    pub change: String,
+    pub field: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// The result of probing a multiaddr where the latency is in milliseconds. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProbeResultRow {
+    pub peer_id: PeerId,
+    pub multiaddr: multiaddr::Multiaddr,
+    pub transport: String,
+    pub status: String,
+    pub latency_ms: Option<f64>,
+    pub error: Option<String>,
+    pub peer_score: Option<f64>,
+}
+
+/// The reachability of a peer next to the peer score reported by the node where the latency is in
+/// milliseconds. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerReachabilityRow {
+    pub peer_id: PeerId,
+    pub probed: usize,
+    pub reachable: usize,
+    pub reachability: f64,
+    pub min_latency_ms: Option<f64>,
+    pub peer_score: Option<f64>,
 }
 
-fn display_version(version: &[u8; 3]) -> String {
-    format!("{}.{}.{}", version[0], version[1], version[2])
+/// A statistic of the peer scores. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScoreStatRow {
+    pub statistic: String,
+    pub value: Option<f64>,
+}
+
+impl ScoreStatRow {
+    fn new(statistic: &str, value: Option<f64>) -> Self {
+        Self {
+            statistic: statistic.to_string(),
+            value,
+        }
+    }
+}
+
+/// The score trend of a peer where only the first and last samples are included and the times are
+/// human readable strings. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScoreTrendRow {
+    pub peer_id: PeerId,
+    pub samples: usize,
+    pub first_at: Option<String>,
+    pub first_score: Option<f64>,
+    pub last_at: Option<String>,
+    pub last_score: Option<f64>,
+    pub change: Option<f64>,
+    pub change_per_hour: Option<f64>,
+    pub crossed_below_threshold: bool,
+}
+
+/// Statistics of a peer manifest metric with a column per percentile. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MetricStatsRow {
+    pub metric: String,
+    pub peers: usize,
+    pub min: Option<f64>,
+    pub p10: Option<f64>,
+    pub p25: Option<f64>,
+    pub p50: Option<f64>,
+    pub p75: Option<f64>,
+    pub p90: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+}
+
+/// The performance index of a peer and its implausible self-reports joined by semicolons. Useful
+/// for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerPerformanceRow {
+    pub peer_id: PeerId,
+    pub performance_index: Option<f64>,
+    pub anomalies: String,
+}
+
+/// Peer manifest where the timings and sizes are human readable strings. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PeerManifestHumanRow {
+    pub peer_id: PeerId,
+    pub difficulty: u32,
+    pub difficulty_metric: String,
+    pub commit_16_metric: String,
+    pub commit_128_metric: String,
+    pub commit_1024_metric: String,
+    pub commit_65536_metric: String,
+    pub proof_16_metric: String,
+    pub proof_128_metric: String,
+    pub proof_1024_metric: String,
+    pub proof_65536_metric: String,
+    pub cores: u32,
+    pub memory: String,
+    pub storage: String,
+    pub master_head_frame: u64,
+}
+
+/// The hardware capacity of all peers or the peers running a protocol version. Useful for CSV
+/// output.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CapacityRow {
+    pub version: String,
+    pub peers: usize,
+    pub cores: u64,
+    pub memory: String,
+    pub storage: String,
+}
+
+/// Token info where the amounts are formatted strings. Useful for CSV and JSON output.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TokenInfoRow {
+    pub confirmed_token_supply: String,
+    pub unconfirmed_token_supply: String,
+    pub owned_tokens: String,
+}
+
+/// The rewards earned in a time window where the amounts are QUIL strings. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RewardReportRow {
+    pub window: String,
+    pub first_sample_at: String,
+    pub last_sample_at: String,
+    pub samples: usize,
+    pub earned: String,
+    pub earned_per_hour: Option<String>,
+    pub projected_daily: Option<String>,
+    pub projected_monthly: Option<String>,
+    pub supply_growth: String,
+    pub supply_share: Option<f64>,
+    pub gaps: usize,
+    pub offline_secs: u64,
+}
+
+/// The supply growth between two samples where the times and amounts are strings. Useful for CSV
+/// output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SupplyGrowthRow {
+    pub from: String,
+    pub to: String,
+    pub from_frame: Option<u64>,
+    pub to_frame: Option<u64>,
+    pub frames: Option<u64>,
+    pub confirmed_token_supply: String,
+    pub issued: String,
+    pub issued_per_frame: Option<String>,
+    pub issued_per_day: Option<String>,
+    pub annualized_inflation: Option<f64>,
+    pub pending: String,
 }
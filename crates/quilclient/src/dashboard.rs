@@ -0,0 +1,694 @@
+use std::io::Stdout;
+use std::time::Duration;
+
+use anyhow::Result;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Sparkline, Table, TableState};
+use ratatui::{Frame, Terminal};
+use tokio::sync::mpsc;
+use tonic::transport::Uri;
+
+use quilibrium::node::{FrameFilter, NodeClient, PeerId};
+use quilibrium::overview::{NodeOverview, OverviewOptions};
+use quilibrium::peer_directory::PeerRecord;
+use quilibrium::units::{ByteSize, HumanDuration};
+
+/// The number of peers in the top and bottom scored peer lists.
+const PEER_ROWS: usize = 10;
+
+/// The peer list with the keyboard focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerList {
+    Top,
+    Bottom,
+}
+
+/// What to do after a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Refresh,
+    Quit,
+}
+
+/// The state of the dashboard.
+pub struct App {
+    node_uri: Uri,
+    overview: Option<NodeOverview>,
+    error: Option<String>,
+    focus: PeerList,
+    selected: Option<PeerId>,
+    detail: bool,
+}
+
+impl App {
+    pub fn new(node_uri: Uri) -> Self {
+        Self {
+            node_uri,
+            overview: None,
+            error: None,
+            focus: PeerList::Top,
+            selected: None,
+            detail: false,
+        }
+    }
+
+    /// Show a freshly collected overview, keeping the selected peer if it's still listed. Otherwise
+    /// the first peer is selected and the detail pane is closed.
+    pub fn update(&mut self, overview: Result<NodeOverview, String>) {
+        match overview {
+            Ok(overview) => {
+                self.overview = Some(overview);
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error),
+        }
+        if self.selected_index().is_none() {
+            self.detail = false;
+            self.select(0);
+        }
+    }
+
+    /// Handle a key press.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.kind != KeyEventKind::Press {
+            return Action::None;
+        }
+        match key.code {
+            KeyCode::Char('q') => return Action::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Action::Quit
+            }
+            KeyCode::Char('r') => return Action::Refresh,
+            KeyCode::Esc if self.detail => self.detail = false,
+            KeyCode::Esc => return Action::Quit,
+            KeyCode::Enter if !self.focused_peers().is_empty() => self.detail = !self.detail,
+            _ if self.detail => {}
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+                self.focus = match self.focus {
+                    PeerList::Top => PeerList::Bottom,
+                    PeerList::Bottom => PeerList::Top,
+                };
+                if self.selected_index().is_none() {
+                    self.select(0);
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let index = self.selected_index().unwrap_or_default();
+                self.select(index.saturating_sub(1));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let index = self.selected_index().unwrap_or_default();
+                self.select(index + 1);
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    /// The peers with the highest scores, highest first.
+    fn top_peers(&self) -> Vec<&PeerRecord> {
+        let Some(overview) = &self.overview else {
+            return vec![];
+        };
+        overview
+            .scored_peers()
+            .into_iter()
+            .take(PEER_ROWS)
+            .collect()
+    }
+
+    /// The peers with the lowest scores, lowest first.
+    fn bottom_peers(&self) -> Vec<&PeerRecord> {
+        let Some(overview) = &self.overview else {
+            return vec![];
+        };
+        overview
+            .scored_peers()
+            .into_iter()
+            .rev()
+            .take(PEER_ROWS)
+            .collect()
+    }
+
+    fn focused_peers(&self) -> Vec<&PeerRecord> {
+        match self.focus {
+            PeerList::Top => self.top_peers(),
+            PeerList::Bottom => self.bottom_peers(),
+        }
+    }
+
+    /// The position of the selected peer in the focused list.
+    fn selected_index(&self) -> Option<usize> {
+        let selected = self.selected?;
+        self.focused_peers()
+            .iter()
+            .position(|record| record.peer_id == selected)
+    }
+
+    /// Select the peer at a position in the focused list, or the last one if the list is shorter.
+    fn select(&mut self, index: usize) {
+        let peers = self.focused_peers();
+        self.selected = peers
+            .get(index)
+            .or(peers.last())
+            .map(|record| record.peer_id);
+    }
+
+    /// The peer to show the details of.
+    fn selected_peer(&self) -> Option<&PeerRecord> {
+        let index = self.selected_index()?;
+        self.focused_peers().get(index).copied()
+    }
+}
+
+/// Restores the terminal when dropped, also on errors and panics.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::terminal::disable_raw_mode();
+        let _ = crossterm::execute!(std::io::stdout(), LeaveAlternateScreen);
+    }
+}
+
+/// Run the dashboard for the node at `uri` until the user quits, collecting a new overview every
+/// `refresh_interval`.
+pub async fn run(uri: Uri, refresh_interval: Duration, options: OverviewOptions) -> Result<()> {
+    crossterm::terminal::enable_raw_mode()?;
+    let _guard = TerminalGuard;
+    crossterm::execute!(std::io::stdout(), EnterAlternateScreen)?;
+    let mut terminal: Terminal<CrosstermBackend<Stdout>> =
+        Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
+
+    // Read the terminal events on a thread as reading them blocks.
+    let (event_sender, mut events) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = crossterm::event::read() {
+            if event_sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (refresh_sender, mut refresh_requests) = mpsc::channel::<()>(1);
+    let (overview_sender, mut overviews) = mpsc::channel(1);
+    let refresh_uri = uri.clone();
+    tokio::spawn(async move {
+        let mut client: Option<NodeClient> = None;
+        loop {
+            let overview = async {
+                if client.is_none() {
+                    client = Some(NodeClient::new(refresh_uri.clone()).await?);
+                }
+                let client = client.as_mut().expect("connected above");
+                Ok::<_, anyhow::Error>(NodeOverview::collect(client, &options).await)
+            }
+            .await
+            .map_err(|error| format!("{error:#}"));
+            if overview_sender.send(overview).await.is_err() {
+                break;
+            }
+            let _ = tokio::time::timeout(refresh_interval, refresh_requests.recv()).await;
+        }
+    });
+
+    let mut app = App::new(uri);
+    loop {
+        terminal.draw(|frame| render(frame, &app))?;
+        tokio::select! {
+            Some(event) = events.recv() => {
+                if let Event::Key(key) = event {
+                    match app.handle_key(key) {
+                        Action::Quit => break,
+                        Action::Refresh => {
+                            let _ = refresh_sender.try_send(());
+                        }
+                        Action::None => {}
+                    }
+                }
+            }
+            Some(overview) = overviews.recv() => app.update(overview),
+            else => break,
+        }
+    }
+    Ok(())
+}
+
+/// Draw the dashboard.
+pub fn render(frame: &mut Frame, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(5),
+            Constraint::Length(6),
+            Constraint::Min(6),
+            Constraint::Length(PEER_ROWS as u16 + 3),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+    let summary = halves(rows[1]);
+    let tables = halves(rows[3]);
+    let peers = halves(rows[4]);
+
+    frame.render_widget(Paragraph::new(status_line(app)), rows[0]);
+    render_head_frames(frame, app, summary[0]);
+    render_token(frame, app, summary[1]);
+    render_frame_intervals(frame, app, rows[2]);
+    render_versions(frame, app, tables[0]);
+    render_manifests(frame, app, tables[1]);
+    render_peers(frame, app, PeerList::Top, peers[0]);
+    render_peers(frame, app, PeerList::Bottom, peers[1]);
+    frame.render_widget(
+        Paragraph::new("q quit  r refresh  tab switch list  ↑/↓ select  enter peer details"),
+        rows[5],
+    );
+
+    if app.detail {
+        if let Some(peer) = app.selected_peer() {
+            render_peer_detail(frame, peer);
+        }
+    }
+}
+
+fn halves(area: Rect) -> std::rc::Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area)
+}
+
+fn block(title: &str) -> Block<'_> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+fn status_line(app: &App) -> String {
+    let mut status = format!("Node {}", app.node_uri);
+    match &app.overview {
+        Some(overview) => {
+            let updated = overview.metrics.collected_at.format("%H:%M:%S");
+            status.push_str(&format!(" | updated {updated} UTC"));
+            let methods: Vec<&str> = overview.failed_calls().map(|call| call.method).collect();
+            if !methods.is_empty() {
+                status.push_str(&format!(" | failed: {}", methods.join(", ")));
+            }
+        }
+        None if app.error.is_none() => status.push_str(" | loading..."),
+        None => {}
+    }
+    if let Some(error) = &app.error {
+        status.push_str(&format!(" | error: {error}"));
+    }
+    status
+}
+
+fn render_head_frames(frame: &mut Frame, app: &App, area: Rect) {
+    let lines: Vec<Line> = [FrameFilter::CeremonyApplication, FrameFilter::MasterClock]
+        .iter()
+        .map(|filter| {
+            let head = app
+                .overview
+                .as_ref()
+                .and_then(|overview| overview.head_frame(filter).map(|head| (overview, head)));
+            let value = match head {
+                Some((overview, head)) => {
+                    let age = (overview.metrics.collected_at - head.timestamp)
+                        .to_std()
+                        .unwrap_or_default();
+                    format!("{} ({} ago)", head.frame_number, HumanDuration(age))
+                }
+                None => "-".to_string(),
+            };
+            Line::from(format!("{filter}: {value}"))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).block(block("Head frames")), area);
+}
+
+fn render_token(frame: &mut Frame, app: &App, area: Rect) {
+    let token_info = app
+        .overview
+        .as_ref()
+        .and_then(|overview| overview.metrics.token_info.as_ref());
+    let lines = match token_info {
+        Some(token_info) => vec![
+            Line::from(format!("Balance: {:.4} QUIL", token_info.owned_tokens)),
+            Line::from(format!(
+                "Confirmed supply: {:.0} QUIL",
+                token_info.confirmed_token_supply
+            )),
+            Line::from(format!(
+                "Unconfirmed supply: {:.0} QUIL",
+                token_info.unconfirmed_token_supply
+            )),
+        ],
+        None => vec![Line::from("-")],
+    };
+    frame.render_widget(Paragraph::new(lines).block(block("Token")), area);
+}
+
+fn render_frame_intervals(frame: &mut Frame, app: &App, area: Rect) {
+    let intervals = app
+        .overview
+        .as_ref()
+        .map(NodeOverview::frame_intervals)
+        .unwrap_or_default();
+    let title = match intervals.last() {
+        Some(last) => {
+            let mean = intervals.iter().sum::<Duration>() / intervals.len() as u32;
+            format!(
+                "Ceremony frame intervals (last {}, mean {})",
+                HumanDuration(*last),
+                HumanDuration(mean)
+            )
+        }
+        None => "Ceremony frame intervals".to_string(),
+    };
+    let data: Vec<u64> = intervals
+        .iter()
+        .map(|interval| interval.as_millis() as u64)
+        .collect();
+    frame.render_widget(Sparkline::default().block(block(&title)).data(&data), area);
+}
+
+fn render_versions(frame: &mut Frame, app: &App, area: Rect) {
+    let versions = app
+        .overview
+        .as_ref()
+        .and_then(|overview| overview.metrics.versions.as_ref());
+    let rows = versions
+        .map(|versions| {
+            versions
+                .versions
+                .iter()
+                .map(|share| {
+                    Row::new([
+                        share.version.to_string(),
+                        share.cooperative_peers.to_string(),
+                        share.uncooperative_peers.to_string(),
+                        format!("{:.1}%", share.share * 100.0),
+                    ])
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let table = Table::new(rows)
+        .header(header_row([
+            "Version",
+            "Cooperative",
+            "Uncooperative",
+            "Share",
+        ]))
+        .widths(&[
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Length(14),
+            Constraint::Length(7),
+        ])
+        .block(block("Peers by version"));
+    frame.render_widget(table, area);
+}
+
+fn render_manifests(frame: &mut Frame, app: &App, area: Rect) {
+    let manifests = app
+        .overview
+        .as_ref()
+        .and_then(|overview| overview.manifests.as_ref());
+    let format = |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{value:.1}"));
+    let rows = manifests
+        .map(|manifests| {
+            manifests
+                .metrics
+                .iter()
+                .map(|stats| {
+                    Row::new([
+                        stats.metric.name().to_string(),
+                        stats.peers.to_string(),
+                        format(stats.min),
+                        format(stats.median()),
+                        format(stats.max),
+                    ])
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let title = match manifests {
+        Some(manifests) => format!("Peer manifests ({} peers)", manifests.peers.len()),
+        None => "Peer manifests".to_string(),
+    };
+    let table = Table::new(rows)
+        .header(header_row(["Metric", "Peers", "Min", "Median", "Max"]))
+        .widths(&[
+            Constraint::Length(20),
+            Constraint::Length(6),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+        ])
+        .block(block(&title));
+    frame.render_widget(table, area);
+}
+
+fn render_peers(frame: &mut Frame, app: &App, list: PeerList, area: Rect) {
+    let (peers, title) = match list {
+        PeerList::Top => (app.top_peers(), "Top scored peers"),
+        PeerList::Bottom => (app.bottom_peers(), "Bottom scored peers"),
+    };
+    let rows: Vec<Row> = peers
+        .iter()
+        .map(|peer| {
+            Row::new([
+                short_peer_id(peer),
+                peer.peer_score()
+                    .map_or("-".to_string(), |score| format!("{score:.1}")),
+                peer.version()
+                    .map_or("-".to_string(), |version| version.to_string()),
+            ])
+        })
+        .collect();
+    let focused = app.focus == list;
+    let table = Table::new(rows)
+        .header(header_row(["Peer ID", "Score", "Version"]))
+        .widths(&[
+            Constraint::Min(16),
+            Constraint::Length(10),
+            Constraint::Length(10),
+        ])
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(block(title).border_style(if focused {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        }));
+    let mut state = TableState::default();
+    if focused && !peers.is_empty() {
+        state.select(app.selected_index());
+    }
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn render_peer_detail(frame: &mut Frame, peer: &PeerRecord) {
+    let area = centered(frame.size(), 80, 60);
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let mut lines = vec![
+        Line::from(format!("Peer ID: {}", peer.peer_id)),
+        Line::from(format!(
+            "Score: {}",
+            optional(peer.peer_score().map(|score| score.to_string()))
+        )),
+        Line::from(format!(
+            "Cooperative: {}",
+            optional(peer.cooperative.map(|cooperative| cooperative.to_string()))
+        )),
+        Line::from(format!(
+            "Version: {}",
+            optional(peer.version().map(|version| version.to_string()))
+        )),
+        Line::from(format!(
+            "Max frame: {}",
+            optional(peer.max_frame().map(|frame| frame.to_string()))
+        )),
+        Line::from(format!(
+            "Sync info timestamp: {}",
+            optional(peer.timestamp().map(|timestamp| timestamp.to_rfc3339()))
+        )),
+    ];
+    if let Some(manifest) = &peer.manifest {
+        lines.push(Line::from(format!(
+            "Hardware: {} cores, {} memory, {} storage",
+            manifest.cores,
+            ByteSize(manifest.memory),
+            ByteSize(manifest.storage)
+        )));
+        lines.push(Line::from(format!(
+            "Master head frame: {}",
+            manifest.master_head_frame
+        )));
+    }
+    lines.push(Line::from("Multiaddrs:"));
+    lines.extend(
+        peer.multiaddrs()
+            .into_iter()
+            .map(|multiaddr| Line::from(format!("  {multiaddr}"))),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(block("Peer details (esc to close)")),
+        area,
+    );
+}
+
+fn header_row<const N: usize>(titles: [&'static str; N]) -> Row<'static> {
+    Row::new(titles.map(Cell::from)).style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+fn short_peer_id(peer: &PeerRecord) -> String {
+    let peer_id = peer.peer_id.to_string();
+    match peer_id.char_indices().nth_back(7) {
+        Some((index, _)) if peer_id.len() > 16 => {
+            format!("{}…{}", &peer_id[..8], &peer_id[index..])
+        }
+        _ => peer_id,
+    }
+}
+
+/// A rectangle with the given percentage of the width and height in the middle of `area`.
+fn centered(area: Rect, width_percent: u16, height_percent: u16) -> Rect {
+    let width = area.width * width_percent / 100;
+    let height = area.height * height_percent / 100;
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use crossterm::event::KeyEvent;
+    use quilibrium::metrics::NodeMetrics;
+    use quilibrium::node::{NetworkInfo, NetworkInfoResponse};
+    use quilibrium::peer_directory::PeerDirectory;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+
+    fn peer_id(seed: u8) -> PeerId {
+        // An identity multihash of a protobuf encoded ed25519 public key.
+        let mut bytes = vec![0x00, 0x24, 0x08, 0x01, 0x12, 0x20];
+        bytes.extend([seed; 32]);
+        PeerId::from_bytes(&bytes).unwrap()
+    }
+
+    fn overview(scores: &[f64]) -> NodeOverview {
+        let network_info = scores
+            .iter()
+            .enumerate()
+            .map(|(index, score)| NetworkInfo {
+                peer_id: peer_id(index as u8),
+                multiaddrs: vec![],
+                peer_score: *score,
+            })
+            .collect();
+        NodeOverview {
+            metrics: NodeMetrics {
+                collected_at: Utc::now(),
+                token_info: None,
+                head_frames: vec![],
+                peers: None,
+                versions: None,
+                scores: None,
+                sync_status: None,
+                rpc: vec![],
+            },
+            recent_frames: vec![],
+            peers: PeerDirectory::new().with_network_info(NetworkInfoResponse { network_info }),
+            manifests: None,
+        }
+    }
+
+    fn draw(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 50)).unwrap();
+        terminal.draw(|frame| render(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Action {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn navigation() {
+        let mut app = App::new("http://127.0.0.1:8337".parse().unwrap());
+        assert!(draw(&app).contains("loading..."));
+
+        app.update(Ok(overview(&[5.0, -3.0, 12.0])));
+        let screen = draw(&app);
+        assert!(screen.contains("Top scored peers"));
+        assert!(screen.contains("12.0"));
+        assert!(!screen.contains("Peer details"));
+
+        assert_eq!(press(&mut app, KeyCode::Down), Action::None);
+        assert_eq!(app.selected_peer().unwrap().peer_score(), Some(5.0));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.selected_peer().unwrap().peer_score(), Some(5.0));
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.selected_peer().unwrap().peer_score(), Some(-3.0));
+
+        press(&mut app, KeyCode::Enter);
+        let screen = draw(&app);
+        assert!(screen.contains("Peer details"));
+        assert!(screen.contains(&format!("Peer ID: {}", peer_id(1))));
+        press(&mut app, KeyCode::Esc);
+        assert!(!draw(&app).contains("Peer details"));
+
+        assert_eq!(press(&mut app, KeyCode::Char('r')), Action::Refresh);
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Action::Quit);
+    }
+
+    #[test]
+    fn refresh_keeps_selected_peer() {
+        let mut app = App::new("http://127.0.0.1:8337".parse().unwrap());
+        app.update(Ok(overview(&[5.0, -3.0, 12.0])));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.selected_peer().unwrap().peer_id, peer_id(0));
+
+        // The selected peer moves to the top.
+        app.update(Ok(overview(&[20.0, -3.0, 12.0])));
+        assert!(app.detail);
+        assert_eq!(app.selected_peer().unwrap().peer_id, peer_id(0));
+        assert_eq!(app.selected_index(), Some(0));
+
+        // The selected peer is gone.
+        app.update(Ok(overview(&[])));
+        assert!(!app.detail);
+        assert!(app.selected_peer().is_none());
+    }
+
+    #[test]
+    fn failed_refresh_keeps_overview() {
+        let mut app = App::new("http://127.0.0.1:8337".parse().unwrap());
+        app.update(Ok(overview(&[1.0])));
+        app.update(Err("transport error".to_string()));
+        let screen = draw(&app);
+        assert!(screen.contains("error: transport error"));
+        assert!(screen.contains("1.0"));
+    }
+}
@@ -0,0 +1,206 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tonic::transport::Uri;
+
+use quilibrium::metrics::{MetricsExport, NodeMetrics};
+use quilibrium::node::NodeClient;
+use quilibrium::peer_scores::ScoreOptions;
+
+/// The content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// A node to export the metrics of, labeled with `node="<name>"`.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub name: String,
+    pub uri: Uri,
+}
+
+/// Parse a target from `[NAME=]URI`. The name defaults to the URI.
+pub fn parse_target(s: &str) -> Result<Target> {
+    let (name, uri) = match s.split_once('=') {
+        Some((name, uri)) => (name.to_string(), uri),
+        None => (s.to_string(), s),
+    };
+    Ok(Target {
+        name,
+        uri: uri.parse()?,
+    })
+}
+
+struct Exporter {
+    targets: Vec<(Target, Mutex<Option<NodeClient>>)>,
+    timeout: Duration,
+    score_options: ScoreOptions,
+}
+
+impl Exporter {
+    /// Collect the metrics of a target, connecting to it first if it isn't connected yet. A failed
+    /// or timed out connect is recorded as a failed "connect" call. Calls that don't finish within
+    /// the rest of the timeout are recorded as timed out and the finished ones are kept.
+    async fn collect(&self, index: usize) -> NodeMetrics {
+        let (target, client) = &self.targets[index];
+        let started = Instant::now();
+        let connect = async {
+            let mut client = client.lock().await;
+            if client.is_none() {
+                *client = Some(NodeClient::new(target.uri.clone()).await?);
+            }
+            Ok::<_, anyhow::Error>(client.clone().expect("connected above"))
+        };
+        let mut client = match tokio::time::timeout(self.timeout, connect).await {
+            Ok(Ok(client)) => client,
+            Ok(Err(error)) => {
+                return NodeMetrics::unreachable(started.elapsed(), format!("{error:#}"))
+            }
+            Err(_) => return NodeMetrics::unreachable(started.elapsed(), "timed out".to_string()),
+        };
+        let remaining = self.timeout.saturating_sub(started.elapsed());
+        NodeMetrics::collect_within(&mut client, &self.score_options, remaining).await
+    }
+}
+
+/// Serve the metrics of the targets at `/metrics` until the process is stopped. The metrics are
+/// collected from all targets concurrently on every scrape.
+pub async fn serve(
+    listen: SocketAddr,
+    targets: Vec<Target>,
+    timeout: Duration,
+    score_options: ScoreOptions,
+) -> Result<()> {
+    axum::Server::bind(&listen)
+        .serve(router(targets, timeout, score_options).into_make_service())
+        .await?;
+    Ok(())
+}
+
+fn router(targets: Vec<Target>, timeout: Duration, score_options: ScoreOptions) -> Router {
+    let exporter = Arc::new(Exporter {
+        targets: targets
+            .into_iter()
+            .map(|target| (target, Mutex::new(None)))
+            .collect(),
+        timeout,
+        score_options,
+    });
+    Router::new()
+        .route("/metrics", get(metrics))
+        .with_state(exporter)
+}
+
+async fn metrics(State(exporter): State<Arc<Exporter>>) -> impl IntoResponse {
+    let mut collections = JoinSet::new();
+    for index in 0..exporter.targets.len() {
+        let exporter = exporter.clone();
+        collections.spawn(async move { (index, exporter.collect(index).await) });
+    }
+    let mut collected = Vec::with_capacity(exporter.targets.len());
+    while let Some(result) = collections.join_next().await {
+        if let Ok(result) = result {
+            collected.push(result);
+        }
+    }
+    collected.sort_by_key(|(index, _)| *index);
+
+    let export = collected
+        .iter()
+        .fold(MetricsExport::new(), |export, (index, metrics)| {
+            let name = exporter.targets[*index].0.name.as_str();
+            export.with_node(&[("node", name)], metrics)
+        });
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        export.to_prometheus_text(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use quilibrium::test_utils::FakeNode;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn scrape(targets: Vec<Target>, timeout: Duration) -> Result<String> {
+        let response = router(targets, timeout, ScoreOptions::new())
+            .oneshot(Request::get("/metrics").body(Body::empty())?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            PROMETHEUS_CONTENT_TYPE
+        );
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok(String::from_utf8(body.to_vec())?)
+    }
+
+    #[test]
+    fn parse_targets() -> Result<()> {
+        let named = parse_target("node-1=http://1.2.3.4:5678")?;
+        assert_eq!(named.name, "node-1");
+        assert_eq!(named.uri, "http://1.2.3.4:5678");
+
+        let unnamed = parse_target("http://1.2.3.4:5678")?;
+        assert_eq!(unnamed.name, "http://1.2.3.4:5678");
+
+        assert!(parse_target("node-1=not a uri").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn metrics_of_each_target() -> Result<()> {
+        let up = FakeNode::new()
+            .with_token_info(8_000_000_000, 8_000_000_000, 4_000_000_000)
+            .serve()
+            .await;
+        // Answers the token info in time, but not the head frame searches after it.
+        let slow = FakeNode::new()
+            .with_token_info(8_000_000_000, 8_000_000_000, 8_000_000_000)
+            .with_delay(Duration::from_millis(300))
+            .serve()
+            .await;
+        let targets = vec![
+            Target {
+                name: "up".to_string(),
+                uri: up,
+            },
+            Target {
+                name: "slow".to_string(),
+                uri: slow,
+            },
+        ];
+
+        let text = scrape(targets, Duration::from_millis(500)).await?;
+        assert!(text.contains("quilibrium_up{node=\"up\"} 1\n"), "{text}");
+        assert!(text.contains("quilibrium_owned_tokens_quil{node=\"up\"} 0.5\n"));
+        assert!(text.contains("quilibrium_up{node=\"slow\"} 1\n"), "{text}");
+        assert!(text.contains("quilibrium_owned_tokens_quil{node=\"slow\"} 1\n"));
+        assert!(text.contains("quilibrium_rpc_success{node=\"slow\",method=\"token_info\"} 1\n"));
+        assert!(
+            text.contains("quilibrium_rpc_success{node=\"slow\",method=\"peer_manifests\"} 0\n")
+        );
+        assert!(!text.contains("method=\"connect\""));
+
+        let unreachable = Target {
+            name: "unreachable".to_string(),
+            uri: "http://127.0.0.1:1".parse()?,
+        };
+        let text = scrape(vec![unreachable], Duration::from_millis(500)).await?;
+        assert!(
+            text.contains("quilibrium_rpc_success{node=\"unreachable\",method=\"connect\"} 0\n")
+        );
+        Ok(())
+    }
+}
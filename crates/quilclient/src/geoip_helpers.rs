@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+
+use quilibrium::geoip::{GeoInfo, GeoIpDatabase, GeoIpError, GeoSummary};
+
+/// Options to add the location and network operator of peer addresses to the output.
+#[derive(Debug, Args)]
+pub struct GeoIpOpts {
+    /// Local MaxMind DB file, e.g. GeoLite2 City or GeoLite2 ASN, to add the country, city, ASN and
+    /// organization columns. Can be repeated to combine databases. No network lookups are made.
+    #[arg(long)]
+    pub geoip_db: Vec<PathBuf>,
+}
+
+/// Write the rows to stdout as CSV with the GeoIP columns appended if any database is provided.
+pub async fn write_csv_with_geo_info<R: Serialize + Send + 'static>(
+    rows: Vec<R>,
+    geoip_opts: GeoIpOpts,
+    lookup: impl Fn(&GeoIpDatabase, &R) -> Result<Option<GeoInfo>, GeoIpError>,
+) -> Result<()> {
+    if geoip_opts.geoip_db.is_empty() {
+        return crate::write_csv_to_stdout(rows).await;
+    }
+
+    let database = GeoIpDatabase::open(&geoip_opts.geoip_db)?;
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            let geo_info = lookup(&database, &row)?.unwrap_or_default();
+            Ok((row, geo_info))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    crate::write_csv_to_stdout(rows).await
+}
+
+/// Flatten a GeoIP summary into a row per country and a row per autonomous system where the shares
+/// are percentages.
+pub fn geo_summary_to_rows(summary: GeoSummary) -> Vec<GeoGroupRow> {
+    let countries = summary
+        .countries
+        .into_iter()
+        .map(|group| ("country", group));
+    let autonomous_systems = summary
+        .autonomous_systems
+        .into_iter()
+        .map(|group| ("asn", group));
+    countries
+        .chain(autonomous_systems)
+        .map(|(kind, group)| GeoGroupRow {
+            group: kind.to_string(),
+            key: group.key,
+            name: group.name,
+            peers: group.peers,
+            percentage: group.share * 100.0,
+        })
+        .collect()
+}
+
+/// The peers in a country or an autonomous system. Useful for CSV output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GeoGroupRow {
+    pub group: String,
+    pub key: Option<String>,
+    pub name: Option<String>,
+    pub peers: usize,
+    pub percentage: f64,
+}
@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Options to configure the logs written to stderr.
+#[derive(Debug, Args)]
+pub struct LogOpts {
+    /// The log level, e.g. "debug", or filter directives, e.g. "warn,quilibrium=debug". Node client
+    /// calls are logged with their duration at the debug level.
+    #[arg(
+        long,
+        short('v'),
+        global = true,
+        env = "QUILCLIENT_LOG",
+        default_value = "warn"
+    )]
+    pub log_level: String,
+    /// The format of the logs.
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+    /// Export the spans that pass the log level to this OpenTelemetry collector over OTLP/gRPC,
+    /// e.g. <http://localhost:4317>.
+    #[cfg(feature = "opentelemetry")]
+    #[arg(long, global = true, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum LogFormat {
+    /// Human readable lines.
+    Text,
+    /// A JSON object per line.
+    Json,
+}
+
+/// Flushes the exported spans when dropped.
+pub struct LogGuard {
+    #[cfg(feature = "opentelemetry")]
+    opentelemetry: bool,
+}
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "opentelemetry")]
+        if self.opentelemetry {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Install the global tracing subscriber. Must be called from within the Tokio runtime if spans
+/// are exported to OpenTelemetry.
+pub fn init(opts: &LogOpts) -> Result<LogGuard> {
+    let filter = EnvFilter::try_new(&opts.log_level)
+        .with_context(|| format!("Invalid log level: {}", opts.log_level))?;
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_span_events(FmtSpan::CLOSE);
+    let fmt_layer = match opts.log_format {
+        LogFormat::Text => fmt_layer.boxed(),
+        LogFormat::Json => fmt_layer.json().boxed(),
+    };
+    let registry = tracing_subscriber::registry().with(filter).with(fmt_layer);
+
+    #[cfg(feature = "opentelemetry")]
+    {
+        let opentelemetry_layer = opts
+            .otlp_endpoint
+            .as_deref()
+            .map(opentelemetry_layer)
+            .transpose()?;
+        let opentelemetry = opentelemetry_layer.is_some();
+        registry.with(opentelemetry_layer).try_init()?;
+        Ok(LogGuard { opentelemetry })
+    }
+    #[cfg(not(feature = "opentelemetry"))]
+    {
+        registry.try_init()?;
+        Ok(LogGuard {})
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+fn opentelemetry_layer<S>(endpoint: &str) -> Result<impl Layer<S>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(opentelemetry_sdk::trace::config().with_resource(
+            opentelemetry_sdk::Resource::new([KeyValue::new("service.name", "quilclient")]),
+        ))
+        .install_batch(opentelemetry_sdk::runtime::Tokio)
+        .context("Failed to set up the OpenTelemetry exporter")?;
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
@@ -1,14 +1,62 @@
+mod api;
 mod csv_helpers;
+mod dashboard;
+mod exporter;
+#[cfg(feature = "geoip")]
+mod geoip_helpers;
+mod logging;
 
-use anyhow::Result;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use clap::error::ErrorKind;
 use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use prost::Message;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
+use tokio::io::AsyncWriteExt;
 use tonic::transport::Uri;
 
-use crate::csv_helpers::{clock_frames_to_rows, network_infos_to_rows, peer_infos_to_rows};
-use quilibrium::node::{FrameFilter, FramesOptions, NodeClient, PeerInfo};
+use crate::api::ApiOptions;
+use crate::csv_helpers::{
+    capacity_estimate_to_rows, clock_frames_to_rows, metric_stats_to_rows, network_infos_to_rows,
+    peer_infos_to_rows, peer_manifests_to_human_rows, peer_performances_to_rows,
+    peer_reachability_to_rows, peer_records_to_rows, probe_results_to_rows, reward_reports_to_rows,
+    score_stats_to_rows, score_trends_to_rows, snapshot_diff_to_rows, supply_growth_to_rows,
+    sync_status_to_rows, token_info_to_row, version_distribution_to_rows,
+};
+use crate::exporter::{parse_target, Target};
+#[cfg(feature = "geoip")]
+use crate::geoip_helpers::{geo_summary_to_rows, write_csv_with_geo_info, GeoIpOpts};
+use crate::logging::LogOpts;
+#[cfg(feature = "geoip")]
+use multiaddr::Multiaddr;
+use quilibrium::capacity::CapacityEstimate;
+use quilibrium::freshness::{Freshness, FreshnessPolicy};
+#[cfg(feature = "geoip")]
+use quilibrium::geoip::{GeoIpDatabase, GeoSummary};
+use quilibrium::graph::{Observer, PeerGraph};
+use quilibrium::health::{HealthOptions, HealthReport, HealthStatus, Threshold};
+use quilibrium::manifest_stats::ManifestSummary;
+use quilibrium::metrics::NodeMetrics;
+use quilibrium::multiaddrs::{AddressFamily, MultiaddrFilter, Transport};
+use quilibrium::node::{
+    FrameFilter, FramesOptions, NetworkInfo, NodeClient, PeerId, PeerInfo, TokenInfo,
+};
+use quilibrium::oblivious_transfer_units::AmountFormat;
+use quilibrium::overview::OverviewOptions;
+use quilibrium::peer_scores::{ScoreOptions, ScoreStats, ScoreTrend};
+use quilibrium::probe::{probe, ProbeOptions};
+use quilibrium::rewards::{RewardReport, RewardSample};
+use quilibrium::snapshot::NetworkSnapshot;
+use quilibrium::supply::{SupplySample, SupplyTracker};
+use quilibrium::version_distribution::{HardwareWeight, VersionDistribution};
+use quilibrium::ObliviousTransferUnits;
 
 /// Quilibrium CLI client.
 #[derive(Debug, Parser)]
@@ -30,11 +78,67 @@ struct GlobalOpts {
     /// <https://github.com/quilibriumnetwork/ceremonyclient#experimental--grpcrest-support>
     #[clap(long, short('u'), global = true, env = QUILCLIENT_NODE_URI)]
     node_uri: Option<Uri>,
+
+    #[clap(flatten)]
+    log_opts: LogOpts,
 }
 
 /// Quilibrium CLI client commands.
 #[derive(Debug, Subcommand)]
 enum Command {
+    /// Sum the cores, memory and storage in the peer manifests and print the totals and the
+    /// breakdown by protocol version to stdout as CSV.
+    Capacity {
+        /// Only count the peers that broadcasted their peer info within this time, e.g. "30m".
+        #[arg(long, short, value_parser = humantime::parse_duration)]
+        seen_within: Option<Duration>,
+        /// Print memory and storage in human readable units, e.g. "1.2 TiB", instead of bytes.
+        #[arg(long)]
+        human: bool,
+    },
+    /// Check the health of the node and print a one line summary with perfdata in the Nagios
+    /// plugin format to stdout. Exits with 0 (OK), 1 (WARNING), 2 (CRITICAL) or 3 (UNKNOWN).
+    ///
+    /// Thresholds are given as "WARNING,CRITICAL" where either can be left empty, e.g. ",100". A
+    /// check is skipped if both are empty.
+    Check {
+        /// The maximum number of ceremony application frames the node may be behind its peers.
+        #[arg(long, default_value = "10,100", value_parser = parse_threshold::<u64>)]
+        max_sync_lag: Threshold<u64>,
+        /// The minimum number of cooperative peers.
+        #[arg(long, default_value = "8,1", value_parser = parse_threshold::<usize>)]
+        min_cooperative_peers: Threshold<usize>,
+        /// The maximum age of the ceremony application head frame, e.g. "5m,15m".
+        #[arg(long, default_value = "5m,15m", value_parser = parse_duration_threshold)]
+        max_frame_age: Threshold<Duration>,
+        /// The maximum duration of a single request call to the node, e.g. "5s,20s". Head frame
+        /// searches are left out as they take multiple requests.
+        #[arg(long, default_value = "5s,20s", value_parser = parse_duration_threshold)]
+        max_rpc_latency: Threshold<Duration>,
+        /// The window within which the token balance must increase, e.g. "1h,6h". The balance is
+        /// compared to the history file. Skipped by default.
+        #[arg(long, default_value = ",", value_parser = parse_duration_threshold)]
+        balance_window: Threshold<Duration>,
+        /// The balance history file, e.g. the one written by `rewards track`. The current balance
+        /// is appended to it if the balance window is set, so running the check periodically
+        /// builds the history.
+        #[arg(long, short = 'f', default_value = "./rewards.jsonl")]
+        history_file: PathBuf,
+        /// Report UNKNOWN if the check takes longer than this, e.g. "30s".
+        #[arg(long, default_value = "30s", value_parser = humantime::parse_duration)]
+        timeout: Duration,
+    },
+    /// Monitor the node in a terminal dashboard with its head frames, frame intervals, token
+    /// balance, peers by version, top and bottom scored peers and peer manifest statistics. Press
+    /// enter to show the details of the selected peer and q to quit.
+    Dashboard {
+        /// How often to refresh the dashboard, e.g. "10s".
+        #[arg(long, short, default_value = "10s", value_parser = humantime::parse_duration)]
+        refresh_interval: Duration,
+        /// The number of recent ceremony application frames to show the intervals of.
+        #[arg(long, default_value = "60")]
+        recent_frames: u64,
+    },
     /// Download a frame as a protobuffer and save it to disk.
     DownloadFrame {
         /// The frame filter.
@@ -48,6 +152,24 @@ enum Command {
         #[arg(long, short)]
         out_file_path: Option<String>,
     },
+    /// Serve the token balance and supply, head frames, peer counts, version distribution, peer
+    /// score statistics, sync lag and call latencies of one or more nodes at `/metrics` in the
+    /// Prometheus text format. Runs until interrupted.
+    Exporter {
+        /// The address to listen on.
+        #[arg(long, short, default_value = "0.0.0.0:9500")]
+        listen: SocketAddr,
+        /// A node to export the metrics of as `[NAME=]URI`, labeled with `node="NAME"`. Can be
+        /// repeated. Defaults to the node URI.
+        #[arg(long, short, value_parser = parse_target)]
+        target: Vec<Target>,
+        /// How long to wait for the metrics of a node on each scrape, e.g. "10s".
+        #[arg(long, default_value = "10s", value_parser = humantime::parse_duration)]
+        timeout: Duration,
+        /// Peers with a score below this are counted as penalized.
+        #[arg(long, default_value = "0")]
+        score_threshold: f64,
+    },
     /// Fetch frame metadata from the node and print it to stdout as CSV.
     Frames {
         /// The frame filter.
@@ -65,18 +187,397 @@ enum Command {
         include_candidates: bool,
     },
     /// Fetch the peers from the node's peer store and print them to stdout as CSV.
-    NetworkInfo,
+    NetworkInfo {
+        #[clap(flatten)]
+        multiaddr_opts: MultiaddrOpts,
+        #[cfg(feature = "geoip")]
+        #[clap(flatten)]
+        geoip_opts: GeoIpOpts,
+    },
     /// Fetch the broadcasted sync info that gets replicated through the network mesh and print it to stdout as CSV.
     PeerInfo {
         #[clap(value_enum, default_value_t=PeerType::Cooperative)]
         peer_type: PeerType,
+        /// Only print peers whose sync info is at most this old and not timestamped in the future
+        /// beyond the clock skew tolerance, e.g. "10m".
+        #[arg(long, value_parser = humantime::parse_duration)]
+        max_age: Option<Duration>,
+        /// How far in the future sync info may be timestamped before it's considered stale.
+        #[arg(long, default_value = "30s", value_parser = humantime::parse_duration)]
+        max_clock_skew: Duration,
+        /// Only print the peers whose sync info is stale instead of the ones that are live.
+        #[arg(long, requires = "max_age")]
+        stale: bool,
+        /// Add an `age_secs` column with the age of the sync info in seconds. Negative if it's
+        /// timestamped in the future.
+        #[arg(long)]
+        show_age: bool,
+        #[clap(flatten)]
+        multiaddr_opts: MultiaddrOpts,
+        #[cfg(feature = "geoip")]
+        #[clap(flatten)]
+        geoip_opts: GeoIpOpts,
+    },
+    /// Export the peers the node sees as a graph with the node at the center and print it to
+    /// stdout. Views of multiple nodes and snapshots are merged into one graph.
+    Graph {
+        /// The graph format.
+        #[arg(long, short)]
+        #[clap(value_enum, default_value_t=GraphFormat::Dot)]
+        format: GraphFormat,
+        /// The label of the node. Defaults to the node URI.
+        #[arg(long, short)]
+        label: Option<String>,
+        /// The libp2p peer ID of the node, so that it's merged with the peer when other nodes see
+        /// it.
+        #[arg(long, short)]
+        peer_id: Option<PeerId>,
+        /// The gRPC URI of another node whose view to add. Can be repeated.
+        #[arg(long, short)]
+        extra_node_uri: Vec<Uri>,
+        /// A snapshot saved with `snapshot save` whose view to add. Can be repeated.
+        #[arg(long, short)]
+        snapshot: Vec<PathBuf>,
     },
     /// Fetch the self-reported peer manifests that the node knows about and print them to stdout as CSV.
-    PeerManifests,
-    /// Fetch the token balance of the node and print it to stdout in QUIL units as an integer.
-    TokenBalance,
-    /// Fetch the confirmed token supply and print it to stdout in QUIL units as an integer.
-    TokenSupply,
+    PeerManifests {
+        /// Print the timings and sizes in human readable units, e.g. "340 ms" and "1.2 TiB",
+        /// instead of milliseconds and bytes.
+        #[arg(long)]
+        human: bool,
+    },
+    /// Compute statistics of the benchmarks in the peer manifests and flag implausible
+    /// self-reports, e.g. zero cores or a 65536 degree proof faster than a 16 degree one. Prints
+    /// the report to stdout as CSV.
+    ManifestsSummary {
+        /// The report to print.
+        #[clap(value_enum, default_value_t=ManifestReport::Metrics)]
+        report: ManifestReport,
+        /// Only print the peers with implausible self-reports in the peers report.
+        #[arg(long)]
+        anomalies_only: bool,
+    },
+    /// Fetch the network info, peer info and peer manifests, join them by peer ID and print them
+    /// to stdout as CSV.
+    Peers {
+        #[cfg(feature = "geoip")]
+        #[clap(flatten)]
+        geoip_opts: GeoIpOpts,
+    },
+    /// Print everything the node knows about a peer to stdout as JSON.
+    Peer {
+        /// The libp2p peer ID of the peer.
+        peer_id: PeerId,
+    },
+    /// Aggregate the peers per country and autonomous system from local MaxMind DB files and
+    /// print the summary to stdout as CSV.
+    #[cfg(feature = "geoip")]
+    GeoSummary {
+        /// Local MaxMind DB file, e.g. GeoLite2 City or GeoLite2 ASN. Can be repeated to combine
+        /// databases. No network lookups are made.
+        #[arg(long, required = true)]
+        geoip_db: Vec<PathBuf>,
+    },
+    /// Probe whether the multiaddrs of the peers the node knows about are reachable from this host
+    /// and print the latency of each multiaddr to stdout as CSV. TCP multiaddrs are probed by
+    /// connecting and QUIC multiaddrs by triggering version negotiation.
+    Probe {
+        /// How long to wait for a multiaddr to respond, e.g. "2s".
+        #[arg(long, short, default_value = "5s", value_parser = humantime::parse_duration)]
+        timeout: Duration,
+        /// The maximum number of multiaddrs to probe at the same time.
+        #[arg(long, short, default_value = "64")]
+        concurrency: usize,
+        /// Print the share of reachable multiaddrs per peer instead of a row per multiaddr.
+        #[arg(long)]
+        per_peer: bool,
+        #[clap(flatten)]
+        multiaddr_opts: MultiaddrOpts,
+    },
+    /// Track the tokens the node earns in a local history file and report the rewards.
+    Rewards {
+        #[clap(subcommand)]
+        command: RewardsCommand,
+    },
+    /// Analyze the scores the node assigns to the peers in its peer store and print the report to
+    /// stdout as CSV.
+    Scores {
+        /// The report to print. The trends report is computed from the snapshots and doesn't
+        /// require a node.
+        #[clap(value_enum, default_value_t=ScoreReport::Summary)]
+        report: ScoreReport,
+        /// Peers with a score below this are reported as penalized.
+        #[arg(long, short, default_value = "0", allow_hyphen_values = true)]
+        threshold: f64,
+        /// The width of the histogram buckets.
+        #[arg(long, short, default_value = "10")]
+        bucket_width: f64,
+        /// A snapshot saved with `snapshot save` to compute the trends from. Can be repeated.
+        #[arg(long, short)]
+        snapshot: Vec<PathBuf>,
+    },
+    /// Serve a REST/JSON API for the node at `/frames`, `/frame/{filter}/{frame_number}`,
+    /// `/peers`, `/network`, `/manifests` and `/token`, described by the OpenAPI document at
+    /// `/openapi.json`. Runs until interrupted.
+    Serve {
+        /// The address to listen on.
+        #[arg(long, short, default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+        /// How long to cache successful responses, e.g. "5s". Caching is disabled if it's zero.
+        #[arg(long, default_value = "5s", value_parser = humantime::parse_duration)]
+        cache_ttl: Duration,
+        /// An origin allowed to call the API from a browser, e.g. "https://example.com", or "*"
+        /// to allow any origin. Can be repeated. Cross-origin requests are rejected by default.
+        #[arg(long)]
+        cors_origin: Vec<String>,
+        /// Require an `Authorization: Bearer <TOKEN>` header with this token on the node routes.
+        #[arg(long, env = "QUILCLIENT_API_TOKEN", hide_env_values = true)]
+        bearer_token: Option<String>,
+        /// How long to wait for the node on each request, e.g. "30s".
+        #[arg(long, default_value = "30s", value_parser = humantime::parse_duration)]
+        timeout: Duration,
+        /// The format of token amounts in the responses.
+        #[arg(long, value_enum, default_value_t = AmountFormatOpt::Hex)]
+        amount_format: AmountFormatOpt,
+    },
+    /// Save snapshots of the peers the node knows about and compare them.
+    Snapshot {
+        #[clap(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Track the confirmed and unconfirmed token supply in a local history file and report the
+    /// issuance.
+    SupplyHistory {
+        #[clap(subcommand)]
+        command: SupplyHistoryCommand,
+    },
+    /// Compare the head frames of the node with the max frames reported by its peers and print the
+    /// sync status to stdout as CSV.
+    SyncStatus {
+        /// How long to wait between two samples to estimate the time it takes to catch up with
+        /// the network, e.g. "30s". No estimate is made if it's zero.
+        #[arg(long, short, default_value = "10s", value_parser = humantime::parse_duration)]
+        sample_interval: Duration,
+    },
+    /// Fetch the token balance of the node and print it to stdout in QUIL with full precision.
+    TokenBalance {
+        #[clap(flatten)]
+        token_opts: TokenOpts,
+    },
+    /// Fetch the confirmed token supply and print it to stdout in QUIL with full precision.
+    TokenSupply {
+        #[clap(flatten)]
+        token_opts: TokenOpts,
+    },
+    /// Count the cooperative and uncooperative peers per protocol version and print the
+    /// distribution to stdout as CSV.
+    Versions {
+        /// Weight the peers by the hardware reported in their peer manifests.
+        #[arg(long, short)]
+        #[clap(value_enum)]
+        weight: Option<HardwareWeightOpt>,
+    },
+}
+
+/// Options to filter the multiaddrs of peers. Duplicate multiaddrs are always removed.
+#[derive(Debug, Args)]
+struct MultiaddrOpts {
+    /// Only print public addresses.
+    #[arg(long)]
+    public_only: bool,
+    /// Only print addresses with this transport.
+    #[arg(long)]
+    #[clap(value_enum)]
+    transport: Option<TransportOpt>,
+    /// Only print addresses with this IP version.
+    #[arg(long)]
+    #[clap(value_enum)]
+    ip_version: Option<IpVersionOpt>,
+}
+
+impl From<MultiaddrOpts> for MultiaddrFilter {
+    fn from(opts: MultiaddrOpts) -> Self {
+        MultiaddrFilter::new()
+            .public_only(opts.public_only)
+            .transport(opts.transport.map(Into::into))
+            .family(opts.ip_version.map(Into::into))
+    }
+}
+
+/// Options to print token amounts.
+#[derive(Debug, Args)]
+struct TokenOpts {
+    /// The number of decimals to print QUIL amounts with, rounded down. Defaults to as many as
+    /// needed for the exact amount. Ignored for oblivious transfer units.
+    #[arg(long, short)]
+    precision: Option<usize>,
+    /// The unit to print amounts in.
+    #[arg(long)]
+    #[clap(value_enum, default_value_t=TokenUnitOpt::Quil)]
+    units: TokenUnitOpt,
+    /// Print the confirmed token supply, the unconfirmed token supply and the owned tokens
+    /// together.
+    #[arg(long, short)]
+    all: bool,
+    /// The output format with `--all`.
+    #[arg(long, short, requires = "all")]
+    #[clap(value_enum, default_value_t=TokenFormat::Csv)]
+    format: TokenFormat,
+}
+
+impl TokenOpts {
+    fn format(&self, amount: ObliviousTransferUnits) -> String {
+        match (&self.units, self.precision) {
+            (TokenUnitOpt::Otu, _) => amount.units().to_string(),
+            (TokenUnitOpt::Quil, None) => amount.to_string(),
+            (TokenUnitOpt::Quil, Some(precision)) => format!("{amount:.precision$}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum TokenUnitOpt {
+    /// QUIL tokens.
+    Quil,
+    /// Oblivious transfer units. One QUIL is 8 * 10^9 units.
+    Otu,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum AmountFormatOpt {
+    /// A hex string of oblivious transfer units, e.g. "0x2cb417800".
+    Hex,
+    /// A decimal string of oblivious transfer units, e.g. "12000000000".
+    Units,
+    /// A decimal string of QUIL, e.g. "1.500000000".
+    Quil,
+}
+
+impl From<AmountFormatOpt> for AmountFormat {
+    fn from(opt: AmountFormatOpt) -> Self {
+        match opt {
+            AmountFormatOpt::Hex => AmountFormat::Hex,
+            AmountFormatOpt::Units => AmountFormat::Units,
+            AmountFormatOpt::Quil => AmountFormat::Quil,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum TokenFormat {
+    /// A CSV table with a column per amount.
+    Csv,
+    /// A JSON object with a field per amount.
+    Json,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum IpVersionOpt {
+    #[value(name = "4")]
+    V4,
+    #[value(name = "6")]
+    V6,
+}
+
+impl From<IpVersionOpt> for AddressFamily {
+    fn from(opt: IpVersionOpt) -> Self {
+        match opt {
+            IpVersionOpt::V4 => AddressFamily::Ipv4,
+            IpVersionOpt::V6 => AddressFamily::Ipv6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum TransportOpt {
+    Tcp,
+    Udp,
+    Quic,
+}
+
+impl From<TransportOpt> for Transport {
+    fn from(opt: TransportOpt) -> Self {
+        match opt {
+            TransportOpt::Tcp => Transport::Tcp,
+            TransportOpt::Udp => Transport::Udp,
+            TransportOpt::Quic => Transport::Quic,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum SnapshotCommand {
+    /// Capture the network info, peer info and peer manifests of the node and save them to disk as
+    /// JSON.
+    Save {
+        /// The path to write the snapshot to. Defaults to `./snapshot-<timestamp>.json`.
+        #[arg(long, short)]
+        out_file_path: Option<String>,
+    },
+    /// Compare two snapshots and print the added, removed and changed peers to stdout as CSV.
+    /// Doesn't require a node.
+    Diff {
+        /// The path to the earlier snapshot.
+        from: PathBuf,
+        /// The path to the later snapshot.
+        to: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum RewardsCommand {
+    /// Sample the owned tokens and the confirmed token supply periodically and append the samples
+    /// to the history file as JSON lines. Runs until interrupted. Failed samples are reported to
+    /// stderr and show up as gaps in the history.
+    Track {
+        /// The history file to append the samples to.
+        #[arg(long, short = 'f', default_value = "./rewards.jsonl")]
+        history_file: PathBuf,
+        /// How long to wait between two samples, e.g. "5m".
+        #[arg(long, short, default_value = "5m", value_parser = humantime::parse_duration)]
+        interval: Duration,
+    },
+    /// Print the tokens earned, the earning rate, the projected daily and monthly rewards and the
+    /// share of the supply growth in time windows ending now to stdout as CSV. Doesn't require a
+    /// node.
+    Report {
+        /// The history file written by `rewards track`.
+        #[arg(long, short = 'f', default_value = "./rewards.jsonl")]
+        history_file: PathBuf,
+        /// A time window to report, e.g. "24h". Can be repeated.
+        #[arg(long, short, value_parser = humantime::parse_duration, default_values = ["1h", "1day", "7days"])]
+        window: Vec<Duration>,
+        /// Consecutive samples further apart than this are reported as gaps. Should be larger than
+        /// the sampling interval.
+        #[arg(long, short, default_value = "15m", value_parser = humantime::parse_duration)]
+        max_gap: Duration,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SupplyHistoryCommand {
+    /// Sample the token supply and the ceremony application head frame periodically and append the
+    /// samples to the history file as JSON lines. Runs until interrupted. Failed samples are
+    /// reported to stderr.
+    Track {
+        /// The history file to append the samples to.
+        #[arg(long, short = 'f', default_value = "./supply.jsonl")]
+        history_file: PathBuf,
+        /// How long to wait between two samples, e.g. "5m".
+        #[arg(long, short, default_value = "5m", value_parser = humantime::parse_duration)]
+        interval: Duration,
+    },
+    /// Print the tokens issued, the issuance per frame and per day, the annualized inflation and
+    /// the pending unconfirmed supply between consecutive samples to stdout as CSV. Doesn't
+    /// require a node.
+    Report {
+        /// The history file written by `supply-history track`.
+        #[arg(long, short = 'f', default_value = "./supply.jsonl")]
+        history_file: PathBuf,
+        /// Print a single row from the first to the latest sample.
+        #[arg(long, short)]
+        total: bool,
+    },
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -94,30 +595,136 @@ impl From<FrameFilterOpt> for FrameFilter {
     }
 }
 
+#[derive(Debug, Clone, ValueEnum)]
+pub enum HardwareWeightOpt {
+    Cores,
+    Memory,
+    Storage,
+}
+
+impl From<HardwareWeightOpt> for HardwareWeight {
+    fn from(opt: HardwareWeightOpt) -> Self {
+        match opt {
+            HardwareWeightOpt::Cores => HardwareWeight::Cores,
+            HardwareWeightOpt::Memory => HardwareWeight::Memory,
+            HardwareWeightOpt::Storage => HardwareWeight::Storage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT.
+    Dot,
+    /// GraphML.
+    Graphml,
+    /// The node-link JSON format used by NetworkX and D3.
+    Json,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum ManifestReport {
+    /// The percentiles of each metric. Timings from manifests with implausible self-reports are
+    /// left out.
+    Metrics,
+    /// The performance index relative to the median peer and the implausible self-reports of each
+    /// peer.
+    Peers,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum ScoreReport {
+    /// The number of peers, min, max, mean and percentiles of the scores.
+    Summary,
+    /// The number of peers per score range.
+    Histogram,
+    /// The peers with a score below the threshold.
+    Penalized,
+    /// The change of the score of each peer between snapshots.
+    Trends,
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 enum PeerType {
     Cooperative,
     Uncooperative,
+    /// Both cooperative and uncooperative peers with a `cooperative` column.
+    All,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let args = QuilClientArgs::parse();
-
-    // Hack to work around not being able to make global args required:
-    // https://github.com/clap-rs/clap/issues/1546
-    let node_uri = args.global_opts.node_uri.unwrap_or_else(|| {
-        let mut cmd = QuilClientArgs::command();
-        cmd.error(
-            ErrorKind::MissingRequiredArgument,
-            format!("The --node_uri argument or the {QUILCLIENT_NODE_URI} must be set"),
-        )
-        .exit();
-    });
-
-    let mut client = NodeClient::new(node_uri).await?;
+async fn main() -> Result<ExitCode> {
+    // Nagios treats any exit code other than 0-3 as a plugin failure, so `check` reports usage and
+    // setup errors as UNKNOWN too.
+    let args = match QuilClientArgs::try_parse() {
+        Ok(args) => args,
+        Err(error) if error.use_stderr() && is_check_command() => {
+            let rendered = error.render().to_string();
+            let message = rendered.lines().next().unwrap_or_default();
+            return Ok(check_unknown(message.trim_start_matches("error: ")));
+        }
+        Err(error) => error.exit(),
+    };
+    if matches!(args.command, Command::Check { .. }) {
+        return Ok(run(args)
+            .await
+            .unwrap_or_else(|error| check_unknown(format!("{error:#}"))));
+    }
+    run(args).await
+}
+
+async fn run(args: QuilClientArgs) -> Result<ExitCode> {
+    let _log_guard = logging::init(&args.global_opts.log_opts)?;
+
+    let mut client = LazyNodeClient::new(args.global_opts.node_uri);
 
     match args.command {
+        Command::Capacity { seen_within, human } => {
+            let seen_since = match seen_within {
+                Some(seen_within) => Some(
+                    Utc::now()
+                        .checked_sub_signed(chrono::Duration::from_std(seen_within)?)
+                        .context("--seen-within is too long")?,
+                ),
+                None => None,
+            };
+            let peer_manifests = client.get().await?.peer_manifests().await?;
+            let peer_info = client.get().await?.peer_info().await?;
+            let estimate = CapacityEstimate::new(&peer_manifests, &peer_info, seen_since);
+            write_csv_to_stdout(capacity_estimate_to_rows(estimate, human)).await?;
+        }
+        Command::Check {
+            max_sync_lag,
+            min_cooperative_peers,
+            max_frame_age,
+            max_rpc_latency,
+            balance_window,
+            history_file,
+            timeout,
+        } => {
+            let options = HealthOptions::new()
+                .max_sync_lag(max_sync_lag)
+                .min_cooperative_peers(min_cooperative_peers)
+                .max_frame_age(max_frame_age)
+                .max_rpc_latency(max_rpc_latency)
+                .balance_window(balance_window);
+            if client.node_uri().is_none() {
+                anyhow::bail!("the --node_uri argument or the {QUILCLIENT_NODE_URI} must be set");
+            }
+            let check = check_health(&mut client, &history_file, &options);
+            let report = tokio::time::timeout(timeout, check).await.map_err(|_| {
+                anyhow::anyhow!("timed out after {}", humantime::format_duration(timeout))
+            })??;
+            println!("{}", report.summary());
+            return Ok(ExitCode::from(report.status().exit_code()));
+        }
+        Command::Dashboard {
+            refresh_interval,
+            recent_frames,
+        } => {
+            let options = OverviewOptions::new().recent_frames(recent_frames);
+            dashboard::run(client.required_node_uri(), refresh_interval, options).await?;
+        }
         Command::DownloadFrame {
             filter,
             frame_number,
@@ -125,6 +732,8 @@ async fn main() -> Result<()> {
         } => {
             let frame_filter: FrameFilter = filter.into();
             match client
+                .get()
+                .await?
                 .frame_info(frame_filter.clone(), frame_number)
                 .await?
             {
@@ -142,6 +751,22 @@ async fn main() -> Result<()> {
                 }
             }
         }
+        Command::Exporter {
+            listen,
+            mut target,
+            timeout,
+            score_threshold,
+        } => {
+            if target.is_empty() {
+                let uri = client.required_node_uri();
+                target.push(Target {
+                    name: uri.to_string(),
+                    uri,
+                });
+            }
+            let score_options = ScoreOptions::new().threshold(score_threshold);
+            exporter::serve(listen, target, timeout, score_options).await?;
+        }
         Command::Frames {
             filter,
             from_frame_number,
@@ -154,39 +779,569 @@ async fn main() -> Result<()> {
                 .to_frame_number(to_frame_number)
                 .include_candidates(include_candidates);
 
-            let frames = client.frames(frames_opts).await?;
+            let frames = client.get().await?.frames(frames_opts).await?;
             write_csv_to_stdout(clock_frames_to_rows(frames.truncated_clock_frames)).await?;
         }
-        Command::NetworkInfo => {
-            let network_info = client.network_info().await?;
-            write_csv_to_stdout(network_infos_to_rows(network_info.network_info)).await?;
+        Command::NetworkInfo {
+            multiaddr_opts,
+            #[cfg(feature = "geoip")]
+            geoip_opts,
+        } => {
+            let filter: MultiaddrFilter = multiaddr_opts.into();
+            let network_info = client.get().await?.network_info().await?;
+            let network_infos =
+                network_info
+                    .network_info
+                    .into_iter()
+                    .map(|network_info| NetworkInfo {
+                        multiaddrs: filter.apply(network_info.multiaddrs),
+                        ..network_info
+                    });
+            let rows = network_infos_to_rows(network_infos);
+            #[cfg(feature = "geoip")]
+            write_csv_with_geo_info(rows, geoip_opts, |database, row| {
+                database.lookup(&row.multiaddr)
+            })
+            .await?;
+            #[cfg(not(feature = "geoip"))]
+            write_csv_to_stdout(rows).await?;
         }
-        Command::PeerInfo { peer_type } => {
-            let peer_info = client.peer_info().await?;
-            match peer_type {
-                PeerType::Cooperative => write_peer_infos(peer_info.peers).await?,
-                PeerType::Uncooperative => write_peer_infos(peer_info.uncooperative_peers).await?,
+        Command::PeerInfo {
+            peer_type,
+            max_age,
+            max_clock_skew,
+            stale,
+            show_age,
+            multiaddr_opts,
+            #[cfg(feature = "geoip")]
+            geoip_opts,
+        } => {
+            let filter: MultiaddrFilter = multiaddr_opts.into();
+            let policy =
+                max_age.map(|max_age| FreshnessPolicy::new(max_age).max_clock_skew(max_clock_skew));
+            let peer_info = client.get().await?.peer_info().await?;
+            let now = Utc::now();
+            let with_cooperative = |peers: Vec<PeerInfo>, cooperative| {
+                peers.into_iter().map(move |p| (p, cooperative))
+            };
+            let peer_infos: Vec<(PeerInfo, Option<bool>)> = match peer_type {
+                PeerType::Cooperative => with_cooperative(peer_info.peers, None).collect(),
+                PeerType::Uncooperative => {
+                    with_cooperative(peer_info.uncooperative_peers, None).collect()
+                }
+                PeerType::All => with_cooperative(peer_info.peers, Some(true))
+                    .chain(with_cooperative(peer_info.uncooperative_peers, Some(false)))
+                    .collect(),
             };
+            let peer_infos = peer_infos
+                .into_iter()
+                .filter(|(peer_info, _)| {
+                    policy.map_or(true, |policy| {
+                        let fresh = policy.classify(peer_info.timestamp, now) == Freshness::Fresh;
+                        fresh != stale
+                    })
+                })
+                .map(|(peer_info, cooperative)| {
+                    let peer_info = PeerInfo {
+                        multiaddrs: filter.apply(peer_info.multiaddrs),
+                        ..peer_info
+                    };
+                    (peer_info, cooperative)
+                });
+            let rows = peer_infos_to_rows(peer_infos, show_age.then_some(now));
+            #[cfg(feature = "geoip")]
+            write_csv_with_geo_info(rows, geoip_opts, |database, row| {
+                database.lookup(&row.multiaddr)
+            })
+            .await?;
+            #[cfg(not(feature = "geoip"))]
+            write_csv_to_stdout(rows).await?;
         }
-        Command::PeerManifests => {
-            let response = client.peer_manifests().await?;
-            write_csv_to_stdout(response.peer_manifests).await?;
+        Command::PeerManifests { human } => {
+            let response = client.get().await?.peer_manifests().await?;
+            if human {
+                let rows = peer_manifests_to_human_rows(response.peer_manifests);
+                write_csv_to_stdout(rows).await?;
+            } else {
+                write_csv_to_stdout(response.peer_manifests).await?;
+            }
+        }
+        Command::Graph {
+            format,
+            label,
+            peer_id,
+            extra_node_uri,
+            snapshot,
+        } => {
+            let mut graph = PeerGraph::new();
+            if client.node_uri().is_some() || (extra_node_uri.is_empty() && snapshot.is_empty()) {
+                let directory = client.get().await?.peer_directory().await?;
+                let label = label
+                    .or_else(|| client.node_uri().map(ToString::to_string))
+                    .unwrap_or_default();
+                graph = graph.with_view(&Observer::new(label).peer_id(peer_id), &directory);
+            }
+            for node_uri in extra_node_uri {
+                let label = node_uri.to_string();
+                let directory = NodeClient::new(node_uri).await?.peer_directory().await?;
+                graph = graph.with_view(&Observer::new(label), &directory);
+            }
+            for path in snapshot {
+                let directory = read_snapshot(&path).await?.peer_directory();
+                graph = graph.with_view(&Observer::new(path.display().to_string()), &directory);
+            }
+            match format {
+                GraphFormat::Dot => print!("{}", graph.to_dot()),
+                GraphFormat::Graphml => print!("{}", graph.to_graphml()),
+                GraphFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&graph.node_link())?)
+                }
+            }
         }
-        Command::TokenBalance => {
-            let token_info = client.token_info().await?;
-            println!("{}", token_info.owned_tokens.quil_tokens());
+        Command::ManifestsSummary {
+            report,
+            anomalies_only,
+        } => {
+            let peer_manifests = client.get().await?.peer_manifests().await?;
+            let summary = ManifestSummary::new(&peer_manifests);
+            match report {
+                ManifestReport::Metrics => {
+                    write_csv_to_stdout(metric_stats_to_rows(summary.metrics)).await?
+                }
+                ManifestReport::Peers if anomalies_only => {
+                    let rows = peer_performances_to_rows(summary.anomalous_peers());
+                    write_csv_to_stdout(rows).await?
+                }
+                ManifestReport::Peers => {
+                    write_csv_to_stdout(peer_performances_to_rows(&summary.peers)).await?
+                }
+            }
         }
-        Command::TokenSupply => {
-            let token_info = client.token_info().await?;
-            println!("{}", token_info.confirmed_token_supply.quil_tokens());
+        Command::Probe {
+            timeout,
+            concurrency,
+            per_peer,
+            multiaddr_opts,
+        } => {
+            let filter: MultiaddrFilter = multiaddr_opts.into();
+            let directory = client.get().await?.peer_directory().await?;
+            let targets: Vec<_> = directory
+                .records()
+                .flat_map(|record| {
+                    filter
+                        .apply(record.multiaddrs().into_iter().cloned())
+                        .into_iter()
+                        .map(|multiaddr| (record.peer_id, multiaddr))
+                })
+                .collect();
+            let options = ProbeOptions::new()
+                .timeout(timeout)
+                .concurrency(concurrency);
+            let results = probe(targets, &options).await;
+            let peer_score = |peer_id: &PeerId| {
+                directory
+                    .get(peer_id)
+                    .and_then(|record| record.peer_score())
+            };
+            if per_peer {
+                write_csv_to_stdout(peer_reachability_to_rows(&results, peer_score)).await?;
+            } else {
+                write_csv_to_stdout(probe_results_to_rows(results, peer_score)).await?;
+            }
         }
+        Command::Rewards {
+            command:
+                RewardsCommand::Track {
+                    history_file,
+                    interval,
+                },
+        } => {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match sample_rewards(&mut client).await {
+                    Ok(sample) => append_json_line(&history_file, &sample).await?,
+                    Err(error) => tracing::warn!("Failed to sample the token info: {error:#}"),
+                }
+            }
+        }
+        Command::Rewards {
+            command:
+                RewardsCommand::Report {
+                    history_file,
+                    window,
+                    max_gap,
+                },
+        } => {
+            let mut samples: Vec<RewardSample> = read_json_lines(&history_file).await?;
+            samples.sort_by_key(|sample| sample.sampled_at);
+            let now = Utc::now();
+            let mut reports = Vec::with_capacity(window.len());
+            for window in window {
+                // Windows reaching before the earliest date cover all samples.
+                let from = chrono::Duration::from_std(window)
+                    .ok()
+                    .and_then(|window| now.checked_sub_signed(window))
+                    .unwrap_or(DateTime::<Utc>::MIN_UTC);
+                if let Some(report) = RewardReport::new(&samples, from, now, max_gap) {
+                    reports.push((window, report));
+                }
+            }
+            write_csv_to_stdout(reward_reports_to_rows(reports)).await?;
+        }
+        Command::Scores {
+            report,
+            threshold,
+            bucket_width,
+            snapshot,
+        } => {
+            let options = ScoreOptions::new()
+                .threshold(threshold)
+                .bucket_width(bucket_width);
+            match report {
+                ScoreReport::Summary => {
+                    let stats = score_stats(&mut client, &options).await?;
+                    write_csv_to_stdout(score_stats_to_rows(&stats)).await?;
+                }
+                ScoreReport::Histogram => {
+                    let stats = score_stats(&mut client, &options).await?;
+                    write_csv_to_stdout(stats.histogram).await?;
+                }
+                ScoreReport::Penalized => {
+                    let stats = score_stats(&mut client, &options).await?;
+                    write_csv_to_stdout(stats.penalized).await?;
+                }
+                ScoreReport::Trends => {
+                    if snapshot.is_empty() {
+                        anyhow::bail!("The trends report requires at least one --snapshot");
+                    }
+                    let mut snapshots = Vec::with_capacity(snapshot.len());
+                    for path in &snapshot {
+                        snapshots.push(read_snapshot(path).await?);
+                    }
+                    let trends = ScoreTrend::from_snapshots(&snapshots);
+                    write_csv_to_stdout(score_trends_to_rows(trends, threshold)).await?;
+                }
+            }
+        }
+        Command::Serve {
+            listen,
+            cache_ttl,
+            cors_origin,
+            bearer_token,
+            timeout,
+            amount_format,
+        } => {
+            AmountFormat::set_default(amount_format.into());
+            let options = ApiOptions {
+                cache_ttl,
+                cors_origins: cors_origin,
+                bearer_token,
+                timeout,
+            };
+            api::serve(listen, client.required_node_uri(), options).await?;
+        }
+        Command::Snapshot {
+            command: SnapshotCommand::Save { out_file_path },
+        } => {
+            let snapshot = client.get().await?.network_snapshot().await?;
+            let out_file_path = out_file_path.unwrap_or_else(|| {
+                let timestamp = snapshot.captured_at.format("%Y%m%dT%H%M%SZ");
+                format!("./snapshot-{timestamp}.json")
+            });
+            tokio::fs::write(out_file_path, serde_json::to_vec(&snapshot)?).await?;
+        }
+        Command::Snapshot {
+            command: SnapshotCommand::Diff { from, to },
+        } => {
+            let from = read_snapshot(&from).await?;
+            let to = read_snapshot(&to).await?;
+            write_csv_to_stdout(snapshot_diff_to_rows(from.diff(&to))).await?;
+        }
+        Command::SupplyHistory {
+            command:
+                SupplyHistoryCommand::Track {
+                    history_file,
+                    interval,
+                },
+        } => {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match sample_supply(&mut client).await {
+                    Ok(sample) => append_json_line(&history_file, &sample).await?,
+                    Err(error) => tracing::warn!("Failed to sample the token supply: {error:#}"),
+                }
+            }
+        }
+        Command::SupplyHistory {
+            command:
+                SupplyHistoryCommand::Report {
+                    history_file,
+                    total,
+                },
+        } => {
+            let tracker: SupplyTracker =
+                read_json_lines(&history_file).await?.into_iter().collect();
+            let growth = if total {
+                tracker.total().into_iter().collect()
+            } else {
+                tracker.intervals()
+            };
+            write_csv_to_stdout(supply_growth_to_rows(growth)).await?;
+        }
+        Command::SyncStatus { sample_interval } => {
+            let mut earlier = None;
+            if !sample_interval.is_zero() {
+                earlier = Some(client.get().await?.sync_status().await?);
+                tokio::time::sleep(sample_interval).await;
+            }
+            let sync_status = client.get().await?.sync_status().await?;
+            write_csv_to_stdout(sync_status_to_rows(&sync_status, earlier.as_ref())).await?;
+        }
+        Command::Peers {
+            #[cfg(feature = "geoip")]
+            geoip_opts,
+        } => {
+            let directory = client.get().await?.peer_directory().await?;
+            let rows = peer_records_to_rows(directory);
+            #[cfg(feature = "geoip")]
+            write_csv_with_geo_info(rows, geoip_opts, |database, row| {
+                let multiaddrs: Vec<Multiaddr> = row
+                    .multiaddrs
+                    .split_whitespace()
+                    .filter_map(|multiaddr| multiaddr.parse().ok())
+                    .collect();
+                database.lookup_peer(&multiaddrs)
+            })
+            .await?;
+            #[cfg(not(feature = "geoip"))]
+            write_csv_to_stdout(rows).await?;
+        }
+        #[cfg(feature = "geoip")]
+        Command::GeoSummary { geoip_db } => {
+            let database = GeoIpDatabase::open(&geoip_db)?;
+            let directory = client.get().await?.peer_directory().await?;
+            let peers = directory
+                .records()
+                .map(|record| database.lookup_peer(record.multiaddrs()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let summary = GeoSummary::new(peers);
+            write_csv_to_stdout(geo_summary_to_rows(summary)).await?;
+        }
+        Command::Peer { peer_id } => {
+            let directory = client.get().await?.peer_directory().await?;
+            match directory.get(&peer_id) {
+                None => anyhow::bail!("Peer {} not found", peer_id),
+                Some(record) => println!("{}", serde_json::to_string_pretty(record)?),
+            }
+        }
+        Command::TokenBalance { token_opts } => {
+            let token_info = client.get().await?.token_info().await?;
+            write_token_info(token_info, &token_opts, |token_info| {
+                token_info.owned_tokens
+            })
+            .await?;
+        }
+        Command::TokenSupply { token_opts } => {
+            let token_info = client.get().await?.token_info().await?;
+            write_token_info(token_info, &token_opts, |token_info| {
+                token_info.confirmed_token_supply
+            })
+            .await?;
+        }
+        Command::Versions { weight } => {
+            let peer_info = client.get().await?.peer_info().await?;
+            let distribution = match weight {
+                None => VersionDistribution::new(&peer_info),
+                Some(weight) => {
+                    let peer_manifests = client.get().await?.peer_manifests().await?;
+                    VersionDistribution::weighted(&peer_info, &peer_manifests, weight.into())
+                }
+            };
+            write_csv_to_stdout(version_distribution_to_rows(distribution)).await?;
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Whether the arguments invoke the `check` command, also if they are invalid.
+fn is_check_command() -> bool {
+    QuilClientArgs::command()
+        .ignore_errors(true)
+        .try_get_matches()
+        .is_ok_and(|matches| matches.subcommand_name() == Some("check"))
+}
+
+/// Print an UNKNOWN `check` summary and return its exit code.
+fn check_unknown(message: impl std::fmt::Display) -> ExitCode {
+    println!("QUILIBRIUM UNKNOWN - {message}");
+    ExitCode::from(HealthStatus::Unknown.exit_code())
+}
+
+/// Connects to the node on first use, so that commands that don't talk to the node don't require
+/// the node URI.
+struct LazyNodeClient {
+    node_uri: Option<Uri>,
+    client: Option<NodeClient>,
+}
+
+impl LazyNodeClient {
+    fn new(node_uri: Option<Uri>) -> Self {
+        Self {
+            node_uri,
+            client: None,
+        }
+    }
+
+    fn node_uri(&self) -> Option<&Uri> {
+        self.node_uri.as_ref()
+    }
+
+    /// The node URI, exiting with a usage error if it isn't set.
+    fn required_node_uri(&self) -> Uri {
+        // Hack to work around not being able to make global args required:
+        // https://github.com/clap-rs/clap/issues/1546
+        self.node_uri.clone().unwrap_or_else(|| {
+            let mut cmd = QuilClientArgs::command();
+            cmd.error(
+                ErrorKind::MissingRequiredArgument,
+                format!("The --node_uri argument or the {QUILCLIENT_NODE_URI} must be set"),
+            )
+            .exit();
+        })
+    }
+
+    async fn get(&mut self) -> Result<&mut NodeClient> {
+        if self.client.is_none() {
+            self.client = Some(NodeClient::new(self.required_node_uri()).await?);
+        }
+        Ok(self.client.as_mut().expect("connected above"))
+    }
+}
+
+async fn score_stats(client: &mut LazyNodeClient, options: &ScoreOptions) -> Result<ScoreStats> {
+    let network_info = client.get().await?.network_info().await?;
+    Ok(ScoreStats::new(&network_info, options))
+}
+
+/// Print the selected amount, or all amounts with `--all`.
+async fn write_token_info(
+    token_info: TokenInfo,
+    token_opts: &TokenOpts,
+    select: impl Fn(&TokenInfo) -> ObliviousTransferUnits,
+) -> Result<()> {
+    if !token_opts.all {
+        println!("{}", token_opts.format(select(&token_info)));
+        return Ok(());
     }
 
+    let row = token_info_to_row(&token_info, |amount| token_opts.format(amount));
+    match token_opts.format {
+        TokenFormat::Csv => write_csv_to_stdout([row]).await,
+        TokenFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&row)?);
+            Ok(())
+        }
+    }
+}
+
+async fn sample_rewards(client: &mut LazyNodeClient) -> Result<RewardSample> {
+    let token_info = client.get().await?.token_info().await?;
+    Ok(RewardSample::new(Utc::now(), &token_info))
+}
+
+/// Collect the metrics of the node and check them. The balance is checked against the history
+/// file, which the current balance is then appended to.
+async fn check_health(
+    client: &mut LazyNodeClient,
+    history_file: &Path,
+    options: &HealthOptions,
+) -> Result<HealthReport> {
+    let started = Instant::now();
+    let metrics = match client.get().await {
+        Ok(client) => NodeMetrics::collect(client, &ScoreOptions::new()).await,
+        Err(error) => NodeMetrics::unreachable(started.elapsed(), format!("{error:#}")),
+    };
+
+    let mut history: Vec<RewardSample> = Vec::new();
+    if options.balance_window.is_set() {
+        if tokio::fs::try_exists(history_file).await? {
+            history = read_json_lines(history_file).await?;
+            history.sort_by_key(|sample| sample.sampled_at);
+        }
+        if let Some(token_info) = &metrics.token_info {
+            let sample = RewardSample::new(metrics.collected_at, token_info);
+            append_json_line(history_file, &sample).await?;
+        }
+    }
+    Ok(HealthReport::new(&metrics, &history, options))
+}
+
+/// Parse a threshold from "WARNING,CRITICAL" where either can be empty. A single value is the
+/// warning threshold.
+fn parse_threshold<T: FromStr + PartialOrd + Copy>(s: &str) -> Result<Threshold<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    parse_threshold_with(s, |value| Ok(value.parse()?))
+}
+
+fn parse_duration_threshold(s: &str) -> Result<Threshold<Duration>> {
+    parse_threshold_with(s, |value| Ok(humantime::parse_duration(value)?))
+}
+
+fn parse_threshold_with<T: PartialOrd + Copy>(
+    s: &str,
+    parse: impl Fn(&str) -> Result<T>,
+) -> Result<Threshold<T>> {
+    let (warning, critical) = s.split_once(',').unwrap_or((s, ""));
+    let parse_optional = |value: &str| match value.trim() {
+        "" => Ok(None),
+        value => parse(value).map(Some),
+    };
+    Ok(Threshold::new(
+        parse_optional(warning)?,
+        parse_optional(critical)?,
+    ))
+}
+
+async fn sample_supply(client: &mut LazyNodeClient) -> Result<SupplySample> {
+    Ok(client.get().await?.supply_sample().await?)
+}
+
+/// Append a value to a JSON lines file, creating the file if it doesn't exist.
+async fn append_json_line(path: &Path, value: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(&line).await?;
     Ok(())
 }
 
-async fn write_peer_infos(peer_infos: impl IntoIterator<Item = PeerInfo>) -> Result<()> {
-    write_csv_to_stdout(peer_infos_to_rows(peer_infos)).await
+/// Read the values from a JSON lines file, skipping blank lines.
+async fn read_json_lines<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let lines = tokio::fs::read_to_string(path).await?;
+    let mut values = Vec::new();
+    for (index, line) in lines.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = serde_json::from_str(line)
+            .with_context(|| format!("Invalid JSON on line {}", index + 1))?;
+        values.push(value);
+    }
+    Ok(values)
+}
+
+async fn read_snapshot(path: &Path) -> Result<NetworkSnapshot> {
+    let bytes = tokio::fs::read(path).await?;
+    Ok(serde_json::from_slice(&bytes)?)
 }
 
 async fn write_csv_to_stdout(
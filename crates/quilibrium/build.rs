@@ -1,6 +1,8 @@
 fn main() {
+    // The server stubs are only needed by the fake node in `test_utils`.
+    let test_utils = std::env::var_os("CARGO_FEATURE_TEST_UTILS").is_some();
     tonic_build::configure()
-        .build_server(false)
+        .build_server(test_utils)
         .type_attribute(
             "quilibrium.node.node.pb.PeerInfoResponse",
             "#[derive(serde::Serialize, serde::Deserialize)]",
@@ -0,0 +1,167 @@
+//! Estimate the hardware capacity of the network from the self-reported peer manifests.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+
+use crate::node::{PeerId, PeerInfoResponse, PeerManifest, PeerManifestsResponse};
+use crate::protocol_version::ProtocolVersion;
+
+/// The summed hardware of a group of peers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Capacity {
+    /// The number of peers.
+    pub peers: usize,
+    /// The number of logical cores.
+    pub cores: u64,
+    /// The memory in bytes.
+    pub memory: u64,
+    /// The storage in bytes.
+    pub storage: u64,
+}
+
+impl Capacity {
+    fn add(&mut self, manifest: &PeerManifest) {
+        self.peers += 1;
+        self.cores = self.cores.saturating_add(manifest.cores.into());
+        self.memory = self.memory.saturating_add(manifest.memory);
+        self.storage = self.storage.saturating_add(manifest.storage);
+    }
+}
+
+/// The capacity of the peers running a protocol version.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VersionCapacity {
+    /// The protocol version. `None` for peers without peer info.
+    pub version: Option<ProtocolVersion>,
+    /// The summed hardware of the peers.
+    pub capacity: Capacity,
+}
+
+/// The hardware capacity of the network.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CapacityEstimate {
+    /// The summed hardware of all the peers.
+    pub total: Capacity,
+    /// The summed hardware per protocol version from the highest version down. Peers without peer
+    /// info come last.
+    pub by_version: Vec<VersionCapacity>,
+}
+
+impl CapacityEstimate {
+    /// Sum the hardware in the peer manifests. If a peer has multiple manifests, the one with the
+    /// highest master head frame is used. The protocol versions are taken from the peer info.
+    ///
+    /// If `seen_since` is set, only the peers with peer info broadcasted at or after that time are
+    /// counted.
+    pub fn new(
+        peer_manifests: &PeerManifestsResponse,
+        peer_info: &PeerInfoResponse,
+        seen_since: Option<DateTime<Utc>>,
+    ) -> Self {
+        let mut last_seen: HashMap<PeerId, (DateTime<Utc>, ProtocolVersion)> = HashMap::new();
+        for peer in peer_info.peers.iter().chain(&peer_info.uncooperative_peers) {
+            let entry = last_seen
+                .entry(peer.peer_id)
+                .or_insert((peer.timestamp, peer.version));
+            if peer.timestamp > entry.0 {
+                *entry = (peer.timestamp, peer.version);
+            }
+        }
+
+        let mut manifests: HashMap<PeerId, &PeerManifest> = HashMap::new();
+        for manifest in &peer_manifests.peer_manifests {
+            let entry = manifests.entry(manifest.peer_id).or_insert(manifest);
+            if manifest.master_head_frame > entry.master_head_frame {
+                *entry = manifest;
+            }
+        }
+
+        let mut total = Capacity::default();
+        let mut by_version: BTreeMap<Option<ProtocolVersion>, Capacity> = BTreeMap::new();
+        for (peer_id, manifest) in manifests {
+            let seen = last_seen.get(&peer_id);
+            if let Some(seen_since) = seen_since {
+                if seen.map_or(true, |(timestamp, _)| *timestamp < seen_since) {
+                    continue;
+                }
+            }
+            total.add(manifest);
+            by_version
+                .entry(seen.map(|(_, version)| *version))
+                .or_default()
+                .add(manifest);
+        }
+
+        // `None` sorts first, so reversing puts the highest version first and `None` last.
+        let by_version = by_version
+            .into_iter()
+            .rev()
+            .map(|(version, capacity)| VersionCapacity { version, capacity })
+            .collect();
+
+        Self { total, by_version }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::test_utils::{peer_info, peer_manifest, random_peer_id};
+
+    fn manifest(peer_id: PeerId, cores: u32, master_head_frame: u64) -> PeerManifest {
+        PeerManifest {
+            cores,
+            memory: 1 << 30,
+            storage: 1 << 40,
+            master_head_frame,
+            ..peer_manifest(peer_id)
+        }
+    }
+
+    #[test]
+    fn estimate() {
+        let (a, b, c) = (random_peer_id(), random_peer_id(), random_peer_id());
+        let old = ProtocolVersion::new(1, 1, 8);
+        let new = ProtocolVersion::new(1, 2, 0);
+        let stale = crate::node::PeerInfo {
+            timestamp: Utc::now() - Duration::hours(2),
+            ..peer_info(b, old)
+        };
+        let peer_info = PeerInfoResponse {
+            peers: vec![peer_info(a, new)],
+            uncooperative_peers: vec![stale],
+        };
+        let peer_manifests = PeerManifestsResponse {
+            peer_manifests: vec![
+                manifest(a, 4, 1),
+                manifest(a, 8, 2),
+                manifest(b, 16, 1),
+                manifest(c, 2, 1),
+            ],
+        };
+
+        let estimate = CapacityEstimate::new(&peer_manifests, &peer_info, None);
+        assert_eq!(estimate.total.peers, 3);
+        assert_eq!(estimate.total.cores, 26);
+        assert_eq!(estimate.total.memory, 3 << 30);
+        assert_eq!(estimate.total.storage, 3 << 40);
+        let versions: Vec<(Option<ProtocolVersion>, u64)> = estimate
+            .by_version
+            .iter()
+            .map(|v| (v.version, v.capacity.cores))
+            .collect();
+        assert_eq!(versions, vec![(Some(new), 8), (Some(old), 16), (None, 2)]);
+
+        let recent = CapacityEstimate::new(
+            &peer_manifests,
+            &peer_info,
+            Some(Utc::now() - Duration::minutes(30)),
+        );
+        assert_eq!(recent.total.peers, 1);
+        assert_eq!(recent.total.cores, 8);
+        assert_eq!(recent.by_version.len(), 1);
+    }
+}
@@ -0,0 +1,182 @@
+//! Track peers moving between the cooperative and the uncooperative lists of the peer info.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::node::{PeerId, PeerInfoResponse};
+
+/// The direction of a move between the cooperative and the uncooperative lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum TransitionKind {
+    /// The peer moved from the cooperative to the uncooperative list.
+    MarkedUncooperative,
+    /// The peer moved from the uncooperative to the cooperative list.
+    Recovered,
+}
+
+/// A peer that moved between the cooperative and the uncooperative lists.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CooperationTransition {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// The direction of the move.
+    pub kind: TransitionKind,
+    /// The time of the observation where the move was first seen.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub observed_at: DateTime<Utc>,
+}
+
+/// The cooperation state of a peer.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PeerCooperation {
+    /// Whether the peer was cooperative in the last observation that had it.
+    pub cooperative: bool,
+    /// The time of the first observation of the current state.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub since: DateTime<Utc>,
+    /// The last time the peer was marked uncooperative.
+    #[serde(with = "chrono::serde::ts_milliseconds_option")]
+    pub marked_uncooperative_at: Option<DateTime<Utc>>,
+    /// The last time the peer recovered.
+    #[serde(with = "chrono::serde::ts_milliseconds_option")]
+    pub recovered_at: Option<DateTime<Utc>>,
+}
+
+/// Tracks the cooperation state of peers over successive peer info responses.
+///
+/// Peers that are in both lists are considered cooperative. Peers missing from a response keep
+/// their state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CooperationTracker {
+    peers: HashMap<PeerId, PeerCooperation>,
+    transitions: Vec<CooperationTransition>,
+}
+
+impl CooperationTracker {
+    /// Create a tracker without observations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an observation and return the transitions since the previous one. The first
+    /// observation of a peer doesn't count as a transition. Observations must be added in
+    /// chronological order.
+    pub fn observe(
+        &mut self,
+        observed_at: DateTime<Utc>,
+        peer_info: &PeerInfoResponse,
+    ) -> Vec<CooperationTransition> {
+        let mut states: HashMap<PeerId, bool> = peer_info
+            .uncooperative_peers
+            .iter()
+            .map(|peer| (peer.peer_id, false))
+            .collect();
+        states.extend(peer_info.peers.iter().map(|peer| (peer.peer_id, true)));
+
+        let mut transitions = Vec::new();
+        for (peer_id, cooperative) in states {
+            let Some(state) = self.peers.get_mut(&peer_id) else {
+                self.peers.insert(
+                    peer_id,
+                    PeerCooperation {
+                        cooperative,
+                        since: observed_at,
+                        marked_uncooperative_at: None,
+                        recovered_at: None,
+                    },
+                );
+                continue;
+            };
+            if state.cooperative == cooperative {
+                continue;
+            }
+
+            state.cooperative = cooperative;
+            state.since = observed_at;
+            let kind = if cooperative {
+                state.recovered_at = Some(observed_at);
+                TransitionKind::Recovered
+            } else {
+                state.marked_uncooperative_at = Some(observed_at);
+                TransitionKind::MarkedUncooperative
+            };
+            transitions.push(CooperationTransition {
+                peer_id,
+                kind,
+                observed_at,
+            });
+        }
+
+        transitions.sort_by_key(|transition| transition.peer_id);
+        self.transitions.extend(transitions.iter().cloned());
+        transitions
+    }
+
+    /// The cooperation state of a peer.
+    pub fn get(&self, peer_id: &PeerId) -> Option<&PeerCooperation> {
+        self.peers.get(peer_id)
+    }
+
+    /// All the transitions observed so far in chronological order.
+    pub fn transitions(&self) -> &[CooperationTransition] {
+        &self.transitions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::node::PeerInfo;
+    use crate::protocol_version::ProtocolVersion;
+    use crate::test_utils::{peer_info, random_peer_id};
+
+    fn response(cooperative: &[PeerId], uncooperative: &[PeerId]) -> PeerInfoResponse {
+        let version = ProtocolVersion::new(1, 2, 0);
+        let infos = |peers: &[PeerId]| -> Vec<PeerInfo> {
+            peers
+                .iter()
+                .map(|peer_id| peer_info(*peer_id, version))
+                .collect()
+        };
+        PeerInfoResponse {
+            peers: infos(cooperative),
+            uncooperative_peers: infos(uncooperative),
+        }
+    }
+
+    #[test]
+    fn track_transitions() {
+        let (a, b) = (random_peer_id(), random_peer_id());
+        let at = |minute| Utc.with_ymd_and_hms(2023, 11, 1, 0, minute, 0).unwrap();
+        let mut tracker = CooperationTracker::new();
+
+        assert!(tracker.observe(at(0), &response(&[a, b], &[])).is_empty());
+
+        let transitions = tracker.observe(at(1), &response(&[b], &[a]));
+        assert_eq!(
+            transitions,
+            vec![CooperationTransition {
+                peer_id: a,
+                kind: TransitionKind::MarkedUncooperative,
+                observed_at: at(1),
+            }]
+        );
+
+        // Missing peers keep their state and peers in both lists are cooperative.
+        assert!(tracker.observe(at(2), &response(&[], &[a])).is_empty());
+        let transitions = tracker.observe(at(3), &response(&[a, b], &[a]));
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].kind, TransitionKind::Recovered);
+
+        let state = tracker.get(&a).unwrap();
+        assert!(state.cooperative);
+        assert_eq!(state.since, at(3));
+        assert_eq!(state.marked_uncooperative_at, Some(at(1)));
+        assert_eq!(state.recovered_at, Some(at(3)));
+        assert_eq!(tracker.get(&b).unwrap().since, at(0));
+        assert_eq!(tracker.transitions().len(), 2);
+    }
+}
@@ -0,0 +1,165 @@
+//! Detect stale peer info from the self-reported timestamps.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::node::{PeerId, PeerInfoResponse};
+
+/// Whether the timestamp of peer info is recent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Freshness {
+    /// The timestamp is within the max age and the clock skew tolerance.
+    Fresh,
+    /// The timestamp is older than the max age.
+    Stale,
+    /// The timestamp is further in the future than the clock skew tolerance.
+    Future,
+}
+
+impl std::fmt::Display for Freshness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Freshness::Fresh => write!(f, "fresh"),
+            Freshness::Stale => write!(f, "stale"),
+            Freshness::Future => write!(f, "future"),
+        }
+    }
+}
+
+/// The thresholds for classifying peer info timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreshnessPolicy {
+    /// Peer info older than this is stale.
+    pub max_age: Duration,
+    /// Peer info timestamped further in the future than this is reported, as the clock of the peer
+    /// is off.
+    pub max_clock_skew: Duration,
+}
+
+impl FreshnessPolicy {
+    /// Create a new freshness policy builder with a clock skew tolerance of 30 seconds.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            max_clock_skew: Duration::from_secs(30),
+        }
+    }
+
+    /// Set the clock skew tolerance.
+    pub fn max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.max_clock_skew = max_clock_skew;
+        self
+    }
+
+    /// Classify a timestamp relative to `now`.
+    pub fn classify(&self, timestamp: DateTime<Utc>, now: DateTime<Utc>) -> Freshness {
+        let age = now - timestamp;
+        match age.to_std() {
+            Ok(age) if age > self.max_age => Freshness::Stale,
+            Ok(_) => Freshness::Fresh,
+            // The timestamp is in the future.
+            Err(_) => match (-age).to_std() {
+                Ok(ahead) if ahead <= self.max_clock_skew => Freshness::Fresh,
+                _ => Freshness::Future,
+            },
+        }
+    }
+
+    /// The cooperative and uncooperative peers whose peer info isn't fresh.
+    pub fn stale_peers(&self, peer_info: &PeerInfoResponse, now: DateTime<Utc>) -> Vec<StalePeer> {
+        let peers = peer_info.peers.iter().map(|peer| (peer, true)).chain(
+            peer_info
+                .uncooperative_peers
+                .iter()
+                .map(|peer| (peer, false)),
+        );
+        peers
+            .filter_map(|(peer, cooperative)| {
+                let freshness = self.classify(peer.timestamp, now);
+                (freshness != Freshness::Fresh).then(|| StalePeer {
+                    peer_id: peer.peer_id,
+                    cooperative,
+                    timestamp: peer.timestamp,
+                    age: peer.age(now),
+                    freshness,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A peer whose peer info is stale or timestamped in the future.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StalePeer {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// Whether the peer is in the cooperative list.
+    pub cooperative: bool,
+    /// The self-reported timestamp of the peer info.
+    pub timestamp: DateTime<Utc>,
+    /// The age of the peer info. Negative if it's timestamped in the future.
+    pub age: chrono::Duration,
+    /// Whether the peer info is stale or timestamped in the future.
+    pub freshness: Freshness,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::PeerInfo;
+    use crate::protocol_version::ProtocolVersion;
+    use crate::test_utils::{peer_info, random_peer_id};
+
+    #[test]
+    fn classify() {
+        let now = Utc::now();
+        let policy = FreshnessPolicy::new(Duration::from_secs(600));
+        let minutes = chrono::Duration::minutes;
+
+        assert_eq!(policy.classify(now, now), Freshness::Fresh);
+        assert_eq!(policy.classify(now - minutes(10), now), Freshness::Fresh);
+        assert_eq!(policy.classify(now - minutes(11), now), Freshness::Stale);
+        assert_eq!(
+            policy.classify(now + chrono::Duration::seconds(30), now),
+            Freshness::Fresh
+        );
+        assert_eq!(policy.classify(now + minutes(1), now), Freshness::Future);
+
+        let tolerant = policy.max_clock_skew(Duration::from_secs(120));
+        assert_eq!(tolerant.classify(now + minutes(1), now), Freshness::Fresh);
+    }
+
+    #[test]
+    fn stale_peers() {
+        let now = Utc::now();
+        let version = ProtocolVersion::new(1, 2, 0);
+        let (fresh, stale, future) = (random_peer_id(), random_peer_id(), random_peer_id());
+        let response = PeerInfoResponse {
+            peers: vec![
+                PeerInfo {
+                    timestamp: now,
+                    ..peer_info(fresh, version)
+                },
+                PeerInfo {
+                    timestamp: now + chrono::Duration::hours(1),
+                    ..peer_info(future, version)
+                },
+            ],
+            uncooperative_peers: vec![PeerInfo {
+                timestamp: now - chrono::Duration::hours(1),
+                ..peer_info(stale, version)
+            }],
+        };
+
+        let policy = FreshnessPolicy::new(Duration::from_secs(600));
+        let stale_peers = policy.stale_peers(&response, now);
+        assert_eq!(stale_peers.len(), 2);
+        assert_eq!(stale_peers[0].peer_id, future);
+        assert_eq!(stale_peers[0].freshness, Freshness::Future);
+        assert_eq!(stale_peers[0].age, -chrono::Duration::hours(1));
+        assert_eq!(stale_peers[1].peer_id, stale);
+        assert_eq!(stale_peers[1].freshness, Freshness::Stale);
+        assert!(!stale_peers[1].cooperative);
+    }
+}
@@ -0,0 +1,252 @@
+//! Offline GeoIP and ASN enrichment of peer addresses from local
+//! [MaxMind DB](https://maxmind.github.io/MaxMind-DB/) files. No network lookups are made, so DNS
+//! multiaddrs are not resolved.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use multiaddr::{Multiaddr, Protocol};
+
+use crate::multiaddrs::{AddressScope, MultiaddrClass};
+
+/// Location and network operator data of an IP address.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct GeoInfo {
+    /// The ISO 3166-1 alpha-2 country code.
+    pub country: Option<String>,
+    /// The English name of the city.
+    pub city: Option<String>,
+    /// The autonomous system number.
+    pub asn: Option<u32>,
+    /// The organization that operates the autonomous system.
+    pub organization: Option<String>,
+}
+
+impl GeoInfo {
+    fn merge(&mut self, other: GeoInfo) {
+        self.country = self.country.take().or(other.country);
+        self.city = self.city.take().or(other.city);
+        self.asn = self.asn.take().or(other.asn);
+        self.organization = self.organization.take().or(other.organization);
+    }
+}
+
+/// Local MaxMind DB files, e.g. GeoLite2 City and GeoLite2 ASN. Each IP address is looked up in
+/// all the databases and the results are merged.
+#[derive(Debug)]
+pub struct GeoIpDatabase {
+    readers: Vec<Reader<Vec<u8>>>,
+}
+
+impl GeoIpDatabase {
+    /// Load the databases from disk.
+    pub fn open(paths: impl IntoIterator<Item = impl AsRef<Path>>) -> Result<Self, GeoIpError> {
+        let readers = paths
+            .into_iter()
+            .map(Reader::open_readfile)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { readers })
+    }
+
+    /// Load the databases from memory.
+    pub fn from_bytes(databases: impl IntoIterator<Item = Vec<u8>>) -> Result<Self, GeoIpError> {
+        let readers = databases
+            .into_iter()
+            .map(Reader::from_source)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { readers })
+    }
+
+    /// Look up an IP address. Fields are `None` if no database has data about them.
+    pub fn lookup_ip(&self, ip: IpAddr) -> Result<GeoInfo, GeoIpError> {
+        let mut info = GeoInfo::default();
+        for reader in &self.readers {
+            match reader.lookup::<geoip2::City>(ip) {
+                Ok(city) => info.merge(GeoInfo {
+                    country: city.country.and_then(|c| c.iso_code).map(Into::into),
+                    city: city
+                        .city
+                        .and_then(|c| c.names)
+                        .and_then(|names| names.get("en").map(|name| name.to_string())),
+                    ..Default::default()
+                }),
+                Err(MaxMindDBError::AddressNotFoundError(_)) => continue,
+                Err(err) => return Err(err.into()),
+            }
+            let asn = reader.lookup::<geoip2::Asn>(ip)?;
+            info.merge(GeoInfo {
+                asn: asn.autonomous_system_number,
+                organization: asn.autonomous_system_organization.map(Into::into),
+                ..Default::default()
+            });
+        }
+        Ok(info)
+    }
+
+    /// Look up the IP address of a multiaddr. Returns `None` if the multiaddr has no IP address.
+    pub fn lookup(&self, multiaddr: &Multiaddr) -> Result<Option<GeoInfo>, GeoIpError> {
+        let ip = multiaddr.iter().find_map(|protocol| match protocol {
+            Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+            Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+            _ => None,
+        });
+        ip.map(|ip| self.lookup_ip(ip)).transpose()
+    }
+
+    /// Look up the first public IP address of a peer. Returns `None` if the peer has no public IP
+    /// address.
+    pub fn lookup_peer<'a>(
+        &self,
+        multiaddrs: impl IntoIterator<Item = &'a Multiaddr>,
+    ) -> Result<Option<GeoInfo>, GeoIpError> {
+        for multiaddr in multiaddrs {
+            if MultiaddrClass::new(multiaddr).scope != AddressScope::Public {
+                continue;
+            }
+            if let Some(info) = self.lookup(multiaddr)? {
+                return Ok(Some(info));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The number of peers per country and per autonomous system.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GeoSummary {
+    /// The total number of peers.
+    pub peers: usize,
+    /// The peers per country ordered by the number of peers descending.
+    pub countries: Vec<GeoGroup>,
+    /// The peers per autonomous system ordered by the number of peers descending.
+    pub autonomous_systems: Vec<GeoGroup>,
+}
+
+/// The peers in a country or an autonomous system.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GeoGroup {
+    /// The country code or the autonomous system number. `None` for peers where it's unknown.
+    pub key: Option<String>,
+    /// The organization that operates the autonomous system.
+    pub name: Option<String>,
+    /// The number of peers in the group.
+    pub peers: usize,
+    /// The fraction of all peers in the group.
+    pub share: f64,
+}
+
+impl GeoSummary {
+    /// Aggregate the location of each peer. `None` means the location of the peer is unknown.
+    pub fn new(peers: impl IntoIterator<Item = Option<GeoInfo>>) -> Self {
+        let mut countries: HashMap<Option<String>, usize> = HashMap::new();
+        let mut autonomous_systems: HashMap<Option<u32>, (Option<String>, usize)> = HashMap::new();
+        let mut total = 0;
+
+        for info in peers {
+            let info = info.unwrap_or_default();
+            total += 1;
+            *countries.entry(info.country).or_default() += 1;
+            let entry = autonomous_systems.entry(info.asn).or_default();
+            entry.0 = entry.0.take().or(info.organization);
+            entry.1 += 1;
+        }
+
+        let share = |peers: usize| peers as f64 / total as f64;
+        let mut countries: Vec<GeoGroup> = countries
+            .into_iter()
+            .map(|(key, peers)| GeoGroup {
+                key,
+                name: None,
+                peers,
+                share: share(peers),
+            })
+            .collect();
+        let mut autonomous_systems: Vec<GeoGroup> = autonomous_systems
+            .into_iter()
+            .map(|(asn, (name, peers))| GeoGroup {
+                key: asn.map(|asn| asn.to_string()),
+                name,
+                peers,
+                share: share(peers),
+            })
+            .collect();
+        for groups in [&mut countries, &mut autonomous_systems] {
+            groups.sort_by(|a, b| b.peers.cmp(&a.peers).then_with(|| a.key.cmp(&b.key)));
+        }
+
+        Self {
+            peers: total,
+            countries,
+            autonomous_systems,
+        }
+    }
+}
+
+/// Errors that occur when looking up IP addresses in MaxMind DB files.
+#[derive(Debug, thiserror::Error)]
+pub enum GeoIpError {
+    /// The database can't be read or it's invalid.
+    #[error(transparent)]
+    Database(#[from] MaxMindDBError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geo_info(country: &str, asn: u32, organization: &str) -> Option<GeoInfo> {
+        Some(GeoInfo {
+            country: Some(country.to_string()),
+            city: None,
+            asn: Some(asn),
+            organization: Some(organization.to_string()),
+        })
+    }
+
+    #[test]
+    fn summary_groups_peers() {
+        let summary = GeoSummary::new([
+            geo_info("DE", 24940, "Hetzner Online GmbH"),
+            geo_info("DE", 24940, "Hetzner Online GmbH"),
+            geo_info("FI", 24940, "Hetzner Online GmbH"),
+            geo_info("US", 16509, "AMAZON-02"),
+            None,
+        ]);
+        assert_eq!(summary.peers, 5);
+
+        let countries: Vec<(Option<&str>, usize)> = summary
+            .countries
+            .iter()
+            .map(|group| (group.key.as_deref(), group.peers))
+            .collect();
+        assert_eq!(
+            countries,
+            vec![(Some("DE"), 2), (None, 1), (Some("FI"), 1), (Some("US"), 1)]
+        );
+
+        let hetzner = &summary.autonomous_systems[0];
+        assert_eq!(hetzner.key.as_deref(), Some("24940"));
+        assert_eq!(hetzner.name.as_deref(), Some("Hetzner Online GmbH"));
+        assert_eq!(hetzner.peers, 3);
+        assert_eq!(hetzner.share, 0.6);
+    }
+
+    #[test]
+    fn lookup_without_databases() -> anyhow::Result<()> {
+        let database = GeoIpDatabase::from_bytes([])?;
+        let private: Multiaddr = "/ip4/10.0.0.1/tcp/8336".parse()?;
+        let public: Multiaddr = "/ip4/1.2.3.4/tcp/8336".parse()?;
+        let dns: Multiaddr = "/dns4/example.com/tcp/8336".parse()?;
+
+        assert_eq!(database.lookup(&dns)?, None);
+        assert_eq!(database.lookup_peer([&private, &dns])?, None);
+        assert_eq!(
+            database.lookup_peer([&private, &dns, &public])?,
+            Some(GeoInfo::default())
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,471 @@
+//! Export the peers that nodes see as a graph with the observing nodes at the center.
+//!
+//! The graph can be written as [Graphviz DOT](https://graphviz.org/doc/info/lang.html),
+//! [GraphML](http://graphml.graphdrawing.org/) or serialized as a node-link JSON document in the
+//! format used by [NetworkX](https://networkx.org/) and [D3](https://d3js.org/).
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::node::PeerId;
+use crate::peer_directory::{PeerDirectory, PeerRecord};
+use crate::protocol_version::ProtocolVersion;
+
+/// A node whose view of the network is added to the graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observer {
+    /// A human readable label, e.g. the gRPC URI of the node.
+    pub label: String,
+    /// The libp2p peer ID of the node, if known. Used to merge the observer with the peer node
+    /// when another observer sees it.
+    pub peer_id: Option<PeerId>,
+}
+
+impl Observer {
+    /// Create an observer with a label and an unknown peer ID.
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            peer_id: None,
+        }
+    }
+
+    /// Set the libp2p peer ID of the node.
+    pub fn peer_id(mut self, peer_id: Option<PeerId>) -> Self {
+        self.peer_id = peer_id;
+        self
+    }
+
+    fn id(&self) -> String {
+        self.peer_id
+            .map_or_else(|| self.label.clone(), |peer_id| peer_id.to_string())
+    }
+}
+
+/// The kind of a node in the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    /// A node whose view was added to the graph.
+    Observer,
+    /// A peer seen by an observer.
+    Peer,
+}
+
+/// A node in the graph. The attributes are `None` if no observer knows them.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GraphNode {
+    /// The peer ID of the node, or the label of an observer with an unknown peer ID.
+    pub id: String,
+    /// Whether the node is an observer or a peer.
+    pub kind: NodeKind,
+    /// The label of an observer.
+    pub label: Option<String>,
+    /// The mean peer score by the observers that have the peer in their peer store.
+    pub peer_score: Option<f64>,
+    /// The protocol version of the peer.
+    pub version: Option<ProtocolVersion>,
+    /// Whether the peer is cooperative.
+    pub cooperative: Option<bool>,
+    /// The number of logical cores of the peer.
+    pub cores: Option<u32>,
+    /// The memory of the peer in bytes.
+    pub memory: Option<u64>,
+    /// The storage of the peer in bytes.
+    pub storage: Option<u64>,
+}
+
+impl GraphNode {
+    fn new(id: String, kind: NodeKind) -> Self {
+        Self {
+            id,
+            kind,
+            label: None,
+            peer_score: None,
+            version: None,
+            cooperative: None,
+            cores: None,
+            memory: None,
+            storage: None,
+        }
+    }
+
+    fn merge(&mut self, record: &PeerRecord) {
+        self.version = self.version.or(record.version());
+        // Cooperative in any view wins, like in the peer directory.
+        self.cooperative = match (self.cooperative, record.cooperative) {
+            (Some(a), Some(b)) => Some(a || b),
+            (a, b) => a.or(b),
+        };
+        if let Some(manifest) = &record.manifest {
+            self.cores = self.cores.or(Some(manifest.cores));
+            self.memory = self.memory.or(Some(manifest.memory));
+            self.storage = self.storage.or(Some(manifest.storage));
+        }
+    }
+}
+
+/// An edge from an observer to a peer it knows about.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GraphEdge {
+    /// The ID of the observer node.
+    pub source: String,
+    /// The ID of the peer node.
+    pub target: String,
+    /// The peer score by the observer. `None` if the peer isn't in the observer's peer store.
+    pub peer_score: Option<f64>,
+    /// Whether the peer is in the observer's peer store.
+    pub network_info: bool,
+    /// Whether the observer has the broadcasted sync info of the peer.
+    pub peer_info: bool,
+}
+
+/// The peers seen by one or more observers. Views are merged by peer ID.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeerGraph {
+    nodes: BTreeMap<String, GraphNode>,
+    edges: BTreeMap<(String, String), GraphEdge>,
+}
+
+impl PeerGraph {
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the view of an observer. Peers that only have a manifest are left out, as the observer
+    /// isn't connected to them.
+    pub fn with_view(mut self, observer: &Observer, directory: &PeerDirectory) -> Self {
+        let observer_id = observer.id();
+        let observer_node = self
+            .nodes
+            .entry(observer_id.clone())
+            .or_insert_with(|| GraphNode::new(observer_id.clone(), NodeKind::Observer));
+        observer_node.kind = NodeKind::Observer;
+        observer_node.label = Some(observer.label.clone());
+
+        for record in directory.records() {
+            if record.network_info.is_none() && record.peer_info.is_none() {
+                continue;
+            }
+            let peer_id = record.peer_id.to_string();
+            if peer_id == observer_id {
+                continue;
+            }
+            self.nodes
+                .entry(peer_id.clone())
+                .or_insert_with(|| GraphNode::new(peer_id.clone(), NodeKind::Peer))
+                .merge(record);
+            self.edges.insert(
+                (observer_id.clone(), peer_id.clone()),
+                GraphEdge {
+                    source: observer_id.clone(),
+                    target: peer_id,
+                    peer_score: record.peer_score(),
+                    network_info: record.network_info.is_some(),
+                    peer_info: record.peer_info.is_some(),
+                },
+            );
+        }
+
+        self.update_peer_scores();
+        self
+    }
+
+    fn update_peer_scores(&mut self) {
+        let mut scores: BTreeMap<&str, (f64, usize)> = BTreeMap::new();
+        for edge in self.edges.values() {
+            if let Some(score) = edge.peer_score {
+                let entry = scores.entry(&edge.target).or_default();
+                entry.0 += score;
+                entry.1 += 1;
+            }
+        }
+        for node in self.nodes.values_mut() {
+            node.peer_score = scores
+                .get(node.id.as_str())
+                .map(|(sum, count)| sum / *count as f64);
+        }
+    }
+
+    /// The nodes ordered by ID.
+    pub fn nodes(&self) -> impl Iterator<Item = &GraphNode> {
+        self.nodes.values()
+    }
+
+    /// The edges ordered by source and target.
+    pub fn edges(&self) -> impl Iterator<Item = &GraphEdge> {
+        self.edges.values()
+    }
+
+    /// The graph in the node-link format. Serialize it with `serde` to get the JSON document.
+    pub fn node_link(&self) -> NodeLinkGraph {
+        NodeLinkGraph {
+            directed: true,
+            multigraph: false,
+            nodes: self.nodes().cloned().collect(),
+            links: self.edges().cloned().collect(),
+        }
+    }
+
+    /// Write the graph in the Graphviz DOT language. Observers are drawn as boxes and
+    /// uncooperative peers in red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph quilibrium {\n");
+        for node in self.nodes() {
+            let mut attributes = vec![("label", node.label.clone().unwrap_or(node.id.clone()))];
+            if node.kind == NodeKind::Observer {
+                attributes.push(("shape", "box".to_string()));
+            }
+            if node.cooperative == Some(false) {
+                attributes.push(("color", "red".to_string()));
+            }
+            attributes.extend(node_attributes(node));
+            writeln!(
+                dot,
+                "  {} [{}];",
+                dot_id(&node.id),
+                dot_attributes(&attributes)
+            )
+            .expect("writing to a string doesn't fail");
+        }
+        for edge in self.edges() {
+            writeln!(
+                dot,
+                "  {} -> {} [{}];",
+                dot_id(&edge.source),
+                dot_id(&edge.target),
+                dot_attributes(&edge_attributes(edge))
+            )
+            .expect("writing to a string doesn't fail");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Write the graph as a GraphML document.
+    pub fn to_graphml(&self) -> String {
+        const NODE_KEYS: [(&str, &str); 8] = [
+            ("kind", "string"),
+            ("label", "string"),
+            ("peer_score", "double"),
+            ("version", "string"),
+            ("cooperative", "boolean"),
+            ("cores", "int"),
+            ("memory", "long"),
+            ("storage", "long"),
+        ];
+        const EDGE_KEYS: [(&str, &str); 3] = [
+            ("peer_score", "double"),
+            ("network_info", "boolean"),
+            ("peer_info", "boolean"),
+        ];
+
+        let mut xml = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#,
+            "\n"
+        ));
+        for (name, kind) in NODE_KEYS {
+            writeln!(
+                xml,
+                r#"  <key id="node_{name}" for="node" attr.name="{name}" attr.type="{kind}"/>"#
+            )
+            .expect("writing to a string doesn't fail");
+        }
+        for (name, kind) in EDGE_KEYS {
+            writeln!(
+                xml,
+                r#"  <key id="edge_{name}" for="edge" attr.name="{name}" attr.type="{kind}"/>"#
+            )
+            .expect("writing to a string doesn't fail");
+        }
+        xml.push_str("  <graph id=\"quilibrium\" edgedefault=\"directed\">\n");
+
+        for node in self.nodes() {
+            writeln!(xml, r#"    <node id="{}">"#, xml_escape(&node.id))
+                .expect("writing to a string doesn't fail");
+            let kind = match node.kind {
+                NodeKind::Observer => "observer",
+                NodeKind::Peer => "peer",
+            };
+            let mut attributes = vec![("kind", kind.to_string())];
+            attributes.extend(node.label.clone().map(|label| ("label", label)));
+            attributes.extend(node_attributes(node));
+            write_graphml_data(&mut xml, "node", &attributes);
+            xml.push_str("    </node>\n");
+        }
+        for edge in self.edges() {
+            writeln!(
+                xml,
+                r#"    <edge source="{}" target="{}">"#,
+                xml_escape(&edge.source),
+                xml_escape(&edge.target)
+            )
+            .expect("writing to a string doesn't fail");
+            write_graphml_data(&mut xml, "edge", &edge_attributes(edge));
+            xml.push_str("    </edge>\n");
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+/// A graph in the node-link format used by NetworkX and D3.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NodeLinkGraph {
+    /// Always `true`, edges point from observers to peers.
+    pub directed: bool,
+    /// Always `false`, there is at most one edge between two nodes.
+    pub multigraph: bool,
+    /// The nodes.
+    pub nodes: Vec<GraphNode>,
+    /// The edges.
+    pub links: Vec<GraphEdge>,
+}
+
+/// The known optional attributes of a node as strings.
+fn node_attributes(node: &GraphNode) -> Vec<(&'static str, String)> {
+    [
+        ("peer_score", node.peer_score.map(double)),
+        ("version", node.version.map(|version| version.to_string())),
+        (
+            "cooperative",
+            node.cooperative.map(|cooperative| cooperative.to_string()),
+        ),
+        ("cores", node.cores.map(|cores| cores.to_string())),
+        ("memory", node.memory.map(|memory| memory.to_string())),
+        ("storage", node.storage.map(|storage| storage.to_string())),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((name, value?)))
+    .collect()
+}
+
+fn edge_attributes(edge: &GraphEdge) -> Vec<(&'static str, String)> {
+    let mut attributes = vec![];
+    attributes.extend(edge.peer_score.map(|score| ("peer_score", double(score))));
+    attributes.push(("network_info", edge.network_info.to_string()));
+    attributes.push(("peer_info", edge.peer_info.to_string()));
+    attributes
+}
+
+/// A float in the `xs:double` lexical form GraphML expects, which spells non-finite values "INF",
+/// "-INF" and "NaN".
+fn double(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "INF" } else { "-INF" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn dot_id(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn dot_attributes(attributes: &[(&str, String)]) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| format!("{name}={}", dot_id(value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn write_graphml_data(xml: &mut String, element: &str, attributes: &[(&str, String)]) {
+    for (name, value) in attributes {
+        writeln!(
+            xml,
+            r#"      <data key="{element}_{name}">{}</data>"#,
+            xml_escape(value)
+        )
+        .expect("writing to a string doesn't fail");
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::PeerInfoResponse;
+    use crate::test_utils::{network_info, peer_info, random_peer_id};
+
+    #[test]
+    fn merge_views() {
+        let (a, b, c) = (random_peer_id(), random_peer_id(), random_peer_id());
+        let version = ProtocolVersion::new(1, 2, 0);
+
+        let first = PeerDirectory::new()
+            .with_network_info(network_info(&[(b, 10.0), (c, -5.0)]))
+            .with_peer_info(PeerInfoResponse {
+                peers: vec![],
+                uncooperative_peers: vec![peer_info(c, version)],
+            });
+        let second = PeerDirectory::new().with_network_info(network_info(&[(b, 20.0)]));
+
+        let graph = PeerGraph::new()
+            .with_view(&Observer::new("http://first:8337").peer_id(Some(a)), &first)
+            .with_view(&Observer::new("http://second:8337"), &second);
+
+        let nodes: Vec<(&str, NodeKind)> = graph
+            .nodes()
+            .map(|node| (node.id.as_str(), node.kind))
+            .collect();
+        assert_eq!(nodes.len(), 4);
+        assert!(nodes.contains(&(a.to_string().as_str(), NodeKind::Observer)));
+        assert!(nodes.contains(&("http://second:8337", NodeKind::Observer)));
+
+        let node_b = graph.nodes().find(|node| node.id == b.to_string()).unwrap();
+        assert_eq!(node_b.peer_score, Some(15.0));
+        let node_c = graph.nodes().find(|node| node.id == c.to_string()).unwrap();
+        assert_eq!(node_c.version, Some(version));
+        assert_eq!(node_c.cooperative, Some(false));
+        assert_eq!(graph.edges().count(), 3);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph quilibrium {\n"));
+        assert!(dot.contains(&format!("\"http://second:8337\" -> \"{b}\"")));
+        assert!(dot.contains("color=\"red\""));
+
+        let graphml = graph.to_graphml();
+        assert!(graphml.contains(&format!("<edge source=\"{a}\" target=\"{c}\">")));
+        assert!(graphml.contains("<data key=\"node_version\">1.2.0</data>"));
+
+        let node_link = graph.node_link();
+        assert_eq!(node_link.nodes.len(), 4);
+        assert_eq!(node_link.links.len(), 3);
+    }
+
+    #[test]
+    fn non_finite_scores() {
+        assert_eq!(double(-1.5), "-1.5");
+        assert_eq!(double(f64::INFINITY), "INF");
+        assert_eq!(double(f64::NEG_INFINITY), "-INF");
+        assert_eq!(double(f64::NAN), "NaN");
+
+        let directory =
+            PeerDirectory::new().with_network_info(network_info(&[(random_peer_id(), f64::NAN)]));
+        let graph = PeerGraph::new().with_view(&Observer::new("http://node:8337"), &directory);
+        let graphml = graph.to_graphml();
+        assert!(graphml.contains(r#"<data key="edge_peer_score">NaN</data>"#));
+        assert!(!graphml.contains(">nan<"));
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(dot_id("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(xml_escape("<a & 'b'>"), "&lt;a &amp; &apos;b&apos;&gt;");
+    }
+}
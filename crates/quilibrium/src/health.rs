@@ -0,0 +1,678 @@
+//! Check the health of a node against thresholds, with Nagios compatible statuses and perfdata.
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::metrics::NodeMetrics;
+use crate::node::FrameFilter;
+use crate::rewards::RewardSample;
+
+/// The status of a check, or of a whole report. The exit code of a Nagios plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HealthStatus {
+    /// All thresholds are met.
+    Ok,
+    /// A warning threshold is breached.
+    Warning,
+    /// A critical threshold is breached.
+    Critical,
+    /// The status couldn't be determined, e.g. because a call to the node failed.
+    Unknown,
+}
+
+impl HealthStatus {
+    /// The exit code of a Nagios plugin with this status: 0, 1, 2 or 3.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Ok => 0,
+            Self::Warning => 1,
+            Self::Critical => 2,
+            Self::Unknown => 3,
+        }
+    }
+
+    /// The more severe of two statuses. Critical is more severe than warning, which is more
+    /// severe than unknown, which is more severe than OK.
+    pub fn worst(self, other: Self) -> Self {
+        if other.severity() > self.severity() {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn severity(&self) -> u8 {
+        match self {
+            Self::Ok => 0,
+            Self::Unknown => 1,
+            Self::Warning => 2,
+            Self::Critical => 3,
+        }
+    }
+}
+
+impl Display for HealthStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "OK"),
+            Self::Warning => write!(f, "WARNING"),
+            Self::Critical => write!(f, "CRITICAL"),
+            Self::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+/// A warning and a critical threshold. A check is skipped if neither is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Threshold<T> {
+    /// The threshold for a warning.
+    pub warning: Option<T>,
+    /// The threshold for a critical status.
+    pub critical: Option<T>,
+}
+
+impl<T: PartialOrd + Copy> Threshold<T> {
+    /// Create a threshold.
+    pub fn new(warning: Option<T>, critical: Option<T>) -> Self {
+        Self { warning, critical }
+    }
+
+    /// Whether the warning or the critical threshold is set.
+    pub fn is_set(&self) -> bool {
+        self.warning.is_some() || self.critical.is_some()
+    }
+
+    /// The status of a value that must not be above the threshold.
+    pub fn status_above(&self, value: T) -> HealthStatus {
+        self.status(|threshold| value > threshold)
+    }
+
+    /// The status of a value that must not be below the threshold.
+    pub fn status_below(&self, value: T) -> HealthStatus {
+        self.status(|threshold| value < threshold)
+    }
+
+    fn status(&self, breached: impl Fn(T) -> bool) -> HealthStatus {
+        if self.critical.map_or(false, &breached) {
+            HealthStatus::Critical
+        } else if self.warning.map_or(false, &breached) {
+            HealthStatus::Warning
+        } else {
+            HealthStatus::Ok
+        }
+    }
+}
+
+/// The thresholds of the checks. Checks without a threshold are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct HealthOptions {
+    /// The maximum number of ceremony application frames the node may be behind its peers.
+    pub max_sync_lag: Threshold<u64>,
+    /// The minimum number of cooperative peers.
+    pub min_cooperative_peers: Threshold<usize>,
+    /// The maximum age of the ceremony application head frame.
+    pub max_frame_age: Threshold<Duration>,
+    /// The maximum duration of a single request call to the node. Head frame searches are left out
+    /// as they take multiple requests.
+    pub max_rpc_latency: Threshold<Duration>,
+    /// The window within which the token balance must increase.
+    pub balance_window: Threshold<Duration>,
+}
+
+impl HealthOptions {
+    /// Create options without thresholds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum sync lag in frames.
+    pub fn max_sync_lag(mut self, threshold: Threshold<u64>) -> Self {
+        self.max_sync_lag = threshold;
+        self
+    }
+
+    /// Set the minimum number of cooperative peers.
+    pub fn min_cooperative_peers(mut self, threshold: Threshold<usize>) -> Self {
+        self.min_cooperative_peers = threshold;
+        self
+    }
+
+    /// Set the maximum age of the head frame.
+    pub fn max_frame_age(mut self, threshold: Threshold<Duration>) -> Self {
+        self.max_frame_age = threshold;
+        self
+    }
+
+    /// Set the maximum duration of a call to the node.
+    pub fn max_rpc_latency(mut self, threshold: Threshold<Duration>) -> Self {
+        self.max_rpc_latency = threshold;
+        self
+    }
+
+    /// Set the window within which the token balance must increase.
+    pub fn balance_window(mut self, threshold: Threshold<Duration>) -> Self {
+        self.balance_window = threshold;
+        self
+    }
+}
+
+/// A performance data point in the Nagios plugin format, e.g. `sync_lag=3;10;100;0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerfData {
+    /// The label of the value.
+    pub label: &'static str,
+    /// The value.
+    pub value: f64,
+    /// The unit of measurement, e.g. "s", or empty.
+    pub unit: &'static str,
+    /// The warning range, e.g. "10" or "8:".
+    pub warning: Option<String>,
+    /// The critical range.
+    pub critical: Option<String>,
+    /// The minimum value.
+    pub min: Option<f64>,
+}
+
+impl Display for PerfData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        write!(
+            f,
+            "{}={}{};{};{};{}",
+            self.label,
+            self.value,
+            self.unit,
+            optional(&self.warning),
+            optional(&self.critical),
+            optional(&self.min.map(|min| min.to_string())),
+        )
+    }
+}
+
+/// The outcome of a single check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthCheck {
+    /// The name of the check, e.g. "sync_lag".
+    pub name: &'static str,
+    /// The status of the check.
+    pub status: HealthStatus,
+    /// A short description of the outcome, e.g. "sync lag 3 frames".
+    pub message: String,
+    /// The measured value, if any.
+    pub perfdata: Option<PerfData>,
+}
+
+impl HealthCheck {
+    fn new(name: &'static str, status: HealthStatus, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+            perfdata: None,
+        }
+    }
+
+    fn with_perfdata(mut self, perfdata: PerfData) -> Self {
+        self.perfdata = Some(perfdata);
+        self
+    }
+}
+
+/// The health of a node: the outcome of each check from its metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    /// The time the metrics were collected.
+    pub checked_at: DateTime<Utc>,
+    /// The checks in the order they ran.
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    /// Check the metrics of a node against the thresholds of the options.
+    ///
+    /// The balance is checked against the latest sample in `balance_history` that is at least
+    /// as old as the window. The history must be in chronological order. If no call to the node
+    /// succeeded, only the critical reachability check is reported.
+    pub fn new(
+        metrics: &NodeMetrics,
+        balance_history: &[RewardSample],
+        options: &HealthOptions,
+    ) -> Self {
+        let checked_at = metrics.collected_at;
+        let mut checks = vec![check_calls(metrics)];
+        if !metrics.is_up() {
+            return Self { checked_at, checks };
+        }
+        if options.max_sync_lag.is_set() {
+            checks.push(check_sync_lag(metrics, &options.max_sync_lag));
+        }
+        if options.min_cooperative_peers.is_set() {
+            checks.push(check_cooperative_peers(
+                metrics,
+                &options.min_cooperative_peers,
+            ));
+        }
+        if options.max_frame_age.is_set() {
+            checks.push(check_frame_age(metrics, &options.max_frame_age));
+        }
+        if options.max_rpc_latency.is_set() {
+            checks.push(check_rpc_latency(metrics, &options.max_rpc_latency));
+        }
+        if options.balance_window.is_set() {
+            checks.push(check_balance(
+                metrics,
+                balance_history,
+                &options.balance_window,
+            ));
+        }
+        Self { checked_at, checks }
+    }
+
+    /// The most severe status of the checks.
+    pub fn status(&self) -> HealthStatus {
+        self.checks
+            .iter()
+            .fold(HealthStatus::Ok, |status, check| status.worst(check.status))
+    }
+
+    /// A one line summary with perfdata in the Nagios plugin format, e.g.
+    /// `QUILIBRIUM WARNING - ...; sync lag 12 frames (WARNING) | sync_lag=12;10;100;0`.
+    pub fn summary(&self) -> String {
+        let messages: Vec<String> = self
+            .checks
+            .iter()
+            .map(|check| match check.status {
+                HealthStatus::Ok => check.message.clone(),
+                status => format!("{} ({status})", check.message),
+            })
+            .collect();
+        let perfdata: Vec<String> = self
+            .checks
+            .iter()
+            .filter_map(|check| check.perfdata.as_ref())
+            .map(ToString::to_string)
+            .collect();
+        let mut summary = format!("QUILIBRIUM {} - {}", self.status(), messages.join("; "));
+        if !perfdata.is_empty() {
+            summary = format!("{summary} | {}", perfdata.join(" "));
+        }
+        summary
+    }
+}
+
+/// Critical if no call to the node succeeded, unknown if some failed.
+fn check_calls(metrics: &NodeMetrics) -> HealthCheck {
+    let failed: Vec<String> = metrics
+        .rpc
+        .iter()
+        .filter_map(|timing| Some(format!("{}: {}", timing.method, timing.error.as_ref()?)))
+        .collect();
+    let (status, message) = if !metrics.is_up() {
+        (
+            HealthStatus::Critical,
+            format!("node unreachable: {}", failed.join(", ")),
+        )
+    } else if !failed.is_empty() {
+        (
+            HealthStatus::Unknown,
+            format!("failed calls: {}", failed.join(", ")),
+        )
+    } else {
+        (HealthStatus::Ok, format!("{} calls", metrics.rpc.len()))
+    };
+    HealthCheck::new("calls", status, message)
+}
+
+fn check_sync_lag(metrics: &NodeMetrics, threshold: &Threshold<u64>) -> HealthCheck {
+    let lag = metrics
+        .sync_status
+        .as_ref()
+        .and_then(|status| status.filter(&FrameFilter::CeremonyApplication))
+        .and_then(|status| status.lag());
+    let Some(lag) = lag else {
+        return HealthCheck::new("sync_lag", HealthStatus::Unknown, "sync lag unknown");
+    };
+    HealthCheck::new(
+        "sync_lag",
+        threshold.status_above(lag),
+        format!("sync lag {lag} frames"),
+    )
+    .with_perfdata(PerfData {
+        label: "sync_lag",
+        value: lag as f64,
+        unit: "",
+        warning: threshold.warning.map(|warning| warning.to_string()),
+        critical: threshold.critical.map(|critical| critical.to_string()),
+        min: Some(0.0),
+    })
+}
+
+fn check_cooperative_peers(metrics: &NodeMetrics, threshold: &Threshold<usize>) -> HealthCheck {
+    let Some((cooperative, _)) = metrics.peers else {
+        return HealthCheck::new(
+            "cooperative_peers",
+            HealthStatus::Unknown,
+            "cooperative peers unknown",
+        );
+    };
+    HealthCheck::new(
+        "cooperative_peers",
+        threshold.status_below(cooperative),
+        format!("{cooperative} cooperative peers"),
+    )
+    .with_perfdata(PerfData {
+        label: "cooperative_peers",
+        value: cooperative as f64,
+        unit: "",
+        warning: threshold.warning.map(|warning| format!("{warning}:")),
+        critical: threshold.critical.map(|critical| format!("{critical}:")),
+        min: Some(0.0),
+    })
+}
+
+fn check_frame_age(metrics: &NodeMetrics, threshold: &Threshold<Duration>) -> HealthCheck {
+    let head = metrics.head_frame(&FrameFilter::CeremonyApplication);
+    let Some(head) = head else {
+        let status = if metrics.is_up() && metrics.sync_status.is_some() {
+            threshold.status_above(Duration::MAX)
+        } else {
+            HealthStatus::Unknown
+        };
+        return HealthCheck::new("frame_age", status, "no head frame");
+    };
+    let age = (metrics.collected_at - head.timestamp)
+        .to_std()
+        .unwrap_or_default();
+    HealthCheck::new(
+        "frame_age",
+        threshold.status_above(age),
+        format!("head frame {} is {}s old", head.frame_number, age.as_secs()),
+    )
+    .with_perfdata(seconds_perfdata("frame_age", age, threshold))
+}
+
+/// The slowest successful call, leaving out the head frame searches, which take a request per
+/// frame looked up.
+fn check_rpc_latency(metrics: &NodeMetrics, threshold: &Threshold<Duration>) -> HealthCheck {
+    let slowest = metrics
+        .rpc
+        .iter()
+        .filter(|timing| timing.error.is_none() && timing.method != "head_frame")
+        .max_by_key(|timing| timing.duration);
+    let Some(slowest) = slowest else {
+        return HealthCheck::new("rpc_latency", HealthStatus::Unknown, "no successful calls");
+    };
+    HealthCheck::new(
+        "rpc_latency",
+        threshold.status_above(slowest.duration),
+        format!(
+            "slowest call {} took {:.3}s",
+            slowest.method,
+            slowest.duration.as_secs_f64()
+        ),
+    )
+    .with_perfdata(seconds_perfdata("rpc_latency", slowest.duration, threshold))
+}
+
+/// Warning or critical if the balance didn't increase since the latest sample at least as old as
+/// the warning or critical window.
+fn check_balance(
+    metrics: &NodeMetrics,
+    history: &[RewardSample],
+    threshold: &Threshold<Duration>,
+) -> HealthCheck {
+    let Some(token_info) = &metrics.token_info else {
+        return HealthCheck::new("balance", HealthStatus::Unknown, "balance unknown");
+    };
+    let sample_before = |window: Duration| {
+        let window = chrono::Duration::from_std(window).ok()?;
+        let before = metrics.collected_at.checked_sub_signed(window)?;
+        history
+            .iter()
+            .rev()
+            .find(|sample| sample.sampled_at <= before)
+    };
+    let increased = |window: Option<Duration>| {
+        let sample = sample_before(window?)?;
+        Some(token_info.owned_tokens > sample.owned_tokens)
+    };
+    let shortest = [threshold.warning, threshold.critical]
+        .into_iter()
+        .flatten()
+        .min()
+        .expect("the threshold is set");
+    let Some(sample) = sample_before(shortest) else {
+        return HealthCheck::new(
+            "balance",
+            HealthStatus::Unknown,
+            format!("no balance sample older than {}s", shortest.as_secs()),
+        );
+    };
+
+    let status = if increased(threshold.critical) == Some(false) {
+        HealthStatus::Critical
+    } else if increased(threshold.warning) == Some(false) {
+        HealthStatus::Warning
+    } else {
+        HealthStatus::Ok
+    };
+    let increase = token_info
+        .owned_tokens
+        .checked_sub(sample.owned_tokens)
+        .unwrap_or_default();
+    let age = (metrics.collected_at - sample.sampled_at)
+        .to_std()
+        .unwrap_or_default();
+    HealthCheck::new(
+        "balance",
+        status,
+        format!("balance increased by {increase} QUIL in {}s", age.as_secs()),
+    )
+    .with_perfdata(PerfData {
+        label: "balance_increase",
+        value: increase.to_quil_f64(),
+        unit: "",
+        warning: None,
+        critical: None,
+        min: Some(0.0),
+    })
+}
+
+fn seconds_perfdata(
+    label: &'static str,
+    value: Duration,
+    threshold: &Threshold<Duration>,
+) -> PerfData {
+    let seconds = |duration: Duration| duration.as_secs_f64().to_string();
+    PerfData {
+        label,
+        value: value.as_secs_f64(),
+        unit: "s",
+        warning: threshold.warning.map(seconds),
+        critical: threshold.critical.map(seconds),
+        min: Some(0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ruint::aliases::U256;
+
+    use super::*;
+    use crate::metrics::RpcTiming;
+    use crate::node::{ClockFrame, TokenInfo};
+    use crate::oblivious_transfer_units::ObliviousTransferUnits;
+    use crate::sync_status::{FilterSyncStatus, SyncStatus};
+
+    fn quil(quil: u64) -> ObliviousTransferUnits {
+        ObliviousTransferUnits::from_quil_tokens(U256::from(quil)).unwrap()
+    }
+
+    fn metrics(collected_at: DateTime<Utc>) -> NodeMetrics {
+        NodeMetrics {
+            collected_at,
+            token_info: Some(TokenInfo {
+                confirmed_token_supply: quil(1_000),
+                unconfirmed_token_supply: quil(1_000),
+                owned_tokens: quil(10),
+            }),
+            head_frames: vec![ClockFrame {
+                filter: FrameFilter::CeremonyApplication,
+                frame_number: 95,
+                timestamp: collected_at - chrono::Duration::seconds(30),
+                difficulty: 0,
+            }],
+            peers: Some((5, 2)),
+            versions: None,
+            scores: None,
+            sync_status: Some(SyncStatus {
+                observed_at: collected_at,
+                filters: vec![FilterSyncStatus {
+                    filter: FrameFilter::CeremonyApplication,
+                    local_head: Some(95),
+                    network_max: Some(100),
+                    network_median: Some(98),
+                    reporting_peers: 5,
+                }],
+            }),
+            rpc: vec![
+                RpcTiming {
+                    method: "token_info",
+                    filter: None,
+                    duration: Duration::from_millis(1_500),
+                    error: None,
+                },
+                RpcTiming {
+                    method: "head_frame",
+                    filter: Some(FrameFilter::CeremonyApplication),
+                    duration: Duration::from_secs(10),
+                    error: None,
+                },
+            ],
+        }
+    }
+
+    fn options() -> HealthOptions {
+        HealthOptions::new()
+            .max_sync_lag(Threshold::new(Some(10), Some(100)))
+            .min_cooperative_peers(Threshold::new(Some(8), Some(1)))
+            .max_frame_age(Threshold::new(
+                Some(Duration::from_secs(60)),
+                Some(Duration::from_secs(300)),
+            ))
+            .max_rpc_latency(Threshold::new(Some(Duration::from_secs(1)), None))
+    }
+
+    #[test]
+    fn thresholds() {
+        let threshold = Threshold::new(Some(10), Some(100));
+        assert_eq!(threshold.status_above(10), HealthStatus::Ok);
+        assert_eq!(threshold.status_above(11), HealthStatus::Warning);
+        assert_eq!(threshold.status_above(101), HealthStatus::Critical);
+        let minimum = Threshold::new(Some(8), Some(1));
+        assert_eq!(minimum.status_below(8), HealthStatus::Ok);
+        assert_eq!(minimum.status_below(5), HealthStatus::Warning);
+        assert_eq!(minimum.status_below(0), HealthStatus::Critical);
+        assert!(!Threshold::<u64>::default().is_set());
+        assert_eq!(
+            HealthStatus::Unknown.worst(HealthStatus::Warning),
+            HealthStatus::Warning
+        );
+        assert_eq!(
+            HealthStatus::Unknown.worst(HealthStatus::Ok),
+            HealthStatus::Unknown
+        );
+        assert_eq!(HealthStatus::Critical.exit_code(), 2);
+    }
+
+    #[test]
+    fn report() {
+        let now = Utc::now();
+        let report = HealthReport::new(&metrics(now), &[], &options());
+        let statuses: Vec<(&str, HealthStatus)> = report
+            .checks
+            .iter()
+            .map(|check| (check.name, check.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("calls", HealthStatus::Ok),
+                ("sync_lag", HealthStatus::Ok),
+                ("cooperative_peers", HealthStatus::Warning),
+                ("frame_age", HealthStatus::Ok),
+                ("rpc_latency", HealthStatus::Warning),
+            ]
+        );
+        assert_eq!(report.status(), HealthStatus::Warning);
+        assert_eq!(
+            report.summary(),
+            "QUILIBRIUM WARNING - 2 calls; sync lag 5 frames; 5 cooperative peers (WARNING); \
+             head frame 95 is 30s old; slowest call token_info took 1.500s (WARNING) | \
+             sync_lag=5;10;100;0 cooperative_peers=5;8:;1:;0 frame_age=30s;60;300;0 \
+             rpc_latency=1.5s;1;;0"
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        let metrics = NodeMetrics::unreachable(Duration::from_secs(1), "refused".to_string());
+        let report = HealthReport::new(&metrics, &[], &options());
+        assert_eq!(report.status(), HealthStatus::Critical);
+        assert_eq!(
+            report.checks[0].message,
+            "node unreachable: connect: refused"
+        );
+        assert_eq!(report.checks.len(), 1);
+        assert_eq!(
+            report.summary(),
+            "QUILIBRIUM CRITICAL - node unreachable: connect: refused (CRITICAL)"
+        );
+    }
+
+    #[test]
+    fn balance() {
+        let now = Utc::now();
+        let sample = |hours: i64, owned: u64| RewardSample {
+            sampled_at: now - chrono::Duration::hours(hours),
+            owned_tokens: quil(owned),
+            confirmed_token_supply: quil(1_000),
+        };
+        let options = HealthOptions::new().balance_window(Threshold::new(
+            Some(Duration::from_secs(60 * 60)),
+            Some(Duration::from_secs(6 * 60 * 60)),
+        ));
+        let status = |history: &[RewardSample]| {
+            let report = HealthReport::new(&metrics(now), history, &options);
+            report.checks.last().unwrap().status
+        };
+
+        assert_eq!(status(&[]), HealthStatus::Unknown);
+        assert_eq!(status(&[sample(7, 5), sample(2, 8)]), HealthStatus::Ok);
+        assert_eq!(
+            status(&[sample(7, 5), sample(2, 10)]),
+            HealthStatus::Warning
+        );
+        assert_eq!(
+            status(&[sample(7, 10), sample(2, 10)]),
+            HealthStatus::Critical
+        );
+        // Not enough history for the critical window.
+        assert_eq!(status(&[sample(2, 10)]), HealthStatus::Warning);
+
+        let report = HealthReport::new(&metrics(now), &[sample(2, 8)], &options);
+        assert_eq!(
+            report
+                .checks
+                .last()
+                .unwrap()
+                .perfdata
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "balance_increase=2;;;0"
+        );
+    }
+}
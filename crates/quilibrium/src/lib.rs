@@ -42,7 +42,33 @@ mod quilibrium_pb {
     }
 }
 
+pub mod capacity;
+pub mod cooperation;
+pub mod freshness;
+#[cfg(feature = "geoip")]
+pub mod geoip;
+pub mod graph;
+pub mod health;
+pub mod manifest_stats;
+pub mod metrics;
+pub mod multiaddrs;
 pub mod node;
 pub mod oblivious_transfer_units;
+pub mod overview;
+pub mod peer_directory;
+pub mod peer_scores;
+pub mod probe;
+pub mod protocol_version;
+pub mod rewards;
+pub mod snapshot;
+mod stats;
+pub mod supply;
+pub mod sync_status;
+pub mod units;
+pub mod version_distribution;
+
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 
 pub use oblivious_transfer_units::ObliviousTransferUnits;
+pub use protocol_version::ProtocolVersion;
@@ -0,0 +1,410 @@
+//! Statistics of the self-reported benchmarks in peer manifests and detection of implausible
+//! reports.
+
+use crate::node::{PeerId, PeerManifest, PeerManifestsResponse};
+use crate::stats::percentile;
+
+/// The percentiles reported in the metric statistics.
+pub const METRIC_PERCENTILES: [f64; 5] = [10.0, 25.0, 50.0, 75.0, 90.0];
+
+/// Peers reporting more memory than this, 100 TiB, are flagged.
+pub const MAX_PLAUSIBLE_MEMORY: u64 = 100 << 40;
+
+/// A numeric field of a peer manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum ManifestMetric {
+    /// The time (ms) of the VDF test divided by its difficulty.
+    VdfPerDifficulty,
+    /// The time (ms) of a KZG commitment for a 16 degree polynomial.
+    Commit16,
+    /// The time (ms) of a KZG commitment for a 128 degree polynomial.
+    Commit128,
+    /// The time (ms) of a KZG commitment for a 1024 degree polynomial.
+    Commit1024,
+    /// The time (ms) of a KZG commitment for a 65536 degree polynomial.
+    Commit65536,
+    /// The time (ms) of a KZG proof for a 16 degree polynomial.
+    Proof16,
+    /// The time (ms) of a KZG proof for a 128 degree polynomial.
+    Proof128,
+    /// The time (ms) of a KZG proof for a 1024 degree polynomial.
+    Proof1024,
+    /// The time (ms) of a KZG proof for a 65536 degree polynomial.
+    Proof65536,
+    /// The number of logical cores.
+    Cores,
+    /// The memory in bytes.
+    Memory,
+    /// The storage in bytes.
+    Storage,
+}
+
+impl ManifestMetric {
+    /// All the metrics.
+    pub const ALL: [ManifestMetric; 12] = [
+        ManifestMetric::VdfPerDifficulty,
+        ManifestMetric::Commit16,
+        ManifestMetric::Commit128,
+        ManifestMetric::Commit1024,
+        ManifestMetric::Commit65536,
+        ManifestMetric::Proof16,
+        ManifestMetric::Proof128,
+        ManifestMetric::Proof1024,
+        ManifestMetric::Proof65536,
+        ManifestMetric::Cores,
+        ManifestMetric::Memory,
+        ManifestMetric::Storage,
+    ];
+
+    /// The KZG commitment timings from the lowest to the highest degree.
+    pub const COMMITS: [ManifestMetric; 4] = [
+        ManifestMetric::Commit16,
+        ManifestMetric::Commit128,
+        ManifestMetric::Commit1024,
+        ManifestMetric::Commit65536,
+    ];
+
+    /// The KZG proof timings from the lowest to the highest degree.
+    pub const PROOFS: [ManifestMetric; 4] = [
+        ManifestMetric::Proof16,
+        ManifestMetric::Proof128,
+        ManifestMetric::Proof1024,
+        ManifestMetric::Proof65536,
+    ];
+
+    /// The snake case name of the metric.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ManifestMetric::VdfPerDifficulty => "vdf_per_difficulty",
+            ManifestMetric::Commit16 => "commit_16",
+            ManifestMetric::Commit128 => "commit_128",
+            ManifestMetric::Commit1024 => "commit_1024",
+            ManifestMetric::Commit65536 => "commit_65536",
+            ManifestMetric::Proof16 => "proof_16",
+            ManifestMetric::Proof128 => "proof_128",
+            ManifestMetric::Proof1024 => "proof_1024",
+            ManifestMetric::Proof65536 => "proof_65536",
+            ManifestMetric::Cores => "cores",
+            ManifestMetric::Memory => "memory",
+            ManifestMetric::Storage => "storage",
+        }
+    }
+
+    /// Whether the metric is a timing where lower is better.
+    pub fn is_timing(&self) -> bool {
+        !matches!(
+            self,
+            ManifestMetric::Cores | ManifestMetric::Memory | ManifestMetric::Storage
+        )
+    }
+
+    /// The value of the metric in a manifest. Returns `None` for the VDF timing if the difficulty
+    /// is zero.
+    pub fn value(&self, manifest: &PeerManifest) -> Option<f64> {
+        let value = match self {
+            ManifestMetric::VdfPerDifficulty => {
+                if manifest.difficulty == 0 {
+                    return None;
+                }
+                manifest.difficulty_metric as f64 / manifest.difficulty as f64
+            }
+            ManifestMetric::Commit16 => manifest.commit_16_metric as f64,
+            ManifestMetric::Commit128 => manifest.commit_128_metric as f64,
+            ManifestMetric::Commit1024 => manifest.commit_1024_metric as f64,
+            ManifestMetric::Commit65536 => manifest.commit_65536_metric as f64,
+            ManifestMetric::Proof16 => manifest.proof_16_metric as f64,
+            ManifestMetric::Proof128 => manifest.proof_128_metric as f64,
+            ManifestMetric::Proof1024 => manifest.proof_1024_metric as f64,
+            ManifestMetric::Proof65536 => manifest.proof_65536_metric as f64,
+            ManifestMetric::Cores => manifest.cores as f64,
+            ManifestMetric::Memory => manifest.memory as f64,
+            ManifestMetric::Storage => manifest.storage as f64,
+        };
+        Some(value)
+    }
+}
+
+impl std::fmt::Display for ManifestMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A self-report in a peer manifest that is physically implausible.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ManifestAnomaly {
+    /// The peer reports zero cores.
+    ZeroCores,
+    /// The peer reports more memory than [`MAX_PLAUSIBLE_MEMORY`].
+    ExcessiveMemory(u64),
+    /// A timing is zero or negative.
+    NonPositiveTiming(ManifestMetric),
+    /// The timing for a higher degree polynomial is lower than for a lower degree polynomial.
+    FasterAtHigherDegree {
+        /// The metric for the lower degree.
+        lower: ManifestMetric,
+        /// The metric for the higher degree.
+        higher: ManifestMetric,
+    },
+}
+
+impl std::fmt::Display for ManifestAnomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestAnomaly::ZeroCores => write!(f, "zero cores"),
+            ManifestAnomaly::ExcessiveMemory(memory) => write!(f, "{memory} bytes of memory"),
+            ManifestAnomaly::NonPositiveTiming(metric) => write!(f, "non-positive {metric}"),
+            ManifestAnomaly::FasterAtHigherDegree { lower, higher } => {
+                write!(f, "{higher} faster than {lower}")
+            }
+        }
+    }
+}
+
+impl ManifestAnomaly {
+    /// Find the implausible self-reports in a manifest.
+    pub fn detect(manifest: &PeerManifest) -> Vec<Self> {
+        let mut anomalies = Vec::new();
+        if manifest.cores == 0 {
+            anomalies.push(ManifestAnomaly::ZeroCores);
+        }
+        if manifest.memory > MAX_PLAUSIBLE_MEMORY {
+            anomalies.push(ManifestAnomaly::ExcessiveMemory(manifest.memory));
+        }
+        for metric in ManifestMetric::ALL.iter().filter(|m| m.is_timing()) {
+            if metric.value(manifest).map_or(false, |value| value <= 0.0) {
+                anomalies.push(ManifestAnomaly::NonPositiveTiming(*metric));
+            }
+        }
+        // Only the highest degree is compared, because the timings of the lower degrees are
+        // close enough to be reordered by measurement noise.
+        for metrics in [ManifestMetric::COMMITS, ManifestMetric::PROOFS] {
+            let (higher, lower) = metrics.split_last().expect("not empty");
+            let higher_value = higher.value(manifest);
+            for lower in lower {
+                if higher_value < lower.value(manifest) {
+                    anomalies.push(ManifestAnomaly::FasterAtHigherDegree {
+                        lower: *lower,
+                        higher: *higher,
+                    });
+                }
+            }
+        }
+        anomalies
+    }
+}
+
+/// Statistics of a metric across peer manifests.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MetricStats {
+    /// The metric.
+    pub metric: ManifestMetric,
+    /// The number of peers with a value for the metric.
+    pub peers: usize,
+    /// The lowest value.
+    pub min: Option<f64>,
+    /// The highest value.
+    pub max: Option<f64>,
+    /// The mean value.
+    pub mean: Option<f64>,
+    /// The values at [`METRIC_PERCENTILES`] as `(percentile, value)` pairs.
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+impl MetricStats {
+    fn new(metric: ManifestMetric, mut values: Vec<f64>) -> Self {
+        values.sort_by(f64::total_cmp);
+        let mean = (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+        Self {
+            metric,
+            peers: values.len(),
+            min: values.first().copied(),
+            max: values.last().copied(),
+            mean,
+            percentiles: METRIC_PERCENTILES
+                .iter()
+                .filter_map(|&p| Some((p, percentile(&values, p)?)))
+                .collect(),
+        }
+    }
+
+    /// The median value.
+    pub fn median(&self) -> Option<f64> {
+        self.percentiles
+            .iter()
+            .find(|(p, _)| *p == 50.0)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// The benchmark performance and the implausible self-reports of a peer.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerPerformance {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// The geometric mean of the median timing divided by the peer's timing over the positive
+    /// timings, so 1 is the median peer and 2 is twice as fast. `None` if the peer has no positive
+    /// timings.
+    pub performance_index: Option<f64>,
+    /// The implausible self-reports in the manifest.
+    pub anomalies: Vec<ManifestAnomaly>,
+}
+
+/// Statistics of the peer manifests a node knows about.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ManifestSummary {
+    /// The statistics of each metric in the order of [`ManifestMetric::ALL`]. Timings from
+    /// manifests with anomalies are left out.
+    pub metrics: Vec<MetricStats>,
+    /// The performance of each peer in the order of the manifests.
+    pub peers: Vec<PeerPerformance>,
+}
+
+impl ManifestSummary {
+    /// Compute the statistics of the peer manifests.
+    pub fn new(peer_manifests: &PeerManifestsResponse) -> Self {
+        let manifests: Vec<(&PeerManifest, Vec<ManifestAnomaly>)> = peer_manifests
+            .peer_manifests
+            .iter()
+            .map(|manifest| (manifest, ManifestAnomaly::detect(manifest)))
+            .collect();
+
+        let metrics: Vec<MetricStats> = ManifestMetric::ALL
+            .iter()
+            .map(|&metric| {
+                let values = manifests
+                    .iter()
+                    .filter(|(_, anomalies)| !metric.is_timing() || anomalies.is_empty())
+                    .filter_map(|(manifest, _)| metric.value(manifest))
+                    .collect();
+                MetricStats::new(metric, values)
+            })
+            .collect();
+
+        let peers = manifests
+            .into_iter()
+            .map(|(manifest, anomalies)| PeerPerformance {
+                peer_id: manifest.peer_id,
+                performance_index: performance_index(manifest, &metrics),
+                anomalies,
+            })
+            .collect();
+
+        Self { metrics, peers }
+    }
+
+    /// The peers with implausible self-reports.
+    pub fn anomalous_peers(&self) -> impl Iterator<Item = &PeerPerformance> {
+        self.peers.iter().filter(|peer| !peer.anomalies.is_empty())
+    }
+}
+
+fn performance_index(manifest: &PeerManifest, metrics: &[MetricStats]) -> Option<f64> {
+    let log_ratios: Vec<f64> = metrics
+        .iter()
+        .filter(|stats| stats.metric.is_timing())
+        .filter_map(|stats| {
+            let median = stats.median()?;
+            let value = stats.metric.value(manifest)?;
+            (median > 0.0 && value > 0.0).then(|| (median / value).ln())
+        })
+        .collect();
+    if log_ratios.is_empty() {
+        return None;
+    }
+    Some((log_ratios.iter().sum::<f64>() / log_ratios.len() as f64).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{peer_manifest, random_peer_id};
+
+    fn benchmarked_manifest(scale: i64) -> PeerManifest {
+        PeerManifest {
+            difficulty: 10_000,
+            difficulty_metric: 20_000 * scale,
+            commit_16_metric: scale,
+            commit_128_metric: 2 * scale,
+            commit_1024_metric: 10 * scale,
+            commit_65536_metric: 500 * scale,
+            proof_16_metric: 2 * scale,
+            proof_128_metric: 4 * scale,
+            proof_1024_metric: 20 * scale,
+            proof_65536_metric: 1000 * scale,
+            cores: 16,
+            memory: 64 << 30,
+            storage: 1 << 40,
+            ..peer_manifest(random_peer_id())
+        }
+    }
+
+    #[test]
+    fn detect_anomalies() {
+        assert!(ManifestAnomaly::detect(&benchmarked_manifest(1)).is_empty());
+
+        let implausible = PeerManifest {
+            cores: 0,
+            memory: 101 << 40,
+            commit_128_metric: 0,
+            proof_65536_metric: 1,
+            ..benchmarked_manifest(1)
+        };
+        assert_eq!(
+            ManifestAnomaly::detect(&implausible),
+            vec![
+                ManifestAnomaly::ZeroCores,
+                ManifestAnomaly::ExcessiveMemory(101 << 40),
+                ManifestAnomaly::NonPositiveTiming(ManifestMetric::Commit128),
+                ManifestAnomaly::FasterAtHigherDegree {
+                    lower: ManifestMetric::Proof16,
+                    higher: ManifestMetric::Proof65536,
+                },
+                ManifestAnomaly::FasterAtHigherDegree {
+                    lower: ManifestMetric::Proof128,
+                    higher: ManifestMetric::Proof65536,
+                },
+                ManifestAnomaly::FasterAtHigherDegree {
+                    lower: ManifestMetric::Proof1024,
+                    higher: ManifestMetric::Proof65536,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let implausible = PeerManifest {
+            cores: 0,
+            ..benchmarked_manifest(1000)
+        };
+        let response = PeerManifestsResponse {
+            peer_manifests: vec![
+                benchmarked_manifest(1),
+                benchmarked_manifest(2),
+                benchmarked_manifest(4),
+                implausible,
+            ],
+        };
+        let summary = ManifestSummary::new(&response);
+
+        let commit_16 = &summary.metrics[1];
+        assert_eq!(commit_16.metric, ManifestMetric::Commit16);
+        assert_eq!(commit_16.peers, 3);
+        assert_eq!(commit_16.median(), Some(2.0));
+        let cores = &summary.metrics[9];
+        assert_eq!(cores.metric, ManifestMetric::Cores);
+        assert_eq!(cores.peers, 4);
+        assert_eq!(cores.min, Some(0.0));
+
+        let indexes: Vec<f64> = summary
+            .peers
+            .iter()
+            .map(|peer| peer.performance_index.unwrap())
+            .collect();
+        assert!((indexes[0] - 2.0).abs() < 1e-9);
+        assert!((indexes[1] - 1.0).abs() < 1e-9);
+        assert!((indexes[2] - 0.5).abs() < 1e-9);
+        assert_eq!(summary.anomalous_peers().count(), 1);
+    }
+}
@@ -0,0 +1,590 @@
+//! Collect metrics from nodes and export them in the Prometheus text format.
+
+use std::fmt::Write;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use crate::node::{
+    ClockFrame, FrameFilter, NetworkInfoResponse, NodeClient, NodeClientError, PeerInfoResponse,
+    PeerManifestsResponse, TokenInfo,
+};
+use crate::peer_scores::{ScoreOptions, ScoreStats};
+use crate::sync_status::SyncStatus;
+use crate::version_distribution::VersionDistribution;
+
+/// The duration and outcome of a call to the node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcTiming {
+    /// The `NodeClient` method, e.g. "token_info".
+    pub method: &'static str,
+    /// The frame filter for the `head_frame` method.
+    pub filter: Option<FrameFilter>,
+    /// The time the call took. Finding a head frame takes multiple requests.
+    pub duration: Duration,
+    /// The error if the call failed.
+    pub error: Option<String>,
+}
+
+/// Metrics of a node collected through a [`NodeClient`]. Values whose calls failed are missing.
+#[derive(Debug, Clone)]
+pub struct NodeMetrics {
+    /// The time the collection started.
+    pub collected_at: DateTime<Utc>,
+    /// The token supply and balance.
+    pub token_info: Option<TokenInfo>,
+    /// The head frame per frame filter. Filters without frames are left out.
+    pub head_frames: Vec<ClockFrame>,
+    /// The number of cooperative and uncooperative peers.
+    pub peers: Option<(usize, usize)>,
+    /// The number of peers per protocol version.
+    pub versions: Option<VersionDistribution>,
+    /// The statistics of the peer scores.
+    pub scores: Option<ScoreStats>,
+    /// The sync status of the node.
+    pub sync_status: Option<SyncStatus>,
+    /// The duration and outcome of each call.
+    pub rpc: Vec<RpcTiming>,
+}
+
+impl NodeMetrics {
+    /// Collect the metrics of a node. Failed calls are recorded in [`NodeMetrics::rpc`] and don't
+    /// stop the collection.
+    pub async fn collect(client: &mut NodeClient, score_options: &ScoreOptions) -> Self {
+        Self::new(&NodeResponses::fetch(client, None).await, score_options)
+    }
+
+    /// Collect the metrics of a node within a timeout. Calls still running at the timeout and the
+    /// calls after them fail with "timed out", and the results of the calls that finished before
+    /// are kept.
+    pub async fn collect_within(
+        client: &mut NodeClient,
+        score_options: &ScoreOptions,
+        timeout: Duration,
+    ) -> Self {
+        let deadline = Instant::now() + timeout;
+        Self::new(
+            &NodeResponses::fetch(client, Some(deadline)).await,
+            score_options,
+        )
+    }
+
+    /// Compute the metrics from the responses of a node.
+    pub(crate) fn new(responses: &NodeResponses, score_options: &ScoreOptions) -> Self {
+        let sync_status = match (
+            &responses.ceremony_head,
+            &responses.master_head,
+            &responses.peer_info,
+            &responses.peer_manifests,
+        ) {
+            (Some(ceremony), Some(master), Some(peer_info), Some(peer_manifests)) => {
+                Some(SyncStatus::new(
+                    responses.collected_at,
+                    ceremony.as_ref(),
+                    master.as_ref(),
+                    peer_info,
+                    peer_manifests,
+                ))
+            }
+            _ => None,
+        };
+        let head_frames = [&responses.ceremony_head, &responses.master_head]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .cloned()
+            .collect();
+        let peer_info = responses.peer_info.as_ref();
+
+        Self {
+            collected_at: responses.collected_at,
+            token_info: responses.token_info.clone(),
+            head_frames,
+            peers: peer_info.map(|info| (info.peers.len(), info.uncooperative_peers.len())),
+            versions: peer_info.map(VersionDistribution::new),
+            scores: responses
+                .network_info
+                .as_ref()
+                .map(|info| ScoreStats::new(info, score_options)),
+            sync_status,
+            rpc: responses.rpc.clone(),
+        }
+    }
+
+    /// Metrics of a node that couldn't be reached, recorded as a failed "connect" call.
+    pub fn unreachable(duration: Duration, error: String) -> Self {
+        Self {
+            collected_at: Utc::now(),
+            token_info: None,
+            head_frames: vec![],
+            peers: None,
+            versions: None,
+            scores: None,
+            sync_status: None,
+            rpc: vec![RpcTiming {
+                method: "connect",
+                filter: None,
+                duration,
+                error: Some(error),
+            }],
+        }
+    }
+
+    /// The head frame for a frame filter.
+    pub fn head_frame(&self, filter: &FrameFilter) -> Option<&ClockFrame> {
+        self.head_frames
+            .iter()
+            .find(|frame| frame.filter == *filter)
+    }
+
+    /// Whether any call to the node succeeded.
+    pub fn is_up(&self) -> bool {
+        self.rpc.iter().any(|timing| timing.error.is_none())
+    }
+}
+
+/// The responses of the calls the metrics are computed from. Responses whose calls failed are
+/// missing. Shared with the [overview](crate::overview::NodeOverview), which keeps the responses
+/// that the metrics only count.
+#[derive(Debug, Clone)]
+pub(crate) struct NodeResponses {
+    pub collected_at: DateTime<Utc>,
+    pub token_info: Option<TokenInfo>,
+    pub ceremony_head: Option<Option<ClockFrame>>,
+    pub master_head: Option<Option<ClockFrame>>,
+    pub peer_info: Option<PeerInfoResponse>,
+    pub network_info: Option<NetworkInfoResponse>,
+    pub peer_manifests: Option<PeerManifestsResponse>,
+    pub rpc: Vec<RpcTiming>,
+}
+
+impl NodeResponses {
+    /// Call the node. Failed calls are recorded in `rpc` and don't stop the others. Calls that
+    /// don't finish by the deadline fail.
+    pub async fn fetch(client: &mut NodeClient, deadline: Option<Instant>) -> Self {
+        let collected_at = Utc::now();
+        let mut rpc = Vec::new();
+        let token_info = timed(&mut rpc, deadline, "token_info", None, client.token_info()).await;
+        let ceremony_head = timed(
+            &mut rpc,
+            deadline,
+            "head_frame",
+            Some(FrameFilter::CeremonyApplication),
+            client.head_frame(FrameFilter::CeremonyApplication),
+        )
+        .await;
+        let master_head = timed(
+            &mut rpc,
+            deadline,
+            "head_frame",
+            Some(FrameFilter::MasterClock),
+            client.head_frame(FrameFilter::MasterClock),
+        )
+        .await;
+        let peer_info = timed(&mut rpc, deadline, "peer_info", None, client.peer_info()).await;
+        let network_info = timed(
+            &mut rpc,
+            deadline,
+            "network_info",
+            None,
+            client.network_info(),
+        )
+        .await;
+        let peer_manifests = timed(
+            &mut rpc,
+            deadline,
+            "peer_manifests",
+            None,
+            client.peer_manifests(),
+        )
+        .await;
+        Self {
+            collected_at,
+            token_info,
+            ceremony_head,
+            master_head,
+            peer_info,
+            network_info,
+            peer_manifests,
+            rpc,
+        }
+    }
+}
+
+/// Run a call and record its duration and outcome in `rpc`. The call fails with "timed out" if it
+/// doesn't finish by the deadline.
+pub(crate) async fn timed<T>(
+    rpc: &mut Vec<RpcTiming>,
+    deadline: Option<Instant>,
+    method: &'static str,
+    filter: Option<FrameFilter>,
+    call: impl Future<Output = Result<T, NodeClientError>>,
+) -> Option<T> {
+    let started = Instant::now();
+    let result = match deadline {
+        Some(deadline) => match tokio::time::timeout_at(deadline.into(), call).await {
+            Ok(result) => result.map_err(|error| error.to_string()),
+            Err(_) => Err("timed out".to_string()),
+        },
+        None => call.await.map_err(|error| error.to_string()),
+    };
+    rpc.push(RpcTiming {
+        method,
+        filter,
+        duration: started.elapsed(),
+        error: result.as_ref().err().cloned(),
+    });
+    result.ok()
+}
+
+/// Metrics of one or more nodes in the Prometheus text format. The samples of each node carry
+/// the labels given for the node.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsExport {
+    families: Vec<Family>,
+}
+
+#[derive(Debug, Clone)]
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    samples: Vec<(String, f64)>,
+}
+
+impl MetricsExport {
+    /// Create an export without nodes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the metrics of a node with labels that tell it apart from the other nodes, e.g.
+    /// `[("node", "bootstrap-1")]`.
+    pub fn with_node(mut self, labels: &[(&str, &str)], metrics: &NodeMetrics) -> Self {
+        let labels: Vec<(&str, String)> = labels
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect();
+        let mut sample = |name, help, extra: &[(&str, String)], value: f64| {
+            let mut all = labels.clone();
+            all.extend_from_slice(extra);
+            self.sample(name, help, &all, value);
+        };
+
+        sample(
+            "quilibrium_up",
+            "Whether any call to the node succeeded.",
+            &[],
+            f64::from(u8::from(metrics.is_up())),
+        );
+        if let Some(token_info) = &metrics.token_info {
+            sample(
+                "quilibrium_owned_tokens_quil",
+                "The QUIL owned by the node's address.",
+                &[],
+                token_info.owned_tokens.to_quil_f64(),
+            );
+            sample(
+                "quilibrium_confirmed_token_supply_quil",
+                "The QUIL supply from confirmed frame data.",
+                &[],
+                token_info.confirmed_token_supply.to_quil_f64(),
+            );
+            sample(
+                "quilibrium_unconfirmed_token_supply_quil",
+                "The QUIL supply including unconfirmed frame data.",
+                &[],
+                token_info.unconfirmed_token_supply.to_quil_f64(),
+            );
+        }
+        for frame in &metrics.head_frames {
+            sample(
+                "quilibrium_head_frame_number",
+                "The number of the highest frame the node has.",
+                &[("filter", frame.filter.to_string())],
+                frame.frame_number as f64,
+            );
+        }
+        if let Some((cooperative, uncooperative)) = metrics.peers {
+            for (status, peers) in [
+                ("cooperative", cooperative),
+                ("uncooperative", uncooperative),
+            ] {
+                sample(
+                    "quilibrium_peers",
+                    "The number of peers in the peer info of the node.",
+                    &[("status", status.to_string())],
+                    peers as f64,
+                );
+            }
+        }
+        if let Some(versions) = &metrics.versions {
+            for share in &versions.versions {
+                for (status, peers) in [
+                    ("cooperative", share.cooperative_peers),
+                    ("uncooperative", share.uncooperative_peers),
+                ] {
+                    sample(
+                        "quilibrium_version_peers",
+                        "The number of peers in the peer info of the node per protocol version.",
+                        &[
+                            ("version", share.version.to_string()),
+                            ("status", status.to_string()),
+                        ],
+                        peers as f64,
+                    );
+                }
+            }
+        }
+        if let Some(scores) = &metrics.scores {
+            sample(
+                "quilibrium_scored_peers",
+                "The number of peers with a finite peer score.",
+                &[],
+                scores.peers as f64,
+            );
+            sample(
+                "quilibrium_penalized_peers",
+                "The number of peers with a peer score below the threshold.",
+                &[],
+                scores.penalized.len() as f64,
+            );
+            let stats = [
+                ("min", scores.min),
+                ("max", scores.max),
+                ("mean", scores.mean),
+            ];
+            for (stat, value) in stats {
+                if let Some(value) = value {
+                    sample(
+                        "quilibrium_peer_score",
+                        "The min, max and mean peer score the node assigns to its peers.",
+                        &[("stat", stat.to_string())],
+                        value,
+                    );
+                }
+            }
+            for (percentile, value) in &scores.percentiles {
+                sample(
+                    "quilibrium_peer_score_percentile",
+                    "The percentiles of the peer scores the node assigns to its peers.",
+                    &[("percentile", percentile.to_string())],
+                    *value,
+                );
+            }
+        }
+        if let Some(sync_status) = &metrics.sync_status {
+            for status in &sync_status.filters {
+                if let Some(lag) = status.lag() {
+                    sample(
+                        "quilibrium_sync_lag_frames",
+                        "The number of frames the node is behind the network.",
+                        &[("filter", status.filter.to_string())],
+                        lag as f64,
+                    );
+                }
+            }
+        }
+        for timing in &metrics.rpc {
+            let mut extra = vec![("method", timing.method.to_string())];
+            if let Some(filter) = &timing.filter {
+                extra.push(("filter", filter.to_string()));
+            }
+            sample(
+                "quilibrium_rpc_duration_seconds",
+                "The time a call to the node took.",
+                &extra,
+                timing.duration.as_secs_f64(),
+            );
+            sample(
+                "quilibrium_rpc_success",
+                "Whether a call to the node succeeded.",
+                &extra,
+                f64::from(u8::from(timing.error.is_none())),
+            );
+        }
+        self
+    }
+
+    /// Render the metrics in the Prometheus text exposition format. All metrics are gauges.
+    pub fn to_prometheus_text(&self) -> String {
+        let mut text = String::new();
+        for family in &self.families {
+            let _ = writeln!(text, "# HELP {} {}", family.name, family.help);
+            let _ = writeln!(text, "# TYPE {} gauge", family.name);
+            for (labels, value) in &family.samples {
+                let _ = writeln!(text, "{}{} {}", family.name, labels, format_value(*value));
+            }
+        }
+        text
+    }
+
+    fn sample(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, String)],
+        value: f64,
+    ) {
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(name, value)| format!("{name}=\"{}\"", escape_label(value)))
+                .collect();
+            format!("{{{}}}", labels.join(","))
+        };
+        match self.families.iter_mut().find(|family| family.name == name) {
+            Some(family) => family.samples.push((labels, value)),
+            None => self.families.push(Family {
+                name,
+                help,
+                samples: vec![(labels, value)],
+            }),
+        }
+    }
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol_version::ProtocolVersion;
+    use crate::test_utils::{random_peer_id, FakeNode};
+
+    #[tokio::test]
+    async fn collect_within_keeps_finished_calls() -> anyhow::Result<()> {
+        let uri = FakeNode::new()
+            .with_token_info(8_000_000_000, 8_000_000_000, 8_000_000_000)
+            .with_delay(Duration::from_millis(200))
+            .serve()
+            .await;
+        let mut client = NodeClient::new(uri).await?;
+        let timeout = Duration::from_millis(300);
+        let metrics = NodeMetrics::collect_within(&mut client, &ScoreOptions::new(), timeout).await;
+
+        assert!(metrics.is_up());
+        assert!(metrics.token_info.is_some());
+        let methods: Vec<&str> = metrics.rpc.iter().map(|timing| timing.method).collect();
+        assert_eq!(
+            methods,
+            [
+                "token_info",
+                "head_frame",
+                "head_frame",
+                "peer_info",
+                "network_info",
+                "peer_manifests"
+            ]
+        );
+        for timing in &metrics.rpc[1..] {
+            assert_eq!(timing.error.as_deref(), Some("timed out"));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn collect_and_export() -> anyhow::Result<()> {
+        let (cooperative, uncooperative) = (random_peer_id(), random_peer_id());
+        let version = ProtocolVersion::new(1, 2, 0);
+        let uri = FakeNode::new()
+            .with_token_info(16_000_000_000, 20_000_000_000, 4_000_000_000)
+            .with_head_frames(Some(90), Some(7))
+            .with_peer_info(cooperative, true, version, 100)
+            .with_peer_info(uncooperative, false, version, 50)
+            .with_network_info(cooperative, 10.0)
+            .with_network_info(uncooperative, -20.0)
+            .with_peer_manifest(cooperative, 7)
+            .serve()
+            .await;
+
+        let mut client = NodeClient::new(uri).await?;
+        let metrics = NodeMetrics::collect(&mut client, &ScoreOptions::new()).await;
+        assert!(metrics.is_up());
+        assert!(metrics.rpc.iter().all(|timing| timing.error.is_none()));
+        let head_frames: Vec<(FrameFilter, u64)> = metrics
+            .head_frames
+            .iter()
+            .map(|frame| (frame.filter.clone(), frame.frame_number))
+            .collect();
+        assert_eq!(
+            head_frames,
+            vec![
+                (FrameFilter::CeremonyApplication, 90),
+                (FrameFilter::MasterClock, 7)
+            ]
+        );
+        assert_eq!(
+            metrics
+                .head_frame(&FrameFilter::MasterClock)
+                .map(|frame| frame.frame_number),
+            Some(7)
+        );
+        assert_eq!(metrics.peers, Some((1, 1)));
+
+        let text = MetricsExport::new()
+            .with_node(&[("node", "a\"b")], &metrics)
+            .to_prometheus_text();
+        let lines: Vec<&str> = text.lines().collect();
+        for expected in [
+            "# TYPE quilibrium_up gauge",
+            r#"quilibrium_up{node="a\"b"} 1"#,
+            r#"quilibrium_owned_tokens_quil{node="a\"b"} 0.5"#,
+            r#"quilibrium_confirmed_token_supply_quil{node="a\"b"} 2"#,
+            r#"quilibrium_unconfirmed_token_supply_quil{node="a\"b"} 2.5"#,
+            r#"quilibrium_head_frame_number{node="a\"b",filter="ceremony-application"} 90"#,
+            r#"quilibrium_peers{node="a\"b",status="cooperative"} 1"#,
+            r#"quilibrium_peers{node="a\"b",status="uncooperative"} 1"#,
+            r#"quilibrium_version_peers{node="a\"b",version="1.2.0",status="cooperative"} 1"#,
+            r#"quilibrium_penalized_peers{node="a\"b"} 1"#,
+            r#"quilibrium_peer_score{node="a\"b",stat="min"} -20"#,
+            r#"quilibrium_sync_lag_frames{node="a\"b",filter="ceremony-application"} 10"#,
+            r#"quilibrium_sync_lag_frames{node="a\"b",filter="master-clock"} 0"#,
+            r#"quilibrium_rpc_success{node="a\"b",method="head_frame",filter="master-clock"} 1"#,
+        ] {
+            assert!(lines.contains(&expected), "missing {expected} in\n{text}");
+        }
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("# TYPE quilibrium_peers "))
+                .count(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_calls() {
+        let metrics = NodeMetrics::unreachable(Duration::from_millis(1500), "refused".to_string());
+        assert!(!metrics.is_up());
+
+        let text = MetricsExport::new()
+            .with_node(&[("node", "a")], &metrics)
+            .to_prometheus_text();
+        assert!(text.contains("quilibrium_up{node=\"a\"} 0\n"));
+        assert!(
+            text.contains("quilibrium_rpc_duration_seconds{node=\"a\",method=\"connect\"} 1.5\n")
+        );
+        assert!(text.contains("quilibrium_rpc_success{node=\"a\",method=\"connect\"} 0\n"));
+        assert!(!text.contains("quilibrium_peers"));
+    }
+}
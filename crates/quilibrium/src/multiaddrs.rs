@@ -0,0 +1,317 @@
+//! Classify and normalize the [multiaddrs](https://multiformats.io/multiaddr/) of peers.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use multiaddr::{Multiaddr, Protocol};
+
+/// The reachability scope of the address in a multiaddr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum AddressScope {
+    /// A globally routable address. DNS names other than `localhost` are assumed to be public.
+    Public,
+    /// A private network address, including shared address space used by carrier-grade NAT and
+    /// IPv6 unique local addresses.
+    Private,
+    /// A loopback address.
+    Loopback,
+    /// A link-local address.
+    LinkLocal,
+    /// The unspecified address, e.g. `0.0.0.0`.
+    Unspecified,
+    /// The multiaddr has no IP address or DNS name.
+    Unknown,
+}
+
+/// The address family of a multiaddr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum AddressFamily {
+    /// An IPv4 address.
+    Ipv4,
+    /// An IPv6 address.
+    Ipv6,
+    /// A DNS name.
+    Dns,
+    /// The multiaddr has no IP address or DNS name.
+    Unknown,
+}
+
+/// The transport protocol of a multiaddr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Transport {
+    /// TCP.
+    Tcp,
+    /// UDP without QUIC.
+    Udp,
+    /// QUIC over UDP.
+    Quic,
+    /// Any other transport.
+    Other,
+}
+
+/// The classification of a multiaddr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct MultiaddrClass {
+    /// The reachability scope of the address.
+    pub scope: AddressScope,
+    /// The address family.
+    pub family: AddressFamily,
+    /// The transport protocol.
+    pub transport: Transport,
+}
+
+impl MultiaddrClass {
+    /// Classify a multiaddr by the first address and transport protocols it contains.
+    pub fn new(multiaddr: &Multiaddr) -> Self {
+        let mut scope = AddressScope::Unknown;
+        let mut family = AddressFamily::Unknown;
+        let mut transport = Transport::Other;
+
+        for protocol in multiaddr.iter() {
+            match protocol {
+                Protocol::Ip4(ip) if family == AddressFamily::Unknown => {
+                    family = AddressFamily::Ipv4;
+                    scope = ip_scope(IpAddr::V4(ip));
+                }
+                Protocol::Ip6(ip) if family == AddressFamily::Unknown => {
+                    family = AddressFamily::Ipv6;
+                    scope = ip_scope(IpAddr::V6(ip));
+                }
+                Protocol::Dns(name)
+                | Protocol::Dns4(name)
+                | Protocol::Dns6(name)
+                | Protocol::Dnsaddr(name)
+                    if family == AddressFamily::Unknown =>
+                {
+                    family = AddressFamily::Dns;
+                    scope = if name.eq_ignore_ascii_case("localhost") {
+                        AddressScope::Loopback
+                    } else {
+                        AddressScope::Public
+                    };
+                }
+                Protocol::Tcp(_) if transport == Transport::Other => transport = Transport::Tcp,
+                Protocol::Udp(_) if transport == Transport::Other => transport = Transport::Udp,
+                Protocol::Quic | Protocol::QuicV1 if transport == Transport::Udp => {
+                    transport = Transport::Quic
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            scope,
+            family,
+            transport,
+        }
+    }
+}
+
+fn ip_scope(ip: IpAddr) -> AddressScope {
+    match ip {
+        IpAddr::V4(ip) => ipv4_scope(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => ipv4_scope(ip),
+            None => ipv6_scope(ip),
+        },
+    }
+}
+
+fn ipv4_scope(ip: Ipv4Addr) -> AddressScope {
+    // 100.64.0.0/10 is the shared address space for carrier-grade NAT.
+    let is_shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0b1100_0000) == 64;
+
+    if ip.is_unspecified() {
+        AddressScope::Unspecified
+    } else if ip.is_loopback() {
+        AddressScope::Loopback
+    } else if ip.is_link_local() {
+        AddressScope::LinkLocal
+    } else if ip.is_private() || is_shared {
+        AddressScope::Private
+    } else {
+        AddressScope::Public
+    }
+}
+
+fn ipv6_scope(ip: Ipv6Addr) -> AddressScope {
+    // fe80::/10
+    let is_link_local = (ip.segments()[0] & 0xffc0) == 0xfe80;
+    // fc00::/7
+    let is_unique_local = (ip.segments()[0] & 0xfe00) == 0xfc00;
+
+    if ip.is_unspecified() {
+        AddressScope::Unspecified
+    } else if ip.is_loopback() {
+        AddressScope::Loopback
+    } else if is_link_local {
+        AddressScope::LinkLocal
+    } else if is_unique_local {
+        AddressScope::Private
+    } else {
+        AddressScope::Public
+    }
+}
+
+/// Remove the trailing `/p2p/<peer id>` component from the multiaddrs and remove the duplicates
+/// while preserving the order.
+pub fn normalize(multiaddrs: impl IntoIterator<Item = Multiaddr>) -> Vec<Multiaddr> {
+    let mut normalized: Vec<Multiaddr> = Vec::new();
+    for mut multiaddr in multiaddrs {
+        if let Some(Protocol::P2p(_)) = multiaddr.iter().last() {
+            multiaddr.pop();
+        }
+        if !normalized.contains(&multiaddr) {
+            normalized.push(multiaddr);
+        }
+    }
+    normalized
+}
+
+/// Filter for multiaddrs by their classification. The default filter matches all multiaddrs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiaddrFilter {
+    /// Only match public addresses.
+    pub public_only: bool,
+    /// Only match this transport.
+    pub transport: Option<Transport>,
+    /// Only match this address family.
+    pub family: Option<AddressFamily>,
+}
+
+impl MultiaddrFilter {
+    /// Create a new multiaddr filter builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to only match public addresses.
+    pub fn public_only(mut self, public_only: bool) -> Self {
+        self.public_only = public_only;
+        self
+    }
+
+    /// Set the transport to match.
+    pub fn transport(mut self, transport: Option<Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Set the address family to match.
+    pub fn family(mut self, family: Option<AddressFamily>) -> Self {
+        self.family = family;
+        self
+    }
+
+    /// Whether the multiaddr matches the filter.
+    pub fn matches(&self, multiaddr: &Multiaddr) -> bool {
+        let class = MultiaddrClass::new(multiaddr);
+        (!self.public_only || class.scope == AddressScope::Public)
+            && self.transport.map_or(true, |t| t == class.transport)
+            && self.family.map_or(true, |f| f == class.family)
+    }
+
+    /// Normalize the multiaddrs and keep the ones that match the filter.
+    pub fn apply(&self, multiaddrs: impl IntoIterator<Item = Multiaddr>) -> Vec<Multiaddr> {
+        normalize(multiaddrs)
+            .into_iter()
+            .filter(|multiaddr| self.matches(multiaddr))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(multiaddr: &str) -> MultiaddrClass {
+        MultiaddrClass::new(&multiaddr.parse().unwrap())
+    }
+
+    #[test]
+    fn classify_scopes() {
+        let cases = [
+            ("/ip4/51.81.48.32/udp/8336/quic", AddressScope::Public),
+            ("/ip4/10.0.0.1/tcp/8336", AddressScope::Private),
+            ("/ip4/192.168.1.2/tcp/8336", AddressScope::Private),
+            ("/ip4/100.64.0.1/tcp/8336", AddressScope::Private),
+            ("/ip4/100.128.0.1/tcp/8336", AddressScope::Public),
+            ("/ip4/127.0.0.1/tcp/8336", AddressScope::Loopback),
+            ("/ip4/169.254.0.1/tcp/8336", AddressScope::LinkLocal),
+            ("/ip4/0.0.0.0/tcp/8336", AddressScope::Unspecified),
+            ("/ip6/::1/tcp/8336", AddressScope::Loopback),
+            ("/ip6/fe80::1/udp/8336/quic", AddressScope::LinkLocal),
+            ("/ip6/fd00::1/tcp/8336", AddressScope::Private),
+            ("/ip6/2001:db8::1/tcp/8336", AddressScope::Public),
+            ("/ip6/::ffff:10.0.0.1/tcp/8336", AddressScope::Private),
+            ("/dns4/example.com/tcp/8336", AddressScope::Public),
+            ("/dns/localhost/tcp/8336", AddressScope::Loopback),
+            ("/memory/1", AddressScope::Unknown),
+        ];
+        for (multiaddr, scope) in cases {
+            assert_eq!(class(multiaddr).scope, scope, "{multiaddr}");
+        }
+    }
+
+    #[test]
+    fn classify_family_and_transport() {
+        let quic = class("/ip4/1.2.3.4/udp/8336/quic");
+        assert_eq!(quic.family, AddressFamily::Ipv4);
+        assert_eq!(quic.transport, Transport::Quic);
+
+        let quic_v1 = class("/ip6/2001:db8::1/udp/8336/quic-v1");
+        assert_eq!(quic_v1.family, AddressFamily::Ipv6);
+        assert_eq!(quic_v1.transport, Transport::Quic);
+
+        let udp = class("/ip4/1.2.3.4/udp/8336");
+        assert_eq!(udp.transport, Transport::Udp);
+
+        let tcp = class("/dns6/example.com/tcp/8336/ws");
+        assert_eq!(tcp.family, AddressFamily::Dns);
+        assert_eq!(tcp.transport, Transport::Tcp);
+
+        assert_eq!(class("/memory/1").transport, Transport::Other);
+    }
+
+    #[test]
+    fn normalize_dedupes_and_strips_peer_id() {
+        let multiaddrs: Vec<Multiaddr> = [
+            "/ip4/1.2.3.4/udp/8336/quic",
+            "/ip4/1.2.3.4/tcp/8336",
+            "/ip4/1.2.3.4/udp/8336/quic/p2p/QmcYNKu7FBUfjUvNRbj2shHXX18KC4t3PEypzkcGXu21RT",
+            "/ip4/1.2.3.4/tcp/8336",
+        ]
+        .iter()
+        .map(|m| m.parse().unwrap())
+        .collect();
+
+        let expected: Vec<Multiaddr> = vec![
+            "/ip4/1.2.3.4/udp/8336/quic".parse().unwrap(),
+            "/ip4/1.2.3.4/tcp/8336".parse().unwrap(),
+        ];
+        assert_eq!(normalize(multiaddrs), expected);
+    }
+
+    #[test]
+    fn filter() {
+        let multiaddrs: Vec<Multiaddr> = [
+            "/ip4/1.2.3.4/udp/8336/quic",
+            "/ip4/1.2.3.4/tcp/8336",
+            "/ip4/10.0.0.1/udp/8336/quic",
+            "/ip6/2001:db8::1/udp/8336/quic",
+        ]
+        .iter()
+        .map(|m| m.parse().unwrap())
+        .collect();
+
+        assert_eq!(MultiaddrFilter::new().apply(multiaddrs.clone()).len(), 4);
+
+        let filter = MultiaddrFilter::new()
+            .public_only(true)
+            .transport(Some(Transport::Quic))
+            .family(Some(AddressFamily::Ipv4));
+        assert_eq!(
+            filter.apply(multiaddrs),
+            vec!["/ip4/1.2.3.4/udp/8336/quic".parse::<Multiaddr>().unwrap()]
+        );
+    }
+}
@@ -15,11 +15,21 @@
 //! # }
 
 use crate::oblivious_transfer_units::ObliviousTransferUnits;
+use crate::peer_directory::PeerDirectory;
+use crate::protocol_version::ProtocolVersion;
+use crate::snapshot::NetworkSnapshot;
+use crate::supply::SupplySample;
+use crate::sync_status::{self, SyncStatus};
+use crate::units::ByteSize;
 use chrono::{DateTime, LocalResult, TimeZone, Utc};
 use lazy_static::lazy_static;
 pub use libp2p_identity::PeerId;
 use std::fmt::Display;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
 use tonic::transport::Uri;
+use tracing::{field, Instrument};
 
 use crate::quilibrium_pb::node::node::pb::GetFrameInfoRequest;
 use crate::quilibrium_pb::node::{
@@ -28,9 +38,16 @@ use crate::quilibrium_pb::node::{
 };
 
 /// gRPC client for a Quilibrium node.
+///
+/// Every method runs in a `node_client` [tracing] span at debug level. The span carries the
+/// method, the endpoint of the node and the request parameters, and records the encoded size of
+/// the response, the number of decoded items and the [kind](NodeClientError::kind) of the error
+/// if the call fails. A failed call is logged once, by the innermost call that failed. The
+/// connect span records the time it took to connect in `duration_ms`.
 #[derive(Debug, Clone)]
 pub struct NodeClient {
     client: NodeServiceClient<tonic::transport::Channel>,
+    uri: Uri,
 }
 
 const MAX_DECODING_MESSAGE_SIZE_BYTES: usize = 25 * 1024 * 1024;
@@ -39,10 +56,22 @@ impl NodeClient {
     /// Create a new node client. The URI should be the address of the [node's gRPC
     /// service.](https://github.com/quilibriumnetwork/ceremonyclient#experimental--grpcrest-support)
     pub async fn new(uri: Uri) -> Result<Self, NodeClientError> {
-        let client = NodeServiceClient::connect(uri)
-            .await?
-            .max_decoding_message_size(MAX_DECODING_MESSAGE_SIZE_BYTES);
-        Ok(Self { client })
+        let span = span("connect", &uri);
+        // Connect outside of the span, otherwise the connection task spawned by the client would
+        // inherit it and keep it open for the lifetime of the connection.
+        let started = std::time::Instant::now();
+        let client = NodeServiceClient::connect(uri.clone()).await;
+        span.record("duration_ms", started.elapsed().as_secs_f64() * 1000.0);
+        let client = record_result(&span, client.map_err(NodeClientError::from))?;
+        Ok(Self {
+            client: client.max_decoding_message_size(MAX_DECODING_MESSAGE_SIZE_BYTES),
+            uri,
+        })
+    }
+
+    /// The URI of the node.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
     }
 
     /// Get frame metadata for a frame filter.
@@ -50,9 +79,18 @@ impl NodeClient {
         &mut self,
         options: FramesOptions,
     ) -> Result<FramesResponse, NodeClientError> {
-        let request = tonic::Request::new(options.into());
-        let response = self.client.get_frames(request).await?;
-        response.into_inner().try_into()
+        let span = span("frames", &self.uri);
+        span.record("filter", field::display(&options.filter));
+        span.record("from_frame_number", options.from_frame_number);
+        span.record("to_frame_number", options.to_frame_number);
+        span.record("include_candidates", options.include_candidates);
+        traced(span, async {
+            let request = tonic::Request::new(options.into());
+            let response = self.client.get_frames(request).await?.into_inner();
+            record_response(&response, response.truncated_clock_frames.len());
+            response.try_into()
+        })
+        .await
     }
 
     /// Get a frame by frame filter and frame number.
@@ -61,42 +99,323 @@ impl NodeClient {
         filter: FrameFilter,
         frame_number: u64,
     ) -> Result<Option<clock_pb::ClockFrame>, NodeClientError> {
-        let request = tonic::Request::new(GetFrameInfoRequest {
-            filter: filter.into(),
-            frame_number,
-            selector: vec![],
-        });
-        let response = self.client.get_frame_info(request).await?;
-        Ok(response.into_inner().clock_frame)
+        let span = span("frame_info", &self.uri);
+        span.record("filter", field::display(&filter));
+        span.record("frame_number", frame_number);
+        traced(span, async {
+            let request = tonic::Request::new(GetFrameInfoRequest {
+                filter: filter.into(),
+                frame_number,
+                selector: vec![],
+            });
+            let response = self.client.get_frame_info(request).await?.into_inner();
+            record_response(&response, usize::from(response.clock_frame.is_some()));
+            Ok(response.clock_frame)
+        })
+        .await
+    }
+
+    /// Get the highest frame the node has for a frame filter. Returns `None` if the node has no
+    /// frames for the filter.
+    ///
+    /// The node keeps a contiguous range of frames up to its head, as old frames are culled. The
+    /// search starts from the median frame number reported by the peers, from the peer info for
+    /// the ceremony application filter and from the peer manifests for the master clock filter.
+    /// If the node doesn't have that frame, an exponential search down from it finds one the node
+    /// has, then an exponential search up from there followed by a binary search finds the head.
+    /// This takes a logarithmic number of requests in the distance from the median. Without peers
+    /// the search starts from frame 1, which fails on nodes that culled it, and it also fails if
+    /// the node is further behind the peers than the number of frames it keeps.
+    pub async fn head_frame(
+        &mut self,
+        filter: FrameFilter,
+    ) -> Result<Option<ClockFrame>, NodeClientError> {
+        let span = span("head_frame", &self.uri);
+        span.record("filter", field::display(&filter));
+        traced_composite(span, async {
+            let mut reported = match filter {
+                FrameFilter::CeremonyApplication => {
+                    let peer_info = self.peer_info().await?;
+                    peer_info.peers.iter().map(|peer| peer.max_frame).collect()
+                }
+                FrameFilter::MasterClock => {
+                    let peer_manifests = self.peer_manifests().await?;
+                    peer_manifests
+                        .peer_manifests
+                        .iter()
+                        .map(|manifest| manifest.master_head_frame)
+                        .collect()
+                }
+                FrameFilter::Unknown(_) => vec![],
+            };
+            reported.sort_unstable();
+            let head = self
+                .search_head_frame(filter, sync_status::median(&reported))
+                .await?;
+            if let Some(head) = &head {
+                tracing::Span::current().record("frame_number", head.frame_number);
+            }
+            Ok(head)
+        })
+        .await
+    }
+
+    /// Compare the head frames of the node with the max frames reported by its peers.
+    pub async fn sync_status(&mut self) -> Result<SyncStatus, NodeClientError> {
+        traced_composite(span("sync_status", &self.uri), async {
+            let ceremony_head = self.head_frame(FrameFilter::CeremonyApplication).await?;
+            let master_head = self.head_frame(FrameFilter::MasterClock).await?;
+            let peer_info = self.peer_info().await?;
+            let peer_manifests = self.peer_manifests().await?;
+            Ok(SyncStatus::new(
+                Utc::now(),
+                ceremony_head.as_ref(),
+                master_head.as_ref(),
+                &peer_info,
+                &peer_manifests,
+            ))
+        })
+        .await
     }
 
     /// Fetch the peers from the node's peer store.
     pub async fn network_info(&mut self) -> Result<NetworkInfoResponse, NodeClientError> {
-        let request = tonic::Request::new(node_pb::GetNetworkInfoRequest {});
-        let response = self.client.get_network_info(request).await?;
-        response.into_inner().try_into()
+        traced(span("network_info", &self.uri), async {
+            let request = tonic::Request::new(node_pb::GetNetworkInfoRequest {});
+            let response = self.client.get_network_info(request).await?.into_inner();
+            record_response(&response, response.network_info.len());
+            response.try_into()
+        })
+        .await
     }
 
     /// Fetch the broadcasted sync info that gets replicated through the network mesh.
     pub async fn peer_info(&mut self) -> Result<PeerInfoResponse, NodeClientError> {
-        let request = tonic::Request::new(node_pb::GetPeerInfoRequest {});
-        let response = self.client.get_peer_info(request).await?;
-        response.into_inner().try_into()
+        traced(span("peer_info", &self.uri), async {
+            let request = tonic::Request::new(node_pb::GetPeerInfoRequest {});
+            let response = self.client.get_peer_info(request).await?.into_inner();
+            record_response(
+                &response,
+                response.peer_info.len() + response.uncooperative_peer_info.len(),
+            );
+            response.try_into()
+        })
+        .await
     }
 
     /// Fetch the self-reported peer manifests that the node knows about.
     pub async fn peer_manifests(&mut self) -> Result<PeerManifestsResponse, NodeClientError> {
-        let request = tonic::Request::new(node_pb::GetPeerManifestsRequest {});
-        let response = self.client.get_peer_manifests(request).await?;
-        response.into_inner().try_into()
+        traced(span("peer_manifests", &self.uri), async {
+            let request = tonic::Request::new(node_pb::GetPeerManifestsRequest {});
+            let response = self.client.get_peer_manifests(request).await?.into_inner();
+            record_response(&response, response.peer_manifests.len());
+            response.try_into()
+        })
+        .await
+    }
+
+    /// Fetch the network info, peer info and peer manifests from the node and join them by peer ID.
+    pub async fn peer_directory(&mut self) -> Result<PeerDirectory, NodeClientError> {
+        traced_composite(span("peer_directory", &self.uri), async {
+            let network_info = self.network_info().await?;
+            let peer_info = self.peer_info().await?;
+            let peer_manifests = self.peer_manifests().await?;
+            Ok(PeerDirectory::new()
+                .with_network_info(network_info)
+                .with_peer_info(peer_info)
+                .with_peer_manifests(peer_manifests))
+        })
+        .await
+    }
+
+    /// Capture the network info, peer info and peer manifests of the node.
+    pub async fn network_snapshot(&mut self) -> Result<NetworkSnapshot, NodeClientError> {
+        traced_composite(span("network_snapshot", &self.uri), async {
+            let captured_at = Utc::now();
+            Ok(NetworkSnapshot {
+                captured_at,
+                network_info: self.network_info().await?,
+                peer_info: self.peer_info().await?,
+                peer_manifests: self.peer_manifests().await?,
+            })
+        })
+        .await
     }
 
     /// Fetch the token info from the node.
     pub async fn token_info(&mut self) -> Result<TokenInfo, NodeClientError> {
-        let request = tonic::Request::new(node_pb::GetTokenInfoRequest {});
-        let response = self.client.get_token_info(request).await?;
-        response.into_inner().try_into()
+        traced(span("token_info", &self.uri), async {
+            let request = tonic::Request::new(node_pb::GetTokenInfoRequest {});
+            let response = self.client.get_token_info(request).await?.into_inner();
+            record_response(&response, 1);
+            response.try_into()
+        })
+        .await
+    }
+
+    /// Fetch the token info and align it to the ceremony application head frame of the node.
+    ///
+    /// The head frame search takes many requests, so it runs first and the token info is fetched
+    /// right after it. The sample is timestamped at the token info request, and the frame number
+    /// is the head found by the search that ended just before.
+    pub async fn supply_sample(&mut self) -> Result<SupplySample, NodeClientError> {
+        traced_composite(span("supply_sample", &self.uri), async {
+            let head_frame = self.head_frame(FrameFilter::CeremonyApplication).await?;
+            let sampled_at = Utc::now();
+            let token_info = self.token_info().await?;
+            Ok(SupplySample::new(
+                sampled_at,
+                &token_info,
+                head_frame.as_ref(),
+            ))
+        })
+        .await
     }
+
+    async fn search_head_frame(
+        &mut self,
+        filter: FrameFilter,
+        start: Option<u64>,
+    ) -> Result<Option<ClockFrame>, NodeClientError> {
+        let Some((mut head, missing)) = self.find_frame(filter.clone(), start).await? else {
+            return Ok(None);
+        };
+
+        // Invariant: frame `head.frame_number` exists and frame `missing` doesn't.
+        let mut missing = match missing {
+            Some(missing) => missing,
+            None => {
+                let mut step = 1_u64;
+                loop {
+                    let Some(frame_number) = head.frame_number.checked_add(step) else {
+                        return Ok(Some(head));
+                    };
+                    match self.frame_metadata(filter.clone(), frame_number).await? {
+                        Some(frame) => head = frame,
+                        None => break frame_number,
+                    }
+                    step = step.saturating_mul(2);
+                }
+            }
+        };
+        while missing - head.frame_number > 1 {
+            let mid = head.frame_number + (missing - head.frame_number) / 2;
+            match self.frame_metadata(filter.clone(), mid).await? {
+                Some(frame) => head = frame,
+                None => missing = mid,
+            }
+        }
+
+        Ok(Some(head))
+    }
+
+    /// Find a frame the node has, starting from `start` and searching down from it, then trying
+    /// frame 1 and frame 0. Returns the frame and a higher frame number the node doesn't have, if
+    /// one was tried.
+    async fn find_frame(
+        &mut self,
+        filter: FrameFilter,
+        start: Option<u64>,
+    ) -> Result<Option<(ClockFrame, Option<u64>)>, NodeClientError> {
+        if let Some(start) = start {
+            let mut missing = None;
+            let mut frame_number = start;
+            let mut step = 1_u64;
+            loop {
+                if let Some(frame) = self.frame_metadata(filter.clone(), frame_number).await? {
+                    return Ok(Some((frame, missing)));
+                }
+                if frame_number == 0 {
+                    break;
+                }
+                missing = Some(frame_number);
+                frame_number = start.saturating_sub(step);
+                step = step.saturating_mul(2);
+            }
+        }
+        for frame_number in [1, 0] {
+            if let Some(frame) = self.frame_metadata(filter.clone(), frame_number).await? {
+                return Ok(Some((frame, None)));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn frame_metadata(
+        &mut self,
+        filter: FrameFilter,
+        frame_number: u64,
+    ) -> Result<Option<ClockFrame>, NodeClientError> {
+        let options = FramesOptions::new()
+            .filter(filter)
+            .from_frame_number(frame_number)
+            .to_frame_number(frame_number.saturating_add(1));
+        let response = self.frames(options).await?;
+        Ok(response
+            .truncated_clock_frames
+            .into_iter()
+            .find(|frame| frame.frame_number == frame_number))
+    }
+}
+
+/// Create the span of a node client method. The request parameters, the response size, the item
+/// count and the error kind are recorded on it once they are known.
+fn span(method: &'static str, uri: &Uri) -> tracing::Span {
+    tracing::debug_span!(
+        "node_client",
+        method,
+        endpoint = %uri,
+        filter = field::Empty,
+        frame_number = field::Empty,
+        from_frame_number = field::Empty,
+        to_frame_number = field::Empty,
+        include_candidates = field::Empty,
+        response_bytes = field::Empty,
+        items = field::Empty,
+        error_kind = field::Empty,
+        duration_ms = field::Empty,
+    )
+}
+
+/// Run a node client call in its span and record the kind of the error if it fails.
+async fn traced<T>(
+    span: tracing::Span,
+    call: impl Future<Output = Result<T, NodeClientError>>,
+) -> Result<T, NodeClientError> {
+    let result = call.instrument(span.clone()).await;
+    record_result(&span, result)
+}
+
+/// Run a node client call that is made of other calls in its span and record the kind of the
+/// error if it fails. The error isn't logged again, as the inner call that failed logged it.
+async fn traced_composite<T>(
+    span: tracing::Span,
+    call: impl Future<Output = Result<T, NodeClientError>>,
+) -> Result<T, NodeClientError> {
+    let result = call.instrument(span.clone()).await;
+    if let Err(error) = &result {
+        span.record("error_kind", error.kind());
+    }
+    result
+}
+
+/// Record the kind of the error on the span and log it if the call failed.
+fn record_result<T>(
+    span: &tracing::Span,
+    result: Result<T, NodeClientError>,
+) -> Result<T, NodeClientError> {
+    if let Err(error) = &result {
+        span.record("error_kind", error.kind());
+        span.in_scope(|| tracing::debug!(%error, "node client call failed"));
+    }
+    result
+}
+
+/// Record the encoded size and the number of items of a response on the current span.
+fn record_response(response: &impl prost::Message, items: usize) {
+    let span = tracing::Span::current();
+    span.record("response_bytes", response.encoded_len());
+    span.record("items", items);
 }
 
 /// Errors that can occur when interacting with a node.
@@ -131,6 +450,23 @@ pub enum NodeClientError {
     Transport(#[from] tonic::transport::Error),
 }
 
+impl NodeClientError {
+    /// A short, stable name of the kind of error, e.g. for logs and metrics labels.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidFrameFilter => "invalid_frame_filter",
+            Self::InvalidMultiaddr(_) => "invalid_multiaddr",
+            Self::InvalidPeerId(_) => "invalid_peer_id",
+            Self::InvalidTimestamp(_) => "invalid_timestamp",
+            Self::InvalidVersion(_) => "invalid_version",
+            Self::InvalidU64Bytes(_) => "invalid_u64_bytes",
+            Self::QuilTokenError(_) => "invalid_token_amount",
+            Self::Status(_) => "status",
+            Self::Transport(_) => "transport",
+        }
+    }
+}
+
 /// Options for a get frames request.
 pub struct FramesOptions {
     /// The frame filter.
@@ -288,6 +624,24 @@ impl Display for FrameFilter {
     }
 }
 
+impl FromStr for FrameFilter {
+    type Err = NodeClientError;
+
+    /// Parse a frame filter from its display form, e.g. "master-clock", or from 32 hex encoded
+    /// bytes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ceremony-application" => Ok(Self::CeremonyApplication),
+            "master-clock" => Ok(Self::MasterClock),
+            _ => {
+                let filter = s.strip_prefix("unknown-").unwrap_or(s);
+                let bytes = hex::decode(filter).map_err(|_| NodeClientError::InvalidFrameFilter)?;
+                bytes.try_into()
+            }
+        }
+    }
+}
+
 impl TryFrom<Vec<u8>> for FrameFilter {
     type Error = NodeClientError;
 
@@ -403,8 +757,8 @@ pub struct PeerInfo {
     /// The self-reported timestamp of the peer info data.
     #[serde(with = "chrono::serde::ts_milliseconds")]
     pub timestamp: DateTime<Utc>,
-    /// The protocol version triple of the peer.
-    pub version: [u8; 3],
+    /// The protocol version of the peer.
+    pub version: ProtocolVersion,
     /// The Ed448 signature of the peer attesting to the peer info.
     pub signature: Vec<u8>,
     /// The Ed448 public key of the peer that was used to sign the message. Must match the peer id.
@@ -424,16 +778,22 @@ impl TryFrom<node_pb::PeerInfo> for PeerInfo {
                 .collect::<Result<_, _>>()?,
             max_frame: value.max_frame,
             timestamp: convert_timestamp(value.timestamp)?,
-            version: value
-                .version
-                .try_into()
-                .map_err(NodeClientError::InvalidVersion)?,
+            version: <[u8; 3]>::try_from(value.version)
+                .map_err(NodeClientError::InvalidVersion)?
+                .into(),
             signature: value.signature,
             public_key: value.public_key,
         })
     }
 }
 
+impl PeerInfo {
+    /// The age of the peer info relative to `now`. Negative if it's timestamped in the future.
+    pub fn age(&self, now: DateTime<Utc>) -> chrono::Duration {
+        now - self.timestamp
+    }
+}
+
 /// Response for get peer manifests request.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct PeerManifestsResponse {
@@ -514,14 +874,86 @@ impl TryFrom<node_pb::PeerManifest> for PeerManifest {
     }
 }
 
+impl PeerManifest {
+    /// The time of computing the VDF test under the difficulty. `None` if the reported time is
+    /// negative.
+    pub fn difficulty_duration(&self) -> Option<Duration> {
+        millis_duration(self.difficulty_metric)
+    }
+
+    /// The time of computing a KZG commitment for a 16 degree polynomial. `None` if the reported
+    /// time is negative.
+    pub fn commit_16_duration(&self) -> Option<Duration> {
+        millis_duration(self.commit_16_metric)
+    }
+
+    /// The time of computing a KZG commitment for a 128 degree polynomial. `None` if the reported
+    /// time is negative.
+    pub fn commit_128_duration(&self) -> Option<Duration> {
+        millis_duration(self.commit_128_metric)
+    }
+
+    /// The time of computing a KZG commitment for a 1024 degree polynomial. `None` if the reported
+    /// time is negative.
+    pub fn commit_1024_duration(&self) -> Option<Duration> {
+        millis_duration(self.commit_1024_metric)
+    }
+
+    /// The time of computing a KZG commitment for a 65536 degree polynomial. `None` if the
+    /// reported time is negative.
+    pub fn commit_65536_duration(&self) -> Option<Duration> {
+        millis_duration(self.commit_65536_metric)
+    }
+
+    /// The time of computing a KZG proof for a 16 degree polynomial. `None` if the reported time
+    /// is negative.
+    pub fn proof_16_duration(&self) -> Option<Duration> {
+        millis_duration(self.proof_16_metric)
+    }
+
+    /// The time of computing a KZG proof for a 128 degree polynomial. `None` if the reported time
+    /// is negative.
+    pub fn proof_128_duration(&self) -> Option<Duration> {
+        millis_duration(self.proof_128_metric)
+    }
+
+    /// The time of computing a KZG proof for a 1024 degree polynomial. `None` if the reported time
+    /// is negative.
+    pub fn proof_1024_duration(&self) -> Option<Duration> {
+        millis_duration(self.proof_1024_metric)
+    }
+
+    /// The time of computing a KZG proof for a 65536 degree polynomial. `None` if the reported
+    /// time is negative.
+    pub fn proof_65536_duration(&self) -> Option<Duration> {
+        millis_duration(self.proof_65536_metric)
+    }
+
+    /// The total available memory.
+    pub fn memory_size(&self) -> ByteSize {
+        ByteSize(self.memory)
+    }
+
+    /// The total available storage.
+    pub fn storage_size(&self) -> ByteSize {
+        ByteSize(self.storage)
+    }
+}
+
 /// Token supply and balance from a node.
+///
+/// The amounts are serialized in the process-wide format set with
+/// [`AmountFormat::set_default`](crate::oblivious_transfer_units::AmountFormat::set_default).
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct TokenInfo {
     /// The token supply from confirmed frame data.
+    #[serde(with = "crate::oblivious_transfer_units::default_format")]
     pub confirmed_token_supply: ObliviousTransferUnits,
     /// The token supply, including unconfirmed frame data.
+    #[serde(with = "crate::oblivious_transfer_units::default_format")]
     pub unconfirmed_token_supply: ObliviousTransferUnits,
     /// The tokens owned by the node's address.
+    #[serde(with = "crate::oblivious_transfer_units::default_format")]
     pub owned_tokens: ObliviousTransferUnits,
 }
 
@@ -545,6 +977,10 @@ fn convert_timestamp(timestamp: i64) -> Result<DateTime<Utc>, NodeClientError> {
     }
 }
 
+fn millis_duration(millis: i64) -> Option<Duration> {
+    u64::try_from(millis).ok().map(Duration::from_millis)
+}
+
 fn u64_from_unpadded_be_bytes(bytes: Vec<u8>) -> Result<u64, NodeClientError> {
     const U64_BYTES: usize = std::mem::size_of::<u64>();
 
@@ -560,7 +996,222 @@ fn u64_from_unpadded_be_bytes(bytes: Vec<u8>) -> Result<u64, NodeClientError> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::span;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
     use super::*;
+    use crate::test_utils::{random_peer_id, FakeNode};
+
+    type Fields = BTreeMap<String, String>;
+
+    /// Collects the fields of every closed span and of every event, with the method of the span
+    /// the event happened in as `span_method`.
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: Arc<Mutex<Vec<Fields>>>,
+        events: Arc<Mutex<Vec<Fields>>>,
+    }
+
+    impl SpanRecorder {
+        fn span(&self, method: &str) -> Fields {
+            self.spans
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|fields| fields.get("method").map(String::as_str) == Some(method))
+                .unwrap()
+                .clone()
+        }
+    }
+
+    struct FieldVisitor<'a>(&'a mut Fields);
+
+    impl field::Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &field::Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S: tracing::Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+            let mut fields = Fields::new();
+            attrs.record(&mut FieldVisitor(&mut fields));
+            ctx.span(id).unwrap().extensions_mut().insert(fields);
+        }
+
+        fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+            let span = ctx.span(id).unwrap();
+            let mut extensions = span.extensions_mut();
+            values.record(&mut FieldVisitor(extensions.get_mut::<Fields>().unwrap()));
+        }
+
+        fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+            let mut fields = Fields::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            let method = ctx.event_span(event).and_then(|span| {
+                let extensions = span.extensions();
+                extensions.get::<Fields>()?.get("method").cloned()
+            });
+            if let Some(method) = method {
+                fields.insert("span_method".to_string(), method);
+            }
+            self.events.lock().unwrap().push(fields);
+        }
+
+        fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+            let span = ctx.span(&id).unwrap();
+            let fields = span.extensions_mut().remove::<Fields>().unwrap();
+            self.spans.lock().unwrap().push(fields);
+        }
+    }
+
+    #[tokio::test]
+    async fn spans() -> anyhow::Result<()> {
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let uri = FakeNode::new()
+            .with_head_frames(Some(2), None)
+            .serve()
+            .await;
+        let mut client = NodeClient::new(uri.clone()).await?;
+        let options = FramesOptions::new()
+            .filter(FrameFilter::CeremonyApplication)
+            .from_frame_number(0)
+            .to_frame_number(10);
+        client.frames(options).await?;
+        let mut failing = NodeClient::new(FakeNode::new().failing().serve().await).await?;
+        assert!(failing
+            .frame_info(FrameFilter::CeremonyApplication, 1)
+            .await
+            .is_err());
+
+        let connect = recorder.span("connect");
+        assert_eq!(connect["endpoint"], uri.to_string());
+        assert!(!connect.contains_key("error_kind"));
+        assert!(connect["duration_ms"].parse::<f64>()? >= 0.0);
+
+        let frames = recorder.span("frames");
+        assert_eq!(frames["endpoint"], uri.to_string());
+        assert_eq!(
+            frames["filter"],
+            FrameFilter::CeremonyApplication.to_string()
+        );
+        assert_eq!(frames["from_frame_number"], "0");
+        assert_eq!(frames["to_frame_number"], "10");
+        assert_eq!(frames["include_candidates"], "false");
+        assert_eq!(frames["items"], "3");
+        assert!(frames["response_bytes"].parse::<usize>()? > 0);
+
+        let frame_info = recorder.span("frame_info");
+        assert_eq!(frame_info["frame_number"], "1");
+        assert_eq!(frame_info["error_kind"], "status");
+        assert!(!frame_info.contains_key("items"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn nested_errors_are_logged_once() -> anyhow::Result<()> {
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let uri = FakeNode::new().failing().serve().await;
+        let mut client = NodeClient::new(uri).await?;
+        assert!(client.sync_status().await.is_err());
+
+        let failures: Vec<Fields> = recorder
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|fields| {
+                fields.get("message").map(String::as_str) == Some("node client call failed")
+            })
+            .cloned()
+            .collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0]["span_method"], "peer_info");
+        for method in ["sync_status", "head_frame", "peer_info"] {
+            assert_eq!(recorder.span(method)["error_kind"], "status");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn head_frame_with_culled_frames() -> anyhow::Result<()> {
+        let version = ProtocolVersion::new(1, 2, 0);
+        let head = |node: FakeNode| async move {
+            let mut client = NodeClient::new(node.serve().await).await?;
+            let ceremony = client.head_frame(FrameFilter::CeremonyApplication).await?;
+            let master = client.head_frame(FrameFilter::MasterClock).await?;
+            anyhow::Ok([ceremony, master].map(|frame| frame.map(|frame| frame.frame_number)))
+        };
+        let node = FakeNode::new()
+            .with_head_frames(Some(50_000), Some(20_000))
+            .with_first_frame(10_000);
+
+        // The peers are ahead of the node.
+        let ahead = node
+            .clone()
+            .with_peer_info(random_peer_id(), true, version, 50_100)
+            .with_peer_manifest(random_peer_id(), 20_010);
+        assert_eq!(head(ahead).await?, [Some(50_000), Some(20_000)]);
+
+        // The peers are behind the node.
+        let behind = node
+            .clone()
+            .with_peer_info(random_peer_id(), true, version, 40_000)
+            .with_peer_manifest(random_peer_id(), 15_000);
+        assert_eq!(head(behind).await?, [Some(50_000), Some(20_000)]);
+
+        // The node keeps every frame.
+        let uncut = FakeNode::new()
+            .with_head_frames(Some(90), Some(0))
+            .with_peer_info(random_peer_id(), true, version, 1_000);
+        assert_eq!(head(uncut).await?, [Some(90), Some(0)]);
+
+        let empty = FakeNode::new().with_peer_info(random_peer_id(), true, version, 100);
+        assert_eq!(head(empty).await?, [None, None]);
+        Ok(())
+    }
+
+    #[test]
+    fn frame_filter_from_str() -> Result<(), NodeClientError> {
+        for filter in [
+            FrameFilter::CeremonyApplication,
+            FrameFilter::MasterClock,
+            FrameFilter::Unknown([7; FRAME_FILTER_BYTES]),
+        ] {
+            assert_eq!(filter.to_string().parse::<FrameFilter>()?, filter);
+        }
+        assert_eq!(
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"
+                .parse::<FrameFilter>()?,
+            FrameFilter::MasterClock
+        );
+        assert!("ceremony".parse::<FrameFilter>().is_err());
+        assert!("ffff".parse::<FrameFilter>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_millis_duration() {
+        assert_eq!(millis_duration(0), Some(Duration::ZERO));
+        assert_eq!(millis_duration(340), Some(Duration::from_millis(340)));
+        assert_eq!(millis_duration(-1), None);
+    }
 
     #[test]
     fn test_u64_from_unpadded_be_bytes() -> Result<(), NodeClientError> {
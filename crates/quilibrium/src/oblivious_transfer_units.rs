@@ -1,27 +1,238 @@
 //! Quilibrium token quantities.
 
-use std::ops::Div;
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, Div, Sub};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 use lazy_static::lazy_static;
 use ruint::aliases::U256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 lazy_static! {
     static ref OT_UNIT_TO_QUIL_RATIO: U256 = U256::from(8_000_000_000_u64);
 }
 
+/// One oblivious transfer unit is 125 * 10^-12 QUIL, so 12 decimals are needed to display every
+/// amount exactly.
+const EXACT_DECIMALS: usize = 12;
+/// The number of 10^-12 QUIL in an oblivious transfer unit.
+const PICO_QUIL_PER_UNIT: u64 = 125;
+/// Amounts are displayed with at least this many decimals.
+const MIN_DISPLAY_DECIMALS: usize = 9;
+
 /// The maximum divisible unit of Quilibrium.
 /// Represents a single bit in an oblivious transfer.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+///
+/// Displayed as exact decimal QUIL with at least 9 decimals, e.g. "50.125000000", and parsed from
+/// decimal QUIL with a "QUIL" suffix, e.g. "1.5 QUIL", or from raw units, e.g. "12000000000".
+/// A precision can be set with the formatter, e.g. `format!("{:.2}", units)`, in which case the
+/// amount is rounded down to that many decimals.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 #[serde(transparent)]
 #[repr(transparent)]
 pub struct ObliviousTransferUnits(U256);
 
 impl ObliviousTransferUnits {
+    /// Zero units.
+    pub const ZERO: Self = Self(U256::ZERO);
+
     /// Convert to QUIL tokens (floored).
     /// One QUIL token corresponds to 8 * 10^9 oblivious transfer units.
     pub fn quil_tokens(&self) -> U256 {
         self.0.div(*OT_UNIT_TO_QUIL_RATIO)
     }
+
+    /// Convert to QUIL tokens as a float, e.g. for metrics. Large amounts lose precision.
+    pub fn to_quil_f64(&self) -> f64 {
+        f64::from(self.0) / f64::from(*OT_UNIT_TO_QUIL_RATIO)
+    }
+
+    /// The number of oblivious transfer units.
+    pub fn units(&self) -> U256 {
+        self.0
+    }
+
+    /// Convert QUIL tokens to units. Returns `None` on overflow.
+    pub fn from_quil_tokens(quil_tokens: U256) -> Option<Self> {
+        quil_tokens.checked_mul(*OT_UNIT_TO_QUIL_RATIO).map(Self)
+    }
+
+    /// The units as 32 big-endian bytes, the format used by the node.
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        self.0.to_be_bytes()
+    }
+
+    /// Add units. Returns `None` on overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    /// Add units, saturating at the maximum.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
+    /// Subtract units. Returns `None` if `rhs` is larger than `self`.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    /// Sum units. Returns `None` on overflow.
+    pub fn checked_sum(iter: impl IntoIterator<Item = Self>) -> Option<Self> {
+        iter.into_iter()
+            .try_fold(Self::ZERO, |sum, units| sum.checked_add(units))
+    }
+}
+
+impl Add for ObliviousTransferUnits {
+    type Output = Self;
+
+    /// Panics on overflow. Use [`ObliviousTransferUnits::checked_add`] to handle it.
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("oblivious transfer units overflow")
+    }
+}
+
+impl Sub for ObliviousTransferUnits {
+    type Output = Self;
+
+    /// Panics if `rhs` is larger than `self`. Use [`ObliviousTransferUnits::checked_sub`] to
+    /// handle it.
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("oblivious transfer units underflow")
+    }
+}
+
+impl Sum for ObliviousTransferUnits {
+    /// Panics on overflow. Use [`ObliviousTransferUnits::checked_sum`] to handle it.
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self::checked_sum(iter).expect("oblivious transfer units overflow")
+    }
+}
+
+impl<'a> Sum<&'a ObliviousTransferUnits> for ObliviousTransferUnits {
+    /// Panics on overflow. Use [`ObliviousTransferUnits::checked_sum`] to handle it.
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl Display for ObliviousTransferUnits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let quil_tokens = self.quil_tokens();
+        let remainder = self.0 - quil_tokens * *OT_UNIT_TO_QUIL_RATIO;
+        let pico_quil = remainder.to::<u64>() * PICO_QUIL_PER_UNIT;
+        let decimals = format!("{pico_quil:0EXACT_DECIMALS$}");
+        let decimals = match f.precision() {
+            Some(0) => return write!(f, "{quil_tokens}"),
+            Some(precision) if precision <= EXACT_DECIMALS => decimals[..precision].to_string(),
+            Some(precision) => format!("{decimals:0<precision$}"),
+            None => {
+                let trimmed = decimals.trim_end_matches('0');
+                decimals[..trimmed.len().max(MIN_DISPLAY_DECIMALS)].to_string()
+            }
+        };
+        write!(f, "{quil_tokens}.{decimals}")
+    }
+}
+
+impl FromStr for ObliviousTransferUnits {
+    type Err = QuilTokenError;
+
+    /// Parse decimal QUIL with a case-insensitive "QUIL" suffix, e.g. "1.5 QUIL", or raw units
+    /// without a suffix, e.g. "12000000000". The QUIL amount must be a whole number of units.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || QuilTokenError::InvalidString(s.to_string());
+        let trimmed = s.trim();
+        let quil = trimmed
+            .len()
+            .checked_sub(4)
+            .filter(|&index| trimmed.is_char_boundary(index))
+            .map(|index| trimmed.split_at(index))
+            .filter(|(_, suffix)| suffix.eq_ignore_ascii_case("quil"))
+            .map(|(amount, _)| amount.trim_end());
+
+        let Some(quil) = quil else {
+            if trimmed.is_empty() || !trimmed.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            return U256::from_str_radix(trimmed, 10)
+                .map(Self)
+                .map_err(|_| invalid());
+        };
+
+        let (integer, fraction) = quil.split_once('.').unwrap_or((quil, ""));
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (integer.is_empty() && fraction.is_empty())
+            || !is_digits(integer)
+            || !is_digits(fraction)
+            || fraction.len() > EXACT_DECIMALS
+        {
+            return Err(invalid());
+        }
+
+        let integer = if integer.is_empty() {
+            U256::ZERO
+        } else {
+            U256::from_str_radix(integer, 10).map_err(|_| invalid())?
+        };
+        let pico_quil: u64 = if fraction.is_empty() {
+            0
+        } else {
+            format!("{fraction:0<EXACT_DECIMALS$}")
+                .parse()
+                .map_err(|_| invalid())?
+        };
+        if pico_quil % PICO_QUIL_PER_UNIT != 0 {
+            return Err(invalid());
+        }
+
+        Self::from_quil_tokens(integer)
+            .and_then(|units| units.checked_add(Self(U256::from(pico_quil / PICO_QUIL_PER_UNIT))))
+            .ok_or_else(invalid)
+    }
+}
+
+impl From<U256> for ObliviousTransferUnits {
+    fn from(units: U256) -> Self {
+        Self(units)
+    }
+}
+
+impl From<ObliviousTransferUnits> for U256 {
+    fn from(units: ObliviousTransferUnits) -> Self {
+        units.0
+    }
+}
+
+impl From<[u8; 32]> for ObliviousTransferUnits {
+    /// Convert from big-endian bytes.
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(U256::from_be_bytes(bytes))
+    }
+}
+
+impl From<ObliviousTransferUnits> for [u8; 32] {
+    /// Convert to big-endian bytes.
+    fn from(units: ObliviousTransferUnits) -> Self {
+        units.to_be_bytes()
+    }
 }
 
 impl TryFrom<&[u8]> for ObliviousTransferUnits {
@@ -47,6 +258,183 @@ pub enum QuilTokenError {
     /// The provided bytes are not valid oblivious transfer units.
     #[error("The provided bytes are not valid oblivious transfer units.")]
     InvalidBytes(Vec<u8>),
+    /// The provided string is not a valid amount of QUIL or oblivious transfer units.
+    #[error("Invalid token amount: {0}")]
+    InvalidString(String),
+}
+
+/// The representations of amounts supported by the serde helper modules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AmountFormat {
+    /// A hex string of units, e.g. "0x2cb417800". The representation of `ObliviousTransferUnits`
+    /// itself.
+    #[default]
+    Hex,
+    /// A decimal string of units, e.g. "12000000000".
+    Units,
+    /// A decimal string of QUIL, e.g. "1.500000000".
+    Quil,
+}
+
+/// The format used by [`default_format`], stored as the index of the variant.
+static DEFAULT_FORMAT: AtomicU8 = AtomicU8::new(0);
+
+impl AmountFormat {
+    const ALL: [Self; 3] = [Self::Hex, Self::Units, Self::Quil];
+
+    /// The format used by [`default_format`] and the JSON of `TokenInfo`. Hex unless changed with
+    /// [`AmountFormat::set_default`].
+    pub fn default_format() -> Self {
+        Self::ALL[usize::from(DEFAULT_FORMAT.load(Ordering::Relaxed))]
+    }
+
+    /// Change the format used by [`default_format`] and the JSON of `TokenInfo` for the whole
+    /// process.
+    ///
+    /// The format is a process-wide global: it is shared by every crate in the process that uses
+    /// this library, so it should only be set by the application, once at startup. Prefer the
+    /// [`hex`], [`decimal_units`] and [`decimal_quil`] helpers on your own types.
+    pub fn set_default(format: Self) {
+        let index = Self::ALL
+            .iter()
+            .position(|candidate| *candidate == format)
+            .expect("all formats are listed");
+        DEFAULT_FORMAT.store(index as u8, Ordering::Relaxed);
+    }
+
+    fn serialize<S: Serializer>(
+        self,
+        units: &ObliviousTransferUnits,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        // Binary formats use the representation of `U256` that `deserialize` expects.
+        if !serializer.is_human_readable() {
+            return units.0.serialize(serializer);
+        }
+        match self {
+            Self::Hex => units.0.serialize(serializer),
+            Self::Units => serializer.collect_str(&units.0),
+            Self::Quil => serializer.collect_str(units),
+        }
+    }
+
+    /// Parse hex units with a "0x" prefix, decimal QUIL with a fraction or a "QUIL" suffix, or a
+    /// bare decimal number which is read as units or as QUIL depending on the format.
+    fn parse(self, s: &str) -> Result<ObliviousTransferUnits, QuilTokenError> {
+        let trimmed = s.trim();
+        if let Some(hex) = trimmed
+            .strip_prefix("0x")
+            .or_else(|| trimmed.strip_prefix("0X"))
+        {
+            return U256::from_str_radix(hex, 16)
+                .map(ObliviousTransferUnits)
+                .map_err(|_| QuilTokenError::InvalidString(s.to_string()));
+        }
+        let has_suffix = trimmed
+            .get(trimmed.len().saturating_sub(4)..)
+            .is_some_and(|suffix| suffix.eq_ignore_ascii_case("quil"));
+        if has_suffix || (self != Self::Quil && !trimmed.contains('.')) {
+            return trimmed.parse();
+        }
+        format!("{trimmed} QUIL")
+            .parse()
+            .map_err(|_| QuilTokenError::InvalidString(s.to_string()))
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<ObliviousTransferUnits, D::Error> {
+        if !deserializer.is_human_readable() {
+            return U256::deserialize(deserializer).map(ObliviousTransferUnits);
+        }
+        let s = String::deserialize(deserializer)?;
+        self.parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serde helpers for a hex string of units, e.g. "0x2cb417800". The same as the representation of
+/// `ObliviousTransferUnits`, but deserializes every [`AmountFormat`].
+pub mod hex {
+    use super::*;
+
+    /// Serialize the units in the format of this module.
+    pub fn serialize<S: Serializer>(
+        units: &ObliviousTransferUnits,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        AmountFormat::Hex.serialize(units, serializer)
+    }
+
+    /// Deserialize units in any format.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ObliviousTransferUnits, D::Error> {
+        AmountFormat::Hex.deserialize(deserializer)
+    }
+}
+
+/// Serde helpers for a decimal string of units, e.g. "12000000000". Deserializes every
+/// [`AmountFormat`], where bare numbers are units.
+pub mod decimal_units {
+    use super::*;
+
+    /// Serialize the units in the format of this module.
+    pub fn serialize<S: Serializer>(
+        units: &ObliviousTransferUnits,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        AmountFormat::Units.serialize(units, serializer)
+    }
+
+    /// Deserialize units in any format.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ObliviousTransferUnits, D::Error> {
+        AmountFormat::Units.deserialize(deserializer)
+    }
+}
+
+/// Serde helpers for a decimal string of QUIL, e.g. "1.500000000". Deserializes every
+/// [`AmountFormat`], where bare numbers are QUIL.
+pub mod decimal_quil {
+    use super::*;
+
+    /// Serialize the units in the format of this module.
+    pub fn serialize<S: Serializer>(
+        units: &ObliviousTransferUnits,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        AmountFormat::Quil.serialize(units, serializer)
+    }
+
+    /// Deserialize units in any format.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ObliviousTransferUnits, D::Error> {
+        AmountFormat::Quil.deserialize(deserializer)
+    }
+}
+
+/// Serde helpers for the format set with [`AmountFormat::set_default`]. Deserializes every
+/// [`AmountFormat`], where bare numbers are units.
+pub mod default_format {
+    use super::*;
+
+    /// Serialize the units in the format of this module.
+    pub fn serialize<S: Serializer>(
+        units: &ObliviousTransferUnits,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        AmountFormat::default_format().serialize(units, serializer)
+    }
+
+    /// Deserialize units in any format.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ObliviousTransferUnits, D::Error> {
+        AmountFormat::Units.deserialize(deserializer)
+    }
 }
 
 #[cfg(test)]
@@ -162,4 +550,201 @@ mod tests {
         let otu = ObliviousTransferUnits(U256::MAX);
         assert_eq!(otu.quil_tokens().log2(), 255 - OT_UNIT_TO_QUIL_RATIO.log2());
     }
+
+    #[test]
+    fn to_quil_f64() {
+        let otu = ObliviousTransferUnits(U256::from(12_000_000_000_u64));
+        assert_eq!(otu.to_quil_f64(), 1.5);
+        assert!(ObliviousTransferUnits(U256::MAX).to_quil_f64().is_finite());
+    }
+
+    #[test]
+    fn display_exact_decimals() {
+        let quil = |units: u64| ObliviousTransferUnits(U256::from(units)).to_string();
+        assert_eq!(quil(0), "0.000000000");
+        assert_eq!(quil(401_000_000_000), "50.125000000");
+        assert_eq!(quil(1), "0.000000000125");
+        assert_eq!(quil(8_000_000_008), "1.000000001");
+        let max = ObliviousTransferUnits(U256::MAX);
+        assert_eq!(
+            format!("{max} QUIL").parse::<ObliviousTransferUnits>(),
+            Ok(max)
+        );
+    }
+
+    #[test]
+    fn display_precision() {
+        let otu = ObliviousTransferUnits(U256::from(401_000_000_001_u64));
+        assert_eq!(format!("{otu:.0}"), "50");
+        assert_eq!(format!("{otu:.2}"), "50.12");
+        assert_eq!(format!("{otu:.12}"), "50.125000000125");
+        assert_eq!(format!("{otu:.14}"), "50.12500000012500");
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let units = |units: u64| ObliviousTransferUnits(U256::from(units));
+        assert_eq!(
+            "1.5 QUIL".parse::<ObliviousTransferUnits>()?,
+            units(12_000_000_000)
+        );
+        assert_eq!(
+            "1.5quil".parse::<ObliviousTransferUnits>()?,
+            units(12_000_000_000)
+        );
+        assert_eq!(
+            ".000000000125 QUIL".parse::<ObliviousTransferUnits>()?,
+            units(1)
+        );
+        assert_eq!(
+            "2 QUIL".parse::<ObliviousTransferUnits>()?,
+            units(16_000_000_000)
+        );
+        assert_eq!(
+            "12000000000".parse::<ObliviousTransferUnits>()?,
+            units(12_000_000_000)
+        );
+
+        for invalid in [
+            "",
+            "QUIL",
+            "1.5",
+            "-1 QUIL",
+            "1.0000000000001 QUIL",
+            "0.0000000001 QUIL",
+            "1e9",
+        ] {
+            assert!(
+                matches!(
+                    invalid.parse::<ObliviousTransferUnits>(),
+                    Err(QuilTokenError::InvalidString(_))
+                ),
+                "{invalid}"
+            );
+        }
+
+        let amount = units(401_000_000_001);
+        assert_eq!(
+            format!("{amount} QUIL").parse::<ObliviousTransferUnits>()?,
+            amount
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn arithmetic() {
+        let units = |units: u64| ObliviousTransferUnits(U256::from(units));
+        assert_eq!(units(1) + units(2), units(3));
+        assert_eq!(units(3) - units(2), units(1));
+        assert_eq!(units(1).checked_sub(units(2)), None);
+        assert_eq!(
+            ObliviousTransferUnits(U256::MAX).checked_add(units(1)),
+            None
+        );
+        assert_eq!(
+            [units(1), units(2)].iter().sum::<ObliviousTransferUnits>(),
+            units(3)
+        );
+        assert_eq!(
+            ObliviousTransferUnits::checked_sum([ObliviousTransferUnits(U256::MAX), units(1)]),
+            None
+        );
+        assert!(units(1) < units(2));
+    }
+
+    #[test]
+    fn conversions() {
+        let n = U256::from(2).pow(U256::from(192));
+        let otu = ObliviousTransferUnits::from(n);
+        let units: U256 = otu.into();
+        assert_eq!(units, n);
+        let bytes: [u8; 32] = otu.into();
+        assert_eq!(bytes, n.to_be_bytes::<32>());
+        assert_eq!(ObliviousTransferUnits::from(bytes), otu);
+    }
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Amounts {
+        #[serde(with = "hex")]
+        hex: ObliviousTransferUnits,
+        #[serde(with = "decimal_units")]
+        units: ObliviousTransferUnits,
+        #[serde(with = "decimal_quil")]
+        quil: ObliviousTransferUnits,
+    }
+
+    #[test]
+    fn serde_helpers() -> Result<()> {
+        let amount = ObliviousTransferUnits(U256::from(12_000_000_001_u64));
+        let amounts = Amounts {
+            hex: amount,
+            units: amount,
+            quil: amount,
+        };
+        let json = serde_json::to_string(&amounts)?;
+        assert_eq!(
+            json,
+            r#"{"hex":"0x2cb417801","units":"12000000001","quil":"1.500000000125"}"#
+        );
+        assert_eq!(serde_json::from_str::<Amounts>(&json)?, amounts);
+
+        // Every helper reads every format, bare numbers are read as units or QUIL.
+        let mixed = r#"{"hex":"12000000001","units":"1.500000000125 QUIL","quil":"0x2cb417801"}"#;
+        assert_eq!(serde_json::from_str::<Amounts>(mixed)?, amounts);
+        let bare = r#"{"hex":"0x0","units":"2","quil":"2"}"#;
+        let parsed: Amounts = serde_json::from_str(bare)?;
+        assert_eq!(parsed.units, ObliviousTransferUnits(U256::from(2)));
+        assert_eq!(
+            parsed.quil,
+            ObliviousTransferUnits::from_quil_tokens(U256::from(2)).unwrap()
+        );
+        assert!(
+            serde_json::from_str::<Amounts>(r#"{"hex":"0xg","units":"1","quil":"1"}"#).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn serde_helpers_binary() -> Result<()> {
+        let amount = ObliviousTransferUnits(U256::from(12_000_000_001_u64));
+        let amounts = Amounts {
+            hex: amount,
+            units: amount,
+            quil: amount,
+        };
+        let bytes = bincode::serialize(&amounts)?;
+        assert_eq!(bincode::deserialize::<Amounts>(&bytes)?, amounts);
+        Ok(())
+    }
+
+    // Serializes through the formats directly instead of changing the process-wide default,
+    // which other tests running in parallel would observe.
+    #[test]
+    fn default_format() -> Result<()> {
+        let units = ObliviousTransferUnits(U256::from(8_000_000_000_u64));
+        let serialize =
+            |format: AmountFormat| format.serialize(&units, serde_json::value::Serializer);
+        assert_eq!(serialize(AmountFormat::Hex)?, "0x1dcd65000");
+        assert_eq!(serialize(AmountFormat::Units)?, "8000000000");
+        assert_eq!(serialize(AmountFormat::Quil)?, "1.000000000");
+
+        let token_info = crate::node::TokenInfo {
+            confirmed_token_supply: units,
+            unconfirmed_token_supply: units,
+            owned_tokens: ObliviousTransferUnits(U256::from(1)),
+        };
+        let json = serde_json::json!({
+            "confirmed_token_supply": "1.000000000",
+            "unconfirmed_token_supply": "0x1dcd65000",
+            "owned_tokens": "1",
+        });
+        assert_eq!(
+            serde_json::from_value::<crate::node::TokenInfo>(json)?,
+            token_info
+        );
+
+        Ok(())
+    }
 }
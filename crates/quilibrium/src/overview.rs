@@ -0,0 +1,203 @@
+//! Collect an overview of a node for live monitoring, e.g. in a terminal dashboard.
+
+use std::time::Duration;
+
+use crate::manifest_stats::ManifestSummary;
+use crate::metrics::{timed, NodeMetrics, NodeResponses, RpcTiming};
+use crate::node::{ClockFrame, FrameFilter, FramesOptions, NodeClient};
+use crate::peer_directory::{PeerDirectory, PeerRecord};
+use crate::peer_scores::ScoreOptions;
+
+/// Options to collect a node overview.
+#[derive(Debug, Clone)]
+pub struct OverviewOptions {
+    /// The number of ceremony application frames up to the head to fetch for the frame
+    /// intervals.
+    pub recent_frames: u64,
+}
+
+impl Default for OverviewOptions {
+    fn default() -> Self {
+        Self { recent_frames: 60 }
+    }
+}
+
+impl OverviewOptions {
+    /// Create the default overview options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of recent frames to fetch.
+    pub fn recent_frames(mut self, recent_frames: u64) -> Self {
+        self.recent_frames = recent_frames;
+        self
+    }
+}
+
+/// The metrics, recent frames, peers and peer manifest statistics of a node. Parts whose calls
+/// failed are missing and the failures are recorded in the [`NodeMetrics::rpc`] of the metrics.
+#[derive(Debug, Clone)]
+pub struct NodeOverview {
+    /// The head frames, token info, peer counts, version distribution, sync status and call
+    /// outcomes.
+    pub metrics: NodeMetrics,
+    /// The most recent ceremony application frames in ascending order.
+    pub recent_frames: Vec<ClockFrame>,
+    /// The peers joined from the network info, peer info and peer manifests.
+    pub peers: PeerDirectory,
+    /// The statistics of the peer manifests.
+    pub manifests: Option<ManifestSummary>,
+}
+
+impl NodeOverview {
+    /// Collect the overview of a node with the same calls as [`NodeMetrics::collect`] and a call
+    /// for the recent frames.
+    pub async fn collect(client: &mut NodeClient, options: &OverviewOptions) -> Self {
+        let mut responses = NodeResponses::fetch(client, None).await;
+
+        let mut recent_frames = Vec::new();
+        if let Some(Some(head)) = &responses.ceremony_head {
+            let frames_options = FramesOptions::new()
+                .filter(FrameFilter::CeremonyApplication)
+                .from_frame_number((head.frame_number + 1).saturating_sub(options.recent_frames))
+                .to_frame_number(head.frame_number + 1);
+            let frames = client.frames(frames_options);
+            if let Some(response) = timed(&mut responses.rpc, None, "frames", None, frames).await {
+                recent_frames = response.truncated_clock_frames;
+            }
+            recent_frames.sort_by_key(|frame| frame.frame_number);
+        }
+
+        let metrics = NodeMetrics::new(&responses, &ScoreOptions::new());
+        let manifests = responses.peer_manifests.as_ref().map(ManifestSummary::new);
+        let mut peers = PeerDirectory::new();
+        if let Some(network_info) = responses.network_info {
+            peers = peers.with_network_info(network_info);
+        }
+        if let Some(peer_info) = responses.peer_info {
+            peers = peers.with_peer_info(peer_info);
+        }
+        if let Some(peer_manifests) = responses.peer_manifests {
+            peers = peers.with_peer_manifests(peer_manifests);
+        }
+
+        Self {
+            metrics,
+            recent_frames,
+            peers,
+            manifests,
+        }
+    }
+
+    /// The head frame for a frame filter.
+    pub fn head_frame(&self, filter: &FrameFilter) -> Option<&ClockFrame> {
+        self.metrics.head_frame(filter)
+    }
+
+    /// The calls that failed.
+    pub fn failed_calls(&self) -> impl Iterator<Item = &RpcTiming> {
+        self.metrics
+            .rpc
+            .iter()
+            .filter(|timing| timing.error.is_some())
+    }
+
+    /// The time between consecutive recent frames. Frames that are older than their predecessor
+    /// count as zero.
+    pub fn frame_intervals(&self) -> Vec<Duration> {
+        self.recent_frames
+            .windows(2)
+            .map(|pair| {
+                (pair[1].timestamp - pair[0].timestamp)
+                    .to_std()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// The peers with a peer score from the highest to the lowest score.
+    pub fn scored_peers(&self) -> Vec<&PeerRecord> {
+        let mut peers: Vec<&PeerRecord> = self
+            .peers
+            .records()
+            .filter(|record| record.peer_score().is_some())
+            .collect();
+        peers.sort_by(|a, b| {
+            let score = |record: &PeerRecord| record.peer_score().unwrap_or_default();
+            score(b).total_cmp(&score(a))
+        });
+        peers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::PeerId;
+    use crate::protocol_version::ProtocolVersion;
+    use crate::test_utils::{random_peer_id, FakeNode};
+
+    #[tokio::test]
+    async fn collect() -> anyhow::Result<()> {
+        let (good, bad) = (random_peer_id(), random_peer_id());
+        let version = ProtocolVersion::new(1, 2, 0);
+        let uri = FakeNode::new()
+            .with_token_info(16_000_000_000, 20_000_000_000, 4_000_000_000)
+            .with_head_frames(Some(90), Some(7))
+            .with_peer_info(good, true, version, 90)
+            .with_peer_info(bad, false, version, 50)
+            .with_network_info(good, 10.0)
+            .with_network_info(bad, -20.0)
+            .with_peer_manifest(good, 7)
+            .serve()
+            .await;
+        let mut client = NodeClient::new(uri).await?;
+        let options = OverviewOptions::new().recent_frames(10);
+        let overview = NodeOverview::collect(&mut client, &options).await;
+
+        assert_eq!(overview.failed_calls().count(), 0);
+        let ceremony_head = overview.head_frame(&FrameFilter::CeremonyApplication);
+        assert_eq!(ceremony_head.map(|frame| frame.frame_number), Some(90));
+        let master_head = overview.head_frame(&FrameFilter::MasterClock);
+        assert_eq!(master_head.map(|frame| frame.frame_number), Some(7));
+        let recent: Vec<u64> = overview
+            .recent_frames
+            .iter()
+            .map(|frame| frame.frame_number)
+            .collect();
+        assert_eq!(recent, (81..=90).collect::<Vec<_>>());
+        assert_eq!(overview.frame_intervals().len(), 9);
+        assert!(overview.metrics.token_info.is_some());
+        assert!(overview
+            .metrics
+            .rpc
+            .iter()
+            .any(|timing| timing.method == "frames"));
+
+        let versions = overview.metrics.versions.as_ref().unwrap();
+        assert_eq!(versions.versions[0].cooperative_peers, 1);
+        assert_eq!(versions.versions[0].uncooperative_peers, 1);
+        assert_eq!(overview.manifests.as_ref().unwrap().peers.len(), 1);
+        let scored: Vec<PeerId> = overview
+            .scored_peers()
+            .iter()
+            .map(|record| record.peer_id)
+            .collect();
+        assert_eq!(scored, vec![good, bad]);
+        assert!(overview.peers.get(&good).unwrap().manifest.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn collect_without_frames() -> anyhow::Result<()> {
+        let uri = FakeNode::new().serve().await;
+        let mut client = NodeClient::new(uri).await?;
+        let overview = NodeOverview::collect(&mut client, &OverviewOptions::new()).await;
+        assert!(overview.metrics.head_frames.is_empty());
+        assert!(overview.recent_frames.is_empty());
+        assert!(overview.frame_intervals().is_empty());
+        assert!(overview.scored_peers().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+//! Join the data about peers from the network info, peer info and peer manifests of a node.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use multiaddr::Multiaddr;
+
+use crate::node::{
+    NetworkInfo, NetworkInfoResponse, PeerId, PeerInfo, PeerInfoResponse, PeerManifest,
+    PeerManifestsResponse,
+};
+use crate::protocol_version::ProtocolVersion;
+
+/// Everything a node knows about a peer. Each source is optional as a peer may only appear in
+/// some of them.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerRecord {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// The peer from the node's peer store.
+    pub network_info: Option<NetworkInfo>,
+    /// The broadcasted sync info of the peer.
+    pub peer_info: Option<PeerInfo>,
+    /// Whether the peer is cooperative. `None` if there is no sync info for the peer.
+    pub cooperative: Option<bool>,
+    /// The self-reported peer manifest of the peer.
+    pub manifest: Option<PeerManifest>,
+}
+
+impl PeerRecord {
+    /// Create a peer record without any data.
+    pub fn new(peer_id: PeerId) -> Self {
+        Self {
+            peer_id,
+            network_info: None,
+            peer_info: None,
+            cooperative: None,
+            manifest: None,
+        }
+    }
+
+    /// The unique multiaddrs of the peer from the peer store and the sync info.
+    pub fn multiaddrs(&self) -> Vec<&Multiaddr> {
+        let mut multiaddrs: Vec<&Multiaddr> = Vec::new();
+        let network_info = self.network_info.iter().flat_map(|info| &info.multiaddrs);
+        let peer_info = self.peer_info.iter().flat_map(|info| &info.multiaddrs);
+        for multiaddr in network_info.chain(peer_info) {
+            if !multiaddrs.contains(&multiaddr) {
+                multiaddrs.push(multiaddr);
+            }
+        }
+        multiaddrs
+    }
+
+    /// The peer score by the node.
+    pub fn peer_score(&self) -> Option<f64> {
+        self.network_info.as_ref().map(|info| info.peer_score)
+    }
+
+    /// The protocol version of the peer.
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        self.peer_info.as_ref().map(|info| info.version)
+    }
+
+    /// The maximum ceremony frame number reported by the peer.
+    pub fn max_frame(&self) -> Option<u64> {
+        self.peer_info.as_ref().map(|info| info.max_frame)
+    }
+
+    /// The self-reported timestamp of the sync info of the peer.
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.peer_info.as_ref().map(|info| info.timestamp)
+    }
+}
+
+/// Peer records joined by peer ID from the network info, peer info and peer manifests of a node.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerDirectory {
+    records: BTreeMap<PeerId, PeerRecord>,
+}
+
+impl PeerDirectory {
+    /// Create an empty peer directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the peers from the node's peer store.
+    pub fn with_network_info(mut self, network_info: NetworkInfoResponse) -> Self {
+        for info in network_info.network_info {
+            let peer_id = info.peer_id;
+            self.record_mut(peer_id).network_info = Some(info);
+        }
+        self
+    }
+
+    /// Add the broadcasted sync info. If a peer is both in the cooperative and the uncooperative
+    /// list, it's considered cooperative.
+    pub fn with_peer_info(mut self, peer_info: PeerInfoResponse) -> Self {
+        let peers = peer_info.peers.into_iter().map(|info| (info, true));
+        let uncooperative_peers = peer_info
+            .uncooperative_peers
+            .into_iter()
+            .map(|info| (info, false));
+        for (info, cooperative) in peers.chain(uncooperative_peers) {
+            let record = self.record_mut(info.peer_id);
+            if record.cooperative == Some(true) {
+                continue;
+            }
+            record.cooperative = Some(cooperative);
+            record.peer_info = Some(info);
+        }
+        self
+    }
+
+    /// Add the self-reported peer manifests.
+    pub fn with_peer_manifests(mut self, peer_manifests: PeerManifestsResponse) -> Self {
+        for manifest in peer_manifests.peer_manifests {
+            let peer_id = manifest.peer_id;
+            self.record_mut(peer_id).manifest = Some(manifest);
+        }
+        self
+    }
+
+    /// Get the record of a peer.
+    pub fn get(&self, peer_id: &PeerId) -> Option<&PeerRecord> {
+        self.records.get(peer_id)
+    }
+
+    /// The records ordered by peer ID.
+    pub fn records(&self) -> impl Iterator<Item = &PeerRecord> {
+        self.records.values()
+    }
+
+    /// The number of peers in the directory.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether the directory is empty.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    fn record_mut(&mut self, peer_id: PeerId) -> &mut PeerRecord {
+        self.records
+            .entry(peer_id)
+            .or_insert_with(|| PeerRecord::new(peer_id))
+    }
+}
+
+impl IntoIterator for PeerDirectory {
+    type Item = PeerRecord;
+    type IntoIter = std::collections::btree_map::IntoValues<PeerId, PeerRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.into_values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{peer_info, peer_manifest, random_peer_id};
+
+    #[test]
+    fn joins_sources_by_peer_id() {
+        let version = ProtocolVersion::new(1, 2, 0);
+        let ids: Vec<PeerId> = (0..3).map(|_| random_peer_id()).collect();
+        let shared: Multiaddr = "/ip4/1.2.3.4/udp/8336/quic".parse().unwrap();
+        let other: Multiaddr = "/ip4/1.2.3.4/tcp/8336".parse().unwrap();
+
+        let network_info = NetworkInfoResponse {
+            network_info: vec![NetworkInfo {
+                peer_id: ids[0],
+                multiaddrs: vec![shared.clone()],
+                peer_score: -1.5,
+            }],
+        };
+        let peer_infos = PeerInfoResponse {
+            peers: vec![PeerInfo {
+                multiaddrs: vec![shared.clone(), other.clone()],
+                max_frame: 42,
+                ..peer_info(ids[0], version)
+            }],
+            uncooperative_peers: vec![peer_info(ids[1], version), peer_info(ids[0], version)],
+        };
+        let manifests = PeerManifestsResponse {
+            peer_manifests: vec![peer_manifest(ids[2])],
+        };
+
+        let directory = PeerDirectory::new()
+            .with_network_info(network_info)
+            .with_peer_info(peer_infos)
+            .with_peer_manifests(manifests);
+        assert_eq!(directory.len(), 3);
+
+        let full = directory.get(&ids[0]).unwrap();
+        assert_eq!(full.peer_score(), Some(-1.5));
+        assert_eq!(full.max_frame(), Some(42));
+        assert_eq!(full.cooperative, Some(true));
+        assert_eq!(full.multiaddrs(), vec![&shared, &other]);
+        assert!(full.manifest.is_none());
+
+        let uncooperative = directory.get(&ids[1]).unwrap();
+        assert_eq!(uncooperative.cooperative, Some(false));
+        assert_eq!(uncooperative.peer_score(), None);
+
+        let manifest_only = directory.get(&ids[2]).unwrap();
+        assert!(manifest_only.manifest.is_some());
+        assert_eq!(manifest_only.cooperative, None);
+        assert_eq!(manifest_only.version(), None);
+    }
+}
@@ -0,0 +1,350 @@
+//! Analyze the scores a node assigns to the peers in its peer store.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::node::{NetworkInfoResponse, PeerId};
+use crate::snapshot::NetworkSnapshot;
+use crate::stats::percentile;
+
+/// The percentiles reported in the score statistics.
+pub const SCORE_PERCENTILES: [f64; 7] = [1.0, 10.0, 25.0, 50.0, 75.0, 90.0, 99.0];
+
+/// The maximum number of histogram buckets. Wider buckets are used if the scores span more.
+pub const MAX_HISTOGRAM_BUCKETS: usize = 1_000;
+
+/// Options for analyzing peer scores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreOptions {
+    /// Peers with a score below this are reported as penalized.
+    pub threshold: f64,
+    /// The width of the histogram buckets.
+    pub bucket_width: f64,
+}
+
+impl Default for ScoreOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.0,
+            bucket_width: 10.0,
+        }
+    }
+}
+
+impl ScoreOptions {
+    /// Create a new score options builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the score below which peers are reported as penalized.
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the width of the histogram buckets. Must be positive. The buckets are widened if the
+    /// scores span more than [`MAX_HISTOGRAM_BUCKETS`] buckets.
+    pub fn bucket_width(mut self, bucket_width: f64) -> Self {
+        self.bucket_width = bucket_width;
+        self
+    }
+}
+
+/// The score of a peer.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerScore {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// The peer score by the node.
+    pub score: f64,
+}
+
+/// Statistics of the scores of the peers in a node's peer store.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScoreStats {
+    /// The number of peers with a finite score.
+    pub peers: usize,
+    /// The lowest score.
+    pub min: Option<f64>,
+    /// The highest score.
+    pub max: Option<f64>,
+    /// The mean score.
+    pub mean: Option<f64>,
+    /// The scores at [`SCORE_PERCENTILES`] as `(percentile, score)` pairs.
+    pub percentiles: Vec<(f64, f64)>,
+    /// The number of peers per score range from the lowest to the highest range. Empty ranges
+    /// between the lowest and the highest score are included.
+    pub histogram: Vec<HistogramBucket>,
+    /// The peers with a score below the threshold ordered by score ascending.
+    pub penalized: Vec<PeerScore>,
+}
+
+/// The number of peers with a score in a range.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HistogramBucket {
+    /// The lower bound of the range, inclusive.
+    pub lower: f64,
+    /// The upper bound of the range, exclusive.
+    pub upper: f64,
+    /// The number of peers with a score in the range.
+    pub peers: usize,
+}
+
+impl ScoreStats {
+    /// Compute the statistics of the peer scores. Peers with a score that isn't finite are ignored.
+    pub fn new(network_info: &NetworkInfoResponse, options: &ScoreOptions) -> Self {
+        let mut scores: Vec<PeerScore> = network_info
+            .network_info
+            .iter()
+            .filter(|info| info.peer_score.is_finite())
+            .map(|info| PeerScore {
+                peer_id: info.peer_id,
+                score: info.peer_score,
+            })
+            .collect();
+        scores.sort_by(|a, b| a.score.total_cmp(&b.score));
+        let sorted: Vec<f64> = scores.iter().map(|peer| peer.score).collect();
+
+        let mean = (!sorted.is_empty()).then(|| sorted.iter().sum::<f64>() / sorted.len() as f64);
+        let percentiles = SCORE_PERCENTILES
+            .iter()
+            .filter_map(|&p| Some((p, percentile(&sorted, p)?)))
+            .collect();
+        let histogram = histogram(&sorted, options.bucket_width);
+        let penalized = scores
+            .into_iter()
+            .take_while(|peer| peer.score < options.threshold)
+            .collect();
+
+        Self {
+            peers: sorted.len(),
+            min: sorted.first().copied(),
+            max: sorted.last().copied(),
+            mean,
+            percentiles,
+            histogram,
+            penalized,
+        }
+    }
+}
+
+fn histogram(sorted: &[f64], bucket_width: f64) -> Vec<HistogramBucket> {
+    let (Some(&min), Some(&max)) = (sorted.first(), sorted.last()) else {
+        return vec![];
+    };
+    let single_bucket = || {
+        vec![HistogramBucket {
+            lower: min,
+            upper: max,
+            peers: sorted.len(),
+        }]
+    };
+    if !bucket_width.is_finite() || bucket_width <= 0.0 || !(max - min).is_finite() {
+        return single_bucket();
+    }
+
+    let width = bucket_width.max((max - min) / (MAX_HISTOGRAM_BUCKETS - 1) as f64);
+    let first = (min / width).floor();
+    let last = (max / width).floor();
+    if !first.is_finite() || !last.is_finite() {
+        return single_bucket();
+    }
+    // Float casts saturate, the count and the indices are clamped to the buckets.
+    let count = ((last - first) as usize).saturating_add(1);
+    let mut buckets: Vec<HistogramBucket> = (0..count.min(MAX_HISTOGRAM_BUCKETS))
+        .map(|index| HistogramBucket {
+            lower: (first + index as f64) * width,
+            upper: (first + index as f64 + 1.0) * width,
+            peers: 0,
+        })
+        .collect();
+    let last_index = buckets.len() - 1;
+    for score in sorted {
+        let index = ((score / width).floor() - first) as usize;
+        buckets[index.min(last_index)].peers += 1;
+    }
+    buckets
+}
+
+/// The scores of a peer over time.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScoreTrend {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// The score of the peer in each snapshot that has it, ordered by time.
+    pub samples: Vec<ScoreSample>,
+}
+
+/// The score of a peer at a point in time.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScoreSample {
+    /// The time when the snapshot was captured.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub captured_at: DateTime<Utc>,
+    /// The peer score by the node.
+    pub score: f64,
+}
+
+impl ScoreTrend {
+    /// The score trend of each peer in the snapshots ordered by peer ID. The snapshots don't have
+    /// to be ordered.
+    pub fn from_snapshots<'a>(
+        snapshots: impl IntoIterator<Item = &'a NetworkSnapshot>,
+    ) -> Vec<Self> {
+        let mut snapshots: Vec<&NetworkSnapshot> = snapshots.into_iter().collect();
+        snapshots.sort_by_key(|snapshot| snapshot.captured_at);
+
+        let mut trends: BTreeMap<PeerId, Vec<ScoreSample>> = BTreeMap::new();
+        for snapshot in snapshots {
+            for info in &snapshot.network_info.network_info {
+                trends.entry(info.peer_id).or_default().push(ScoreSample {
+                    captured_at: snapshot.captured_at,
+                    score: info.peer_score,
+                });
+            }
+        }
+        trends
+            .into_iter()
+            .map(|(peer_id, samples)| ScoreTrend { peer_id, samples })
+            .collect()
+    }
+
+    /// The first sample.
+    pub fn first(&self) -> Option<&ScoreSample> {
+        self.samples.first()
+    }
+
+    /// The last sample.
+    pub fn last(&self) -> Option<&ScoreSample> {
+        self.samples.last()
+    }
+
+    /// The change of the score from the first to the last sample.
+    pub fn change(&self) -> Option<f64> {
+        Some(self.last()?.score - self.first()?.score)
+    }
+
+    /// The change of the score per hour from the first to the last sample. Returns `None` if there
+    /// are fewer than two samples at different times.
+    pub fn change_per_hour(&self) -> Option<f64> {
+        let (first, last) = (self.first()?, self.last()?);
+        let hours = (last.captured_at - first.captured_at).num_milliseconds() as f64 / 3_600_000.0;
+        (hours > 0.0).then_some((last.score - first.score) / hours)
+    }
+
+    /// Whether the score dropped from above the threshold to below it, i.e. the node started
+    /// penalizing the peer.
+    pub fn crossed_below(&self, threshold: f64) -> bool {
+        match (self.first(), self.last()) {
+            (Some(first), Some(last)) => first.score >= threshold && last.score < threshold,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::node::{PeerInfoResponse, PeerManifestsResponse};
+    use crate::test_utils::{network_info, random_peer_id};
+
+    fn snapshot(hour: u32, scores: &[(PeerId, f64)]) -> NetworkSnapshot {
+        NetworkSnapshot {
+            captured_at: Utc.with_ymd_and_hms(2023, 11, 1, hour, 0, 0).unwrap(),
+            network_info: network_info(scores),
+            peer_info: PeerInfoResponse {
+                peers: vec![],
+                uncooperative_peers: vec![],
+            },
+            peer_manifests: PeerManifestsResponse {
+                peer_manifests: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn stats() {
+        let peers: Vec<PeerId> = (0..5).map(|_| random_peer_id()).collect();
+        let response = network_info(&[
+            (peers[0], -25.0),
+            (peers[1], -1.0),
+            (peers[2], 0.0),
+            (peers[3], 12.0),
+            (peers[4], f64::NAN),
+        ]);
+        let stats = ScoreStats::new(&response, &ScoreOptions::new());
+
+        assert_eq!(stats.peers, 4);
+        assert_eq!(stats.min, Some(-25.0));
+        assert_eq!(stats.max, Some(12.0));
+        assert_eq!(stats.mean, Some(-3.5));
+        assert!(stats.percentiles.contains(&(50.0, -0.5)));
+
+        let histogram: Vec<(f64, usize)> = stats
+            .histogram
+            .iter()
+            .map(|bucket| (bucket.lower, bucket.peers))
+            .collect();
+        assert_eq!(
+            histogram,
+            vec![(-30.0, 1), (-20.0, 0), (-10.0, 1), (0.0, 1), (10.0, 1)]
+        );
+
+        let penalized: Vec<PeerId> = stats.penalized.iter().map(|peer| peer.peer_id).collect();
+        assert_eq!(penalized, vec![peers[0], peers[1]]);
+
+        let strict = ScoreStats::new(&response, &ScoreOptions::new().threshold(-10.0));
+        assert_eq!(strict.penalized.len(), 1);
+    }
+
+    #[test]
+    fn stats_without_peers() {
+        let stats = ScoreStats::new(&network_info(&[]), &ScoreOptions::new());
+        assert_eq!(stats.peers, 0);
+        assert_eq!(stats.mean, None);
+        assert!(stats.percentiles.is_empty());
+        assert!(stats.histogram.is_empty());
+    }
+
+    #[test]
+    fn histogram_is_bounded() {
+        let buckets = histogram(&[0.0, 50.0, 100.0], 1e-300);
+        assert!(buckets.len() <= MAX_HISTOGRAM_BUCKETS);
+        assert_eq!(buckets.iter().map(|bucket| bucket.peers).sum::<usize>(), 3);
+        assert_eq!(buckets.last().unwrap().peers, 1);
+
+        let buckets = histogram(&[-1e300, 0.0, 1e300], 10.0);
+        assert!(buckets.len() <= MAX_HISTOGRAM_BUCKETS);
+        assert_eq!(buckets.iter().map(|bucket| bucket.peers).sum::<usize>(), 3);
+
+        let buckets = histogram(&[f64::MIN, f64::MAX], 10.0);
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].peers, 2);
+    }
+
+    #[test]
+    fn trends() {
+        let (a, b) = (random_peer_id(), random_peer_id());
+        let snapshots = [
+            snapshot(2, &[(a, -4.0), (b, 5.0)]),
+            snapshot(0, &[(a, 2.0)]),
+        ];
+        let trends = ScoreTrend::from_snapshots(&snapshots);
+        assert_eq!(trends.len(), 2);
+
+        let trend_a = trends.iter().find(|trend| trend.peer_id == a).unwrap();
+        assert_eq!(trend_a.samples.len(), 2);
+        assert_eq!(trend_a.change(), Some(-6.0));
+        assert_eq!(trend_a.change_per_hour(), Some(-3.0));
+        assert!(trend_a.crossed_below(0.0));
+
+        let trend_b = trends.iter().find(|trend| trend.peer_id == b).unwrap();
+        assert_eq!(trend_b.change(), Some(0.0));
+        assert_eq!(trend_b.change_per_hour(), None);
+        assert!(!trend_b.crossed_below(0.0));
+    }
+}
@@ -0,0 +1,383 @@
+//! Probe whether the multiaddrs of peers are reachable from this host.
+//!
+//! TCP multiaddrs are probed by opening a connection. QUIC multiaddrs are probed by sending a
+//! packet with a reserved QUIC version which servers must answer with a version negotiation
+//! packet, so no TLS handshake is needed. Plain UDP multiaddrs can't be probed without knowing the
+//! protocol on top, so they're reported as unsupported.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use multiaddr::{Multiaddr, Protocol};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::multiaddrs::{MultiaddrClass, Transport};
+use crate::node::PeerId;
+
+/// A QUIC version reserved for forcing version negotiation (RFC 9000 section 15).
+const RESERVED_QUIC_VERSION: [u8; 4] = [0x1a, 0x2a, 0x3a, 0x4a];
+/// Servers only answer datagrams that are at least this large (RFC 9000 section 14.1).
+const MIN_QUIC_INITIAL_DATAGRAM_BYTES: usize = 1200;
+
+/// Options for probing multiaddrs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeOptions {
+    /// How long to wait for a multiaddr to respond.
+    pub timeout: Duration,
+    /// The maximum number of multiaddrs to probe at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for ProbeOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            concurrency: 64,
+        }
+    }
+}
+
+impl ProbeOptions {
+    /// Create a new probe options builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how long to wait for a multiaddr to respond.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of multiaddrs to probe at the same time. At least one multiaddr is
+    /// probed at a time.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+}
+
+/// The outcome of probing a multiaddr.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ProbeOutcome {
+    /// The multiaddr responded.
+    Reachable,
+    /// The connection failed, e.g. it was refused or the response was invalid.
+    Unreachable(String),
+    /// The multiaddr didn't respond in time.
+    Timeout,
+    /// The multiaddr can't be probed, e.g. because its transport is unknown.
+    Unsupported,
+}
+
+/// The result of probing a multiaddr of a peer.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProbeResult {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// The probed multiaddr.
+    pub multiaddr: Multiaddr,
+    /// The transport of the multiaddr.
+    pub transport: Transport,
+    /// The outcome of the probe.
+    pub outcome: ProbeOutcome,
+    /// The time it took to get a response. Only set if the multiaddr is reachable.
+    pub latency: Option<Duration>,
+}
+
+impl ProbeResult {
+    /// Whether the multiaddr responded.
+    pub fn is_reachable(&self) -> bool {
+        self.outcome == ProbeOutcome::Reachable
+    }
+}
+
+/// Probe the multiaddrs of peers concurrently. The results are in the same order as the targets.
+pub async fn probe(
+    targets: impl IntoIterator<Item = (PeerId, Multiaddr)>,
+    options: &ProbeOptions,
+) -> Vec<ProbeResult> {
+    let semaphore = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let mut join_set = JoinSet::new();
+
+    for (index, (peer_id, multiaddr)) in targets.into_iter().enumerate() {
+        let semaphore = semaphore.clone();
+        let timeout = options.timeout;
+        join_set.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            (index, probe_multiaddr(peer_id, multiaddr, timeout).await)
+        });
+    }
+
+    let mut results = Vec::with_capacity(join_set.len());
+    while let Some(result) = join_set.join_next().await {
+        results.push(result.expect("probe task doesn't panic"));
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Probe a single multiaddr of a peer.
+pub async fn probe_multiaddr(
+    peer_id: PeerId,
+    multiaddr: Multiaddr,
+    timeout: Duration,
+) -> ProbeResult {
+    let transport = MultiaddrClass::new(&multiaddr).transport;
+    let start = Instant::now();
+    let outcome = match tokio::time::timeout(timeout, connect(&multiaddr, transport)).await {
+        Ok(Ok(true)) => ProbeOutcome::Reachable,
+        Ok(Ok(false)) => ProbeOutcome::Unsupported,
+        Ok(Err(err)) => ProbeOutcome::Unreachable(err.to_string()),
+        Err(_) => ProbeOutcome::Timeout,
+    };
+    let latency = (outcome == ProbeOutcome::Reachable).then(|| start.elapsed());
+
+    ProbeResult {
+        peer_id,
+        multiaddr,
+        transport,
+        outcome,
+        latency,
+    }
+}
+
+/// Returns `Ok(false)` if the multiaddr can't be probed.
+async fn connect(multiaddr: &Multiaddr, transport: Transport) -> std::io::Result<bool> {
+    if matches!(transport, Transport::Udp | Transport::Other) {
+        return Ok(false);
+    }
+    let Some(addr) = resolve(multiaddr).await? else {
+        return Ok(false);
+    };
+    match transport {
+        Transport::Tcp => {
+            TcpStream::connect(addr).await?;
+            Ok(true)
+        }
+        Transport::Quic => {
+            quic_version_negotiation(addr).await?;
+            Ok(true)
+        }
+        Transport::Udp | Transport::Other => Ok(false),
+    }
+}
+
+/// Resolve the first host and port of the multiaddr to a socket address. DNS names are resolved
+/// with the system resolver.
+async fn resolve(multiaddr: &Multiaddr) -> std::io::Result<Option<SocketAddr>> {
+    let mut ip = None;
+    let mut name = None;
+    let mut port = None;
+    for protocol in multiaddr.iter() {
+        match protocol {
+            Protocol::Ip4(addr) if ip.is_none() && name.is_none() => ip = Some(IpAddr::V4(addr)),
+            Protocol::Ip6(addr) if ip.is_none() && name.is_none() => ip = Some(IpAddr::V6(addr)),
+            Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host)
+                if ip.is_none() && name.is_none() =>
+            {
+                name = Some(host.to_string())
+            }
+            Protocol::Tcp(p) | Protocol::Udp(p) if port.is_none() => port = Some(p),
+            _ => {}
+        }
+    }
+
+    match (ip, name, port) {
+        (Some(ip), _, Some(port)) => Ok(Some(SocketAddr::new(ip, port))),
+        (None, Some(name), Some(port)) => {
+            Ok(tokio::net::lookup_host((name.as_str(), port)).await?.next())
+        }
+        _ => Ok(None),
+    }
+}
+
+async fn quic_version_negotiation(addr: SocketAddr) -> std::io::Result<()> {
+    let local_addr: SocketAddr = if addr.is_ipv4() {
+        "0.0.0.0:0".parse().expect("valid address")
+    } else {
+        "[::]:0".parse().expect("valid address")
+    };
+    let socket = UdpSocket::bind(local_addr).await?;
+    socket.connect(addr).await?;
+    socket.send(&version_negotiation_probe()).await?;
+
+    let mut buf = [0_u8; 1500];
+    let len = socket.recv(&mut buf).await?;
+    if is_version_negotiation(&buf[..len]) {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Not a QUIC version negotiation packet",
+        ))
+    }
+}
+
+/// A QUIC long header packet with a reserved version padded to the minimum initial size.
+fn version_negotiation_probe() -> Vec<u8> {
+    const CONNECTION_ID: &[u8; 8] = b"quilprob";
+
+    let mut packet = Vec::with_capacity(MIN_QUIC_INITIAL_DATAGRAM_BYTES);
+    // Long header with the fixed bit set.
+    packet.push(0b1100_0000);
+    packet.extend_from_slice(&RESERVED_QUIC_VERSION);
+    for _ in 0..2 {
+        packet.push(CONNECTION_ID.len() as u8);
+        packet.extend_from_slice(CONNECTION_ID);
+    }
+    packet.resize(MIN_QUIC_INITIAL_DATAGRAM_BYTES, 0);
+    packet
+}
+
+/// A version negotiation packet is a long header packet with version zero.
+fn is_version_negotiation(packet: &[u8]) -> bool {
+    packet.len() >= 5 && packet[0] & 0b1000_0000 != 0 && packet[1..5] == [0, 0, 0, 0]
+}
+
+/// The share of reachable multiaddrs of a peer.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerReachability {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// The number of multiaddrs that could be probed.
+    pub probed: usize,
+    /// The number of reachable multiaddrs.
+    pub reachable: usize,
+    /// The lowest latency of the reachable multiaddrs.
+    pub min_latency: Option<Duration>,
+}
+
+impl PeerReachability {
+    /// Aggregate the probe results per peer in the order the peers first appear. Unsupported
+    /// multiaddrs are not counted.
+    pub fn from_results<'a>(results: impl IntoIterator<Item = &'a ProbeResult>) -> Vec<Self> {
+        let mut peers: Vec<PeerReachability> = Vec::new();
+        for result in results {
+            if result.outcome == ProbeOutcome::Unsupported {
+                continue;
+            }
+            let index = match peers.iter().position(|p| p.peer_id == result.peer_id) {
+                Some(index) => index,
+                None => {
+                    peers.push(PeerReachability {
+                        peer_id: result.peer_id,
+                        probed: 0,
+                        reachable: 0,
+                        min_latency: None,
+                    });
+                    peers.len() - 1
+                }
+            };
+            let peer = &mut peers[index];
+            peer.probed += 1;
+            if result.is_reachable() {
+                peer.reachable += 1;
+                peer.min_latency = match (peer.min_latency, result.latency) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+            }
+        }
+        peers
+    }
+
+    /// The fraction of the probed multiaddrs that are reachable.
+    pub fn score(&self) -> f64 {
+        if self.probed == 0 {
+            0.0
+        } else {
+            self.reachable as f64 / self.probed as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::test_utils::random_peer_id;
+
+    fn tcp_multiaddr(addr: SocketAddr) -> Multiaddr {
+        format!("/ip4/{}/tcp/{}", addr.ip(), addr.port())
+            .parse()
+            .unwrap()
+    }
+
+    fn quic_multiaddr(addr: SocketAddr) -> Multiaddr {
+        format!("/ip4/{}/udp/{}/quic", addr.ip(), addr.port())
+            .parse()
+            .unwrap()
+    }
+
+    /// A UDP socket that answers every datagram with a version negotiation packet.
+    async fn fake_quic_server() -> std::io::Result<SocketAddr> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await?;
+        let addr = socket.local_addr()?;
+        tokio::spawn(async move {
+            let mut buf = [0_u8; 1500];
+            while let Ok((_, peer)) = socket.recv_from(&mut buf).await {
+                let response = [0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+                let _ = socket.send_to(&response, peer).await;
+            }
+        });
+        Ok(addr)
+    }
+
+    #[tokio::test]
+    async fn probe_local_listeners() -> anyhow::Result<()> {
+        let peer_id = random_peer_id();
+        let timeout = Duration::from_millis(500);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let open_tcp = tcp_multiaddr(listener.local_addr()?);
+        let closed_tcp = {
+            let closed = TcpListener::bind("127.0.0.1:0").await?;
+            tcp_multiaddr(closed.local_addr()?)
+        };
+        let quic = quic_multiaddr(fake_quic_server().await?);
+        let silent = UdpSocket::bind("127.0.0.1:0").await?;
+        let silent_quic = quic_multiaddr(silent.local_addr()?);
+        let udp: Multiaddr = "/ip4/127.0.0.1/udp/1".parse()?;
+
+        let targets = [&open_tcp, &closed_tcp, &quic, &silent_quic, &udp]
+            .into_iter()
+            .map(|multiaddr| (peer_id, multiaddr.clone()));
+        let options = ProbeOptions::new().timeout(timeout).concurrency(2);
+        let results = probe(targets, &options).await;
+
+        assert_eq!(results[0].multiaddr, open_tcp);
+        assert_eq!(results[0].outcome, ProbeOutcome::Reachable);
+        assert!(results[0].latency.is_some());
+        assert!(matches!(results[1].outcome, ProbeOutcome::Unreachable(_)));
+        assert_eq!(results[2].outcome, ProbeOutcome::Reachable);
+        assert_eq!(results[2].transport, Transport::Quic);
+        assert_eq!(results[3].outcome, ProbeOutcome::Timeout);
+        assert_eq!(results[4].outcome, ProbeOutcome::Unsupported);
+
+        let reachability = PeerReachability::from_results(&results);
+        assert_eq!(reachability.len(), 1);
+        assert_eq!(reachability[0].probed, 4);
+        assert_eq!(reachability[0].reachable, 2);
+        assert_eq!(reachability[0].score(), 0.5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn version_negotiation_packets() {
+        let probe = version_negotiation_probe();
+        assert_eq!(probe.len(), MIN_QUIC_INITIAL_DATAGRAM_BYTES);
+        assert!(!is_version_negotiation(&probe));
+        assert!(is_version_negotiation(&[0xff, 0, 0, 0, 0]));
+        assert!(!is_version_negotiation(&[0x7f, 0, 0, 0, 0]));
+        assert!(!is_version_negotiation(&[0xff, 0, 0]));
+    }
+}
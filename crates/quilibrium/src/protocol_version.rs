@@ -0,0 +1,192 @@
+//! Quilibrium protocol versions.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// A Quilibrium protocol version triple. Serialized as a dot separated string, e.g. "1.4.2".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProtocolVersion {
+    /// The major version.
+    pub major: u8,
+    /// The minor version.
+    pub minor: u8,
+    /// The patch version.
+    pub patch: u8,
+}
+
+/// The minimum peer version accepted by a release of the ceremony client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compatibility {
+    /// The version of the release.
+    pub version: ProtocolVersion,
+    /// The minimum peer version accepted by the release.
+    pub minimum_accepted: ProtocolVersion,
+}
+
+/// The minimum peer versions accepted by the releases of the ceremony client, ordered by version.
+///
+/// A best-effort snapshot as of November 2023, taken from `GetVersion` and `GetMinimumVersion` in
+/// `node/consensus/consensus_engine.go` of the ceremony client sources packaged with quilibrium
+/// 0.2.1 (quilibrium-rs commit 879dadf35b92f7e6fb2c15c0a455cbcee24af40f). The minimum is enforced
+/// from 2023-11-03. Releases that aren't listed are treated like the closest earlier entry, so
+/// answers for later releases may be wrong.
+pub const COMPATIBILITY_TABLE: &[Compatibility] = &[Compatibility {
+    version: ProtocolVersion::new(1, 1, 6),
+    minimum_accepted: ProtocolVersion::new(1, 1, 5),
+}];
+
+impl ProtocolVersion {
+    /// Create a new protocol version.
+    pub const fn new(major: u8, minor: u8, patch: u8) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// The latest release in the [compatibility table](COMPATIBILITY_TABLE).
+    pub fn latest_known() -> Self {
+        COMPATIBILITY_TABLE
+            .last()
+            .expect("compatibility table is not empty")
+            .version
+    }
+
+    /// The minimum peer version accepted by a node running this version according to the
+    /// [compatibility table](COMPATIBILITY_TABLE). `None` if the version predates the table.
+    pub fn minimum_accepted(&self) -> Option<Self> {
+        COMPATIBILITY_TABLE
+            .iter()
+            .rev()
+            .find(|entry| entry.version <= *self)
+            .map(|entry| entry.minimum_accepted)
+    }
+
+    /// Whether a node running this version accepts a peer running the other version.
+    pub fn accepts(&self, peer: &Self) -> bool {
+        self.minimum_accepted()
+            .map_or(false, |minimum_accepted| *peer >= minimum_accepted)
+    }
+
+    /// Whether this version is not accepted by the latest known release anymore.
+    pub fn is_outdated(&self) -> bool {
+        !Self::latest_known().accepts(self)
+    }
+}
+
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = ProtocolVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('.').map(|part| part.parse::<u8>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => {
+                Ok(Self::new(major, minor, patch))
+            }
+            _ => Err(ProtocolVersionError::InvalidString(s.to_string())),
+        }
+    }
+}
+
+impl From<[u8; 3]> for ProtocolVersion {
+    fn from(value: [u8; 3]) -> Self {
+        Self::new(value[0], value[1], value[2])
+    }
+}
+
+impl From<ProtocolVersion> for [u8; 3] {
+    fn from(value: ProtocolVersion) -> Self {
+        [value.major, value.minor, value.patch]
+    }
+}
+
+impl serde::Serialize for ProtocolVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ProtocolVersion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Errors that occur when interacting with protocol versions.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProtocolVersionError {
+    /// The string is not a dot separated version triple.
+    #[error("Invalid protocol version: {0:?}")]
+    InvalidString(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn display_from_str_roundtrip() -> Result<()> {
+        let version: ProtocolVersion = "1.4.2".parse()?;
+        assert_eq!(version, ProtocolVersion::new(1, 4, 2));
+        assert_eq!(version.to_string(), "1.4.2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_str_invalid() {
+        for s in ["", "1.4", "1.4.2.0", "1.4.x", "1.256.0", "-1.0.0"] {
+            assert_eq!(
+                s.parse::<ProtocolVersion>(),
+                Err(ProtocolVersionError::InvalidString(s.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(ProtocolVersion::new(1, 1, 8) < ProtocolVersion::new(1, 2, 0));
+        assert!(ProtocolVersion::new(1, 10, 0) > ProtocolVersion::new(1, 9, 255));
+        assert!(ProtocolVersion::new(2, 0, 0) > ProtocolVersion::new(1, 255, 255));
+    }
+
+    #[test]
+    fn serde_as_string() -> Result<()> {
+        let version = ProtocolVersion::new(1, 4, 2);
+        let json = serde_json::to_string(&version)?;
+        assert_eq!(json, "\"1.4.2\"");
+        assert_eq!(serde_json::from_str::<ProtocolVersion>(&json)?, version);
+
+        Ok(())
+    }
+
+    #[test]
+    fn compatibility() {
+        let table_is_sorted = COMPATIBILITY_TABLE
+            .windows(2)
+            .all(|pair| pair[0].version < pair[1].version);
+        assert!(table_is_sorted);
+
+        let v1_1_6 = ProtocolVersion::new(1, 1, 6);
+        assert_eq!(
+            ProtocolVersion::new(1, 2, 0).minimum_accepted(),
+            Some(ProtocolVersion::new(1, 1, 5))
+        );
+        assert!(v1_1_6.accepts(&ProtocolVersion::new(1, 1, 5)));
+        assert!(!v1_1_6.accepts(&ProtocolVersion::new(1, 1, 4)));
+        assert_eq!(ProtocolVersion::new(1, 1, 5).minimum_accepted(), None);
+
+        assert!(ProtocolVersion::new(1, 1, 4).is_outdated());
+        assert!(!ProtocolVersion::latest_known().is_outdated());
+    }
+}
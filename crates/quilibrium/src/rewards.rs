@@ -0,0 +1,277 @@
+//! Track the tokens a node earns from periodic samples of its token info.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::node::TokenInfo;
+use crate::oblivious_transfer_units::ObliviousTransferUnits;
+use crate::stats::rate;
+use crate::units::{DAY, MONTH};
+
+/// The owned tokens and the confirmed token supply of a node at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RewardSample {
+    /// The time the token info was fetched.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub sampled_at: DateTime<Utc>,
+    /// The tokens owned by the node's address.
+    #[serde(with = "crate::oblivious_transfer_units::decimal_units")]
+    pub owned_tokens: ObliviousTransferUnits,
+    /// The token supply from confirmed frame data.
+    #[serde(with = "crate::oblivious_transfer_units::decimal_units")]
+    pub confirmed_token_supply: ObliviousTransferUnits,
+}
+
+impl RewardSample {
+    /// Create a sample from the token info fetched at `sampled_at`.
+    pub fn new(sampled_at: DateTime<Utc>, token_info: &TokenInfo) -> Self {
+        Self {
+            sampled_at,
+            owned_tokens: token_info.owned_tokens,
+            confirmed_token_supply: token_info.confirmed_token_supply,
+        }
+    }
+}
+
+/// Two consecutive samples that are further apart than expected, usually because the node or the
+/// sampler was offline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SampleGap {
+    /// The time of the sample before the gap.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub from: DateTime<Utc>,
+    /// The time of the sample after the gap.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub to: DateTime<Utc>,
+}
+
+impl SampleGap {
+    /// The time between the two samples.
+    pub fn duration(&self) -> Duration {
+        (self.to - self.from).to_std().unwrap_or_default()
+    }
+}
+
+/// The rewards earned within a time window.
+///
+/// Earnings are the sum of the increases of the owned tokens between consecutive samples, so
+/// transfers out of the node's address don't count against them. The same goes for the supply
+/// growth. Rates and projections are computed over the time between the first and the last sample
+/// in the window, including gaps.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RewardReport {
+    /// The time of the first sample in the window.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub first_sample_at: DateTime<Utc>,
+    /// The time of the last sample in the window.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub last_sample_at: DateTime<Utc>,
+    /// The number of samples in the window.
+    pub samples: usize,
+    /// The tokens earned in the window.
+    #[serde(with = "crate::oblivious_transfer_units::decimal_units")]
+    pub earned: ObliviousTransferUnits,
+    /// The growth of the confirmed token supply in the window.
+    #[serde(with = "crate::oblivious_transfer_units::decimal_units")]
+    pub supply_growth: ObliviousTransferUnits,
+    /// The gaps between consecutive samples in the window.
+    pub gaps: Vec<SampleGap>,
+}
+
+impl RewardReport {
+    /// Report the rewards from the samples taken between `from` and `to`, both inclusive.
+    /// Consecutive samples more than `max_gap` apart are reported as gaps.
+    ///
+    /// The samples must be in chronological order. Returns `None` if there are no samples in the
+    /// window. The earned tokens and the supply growth saturate at the maximum units.
+    pub fn new(
+        samples: &[RewardSample],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        max_gap: Duration,
+    ) -> Option<Self> {
+        let window: Vec<&RewardSample> = samples
+            .iter()
+            .filter(|sample| sample.sampled_at >= from && sample.sampled_at <= to)
+            .collect();
+        let (first, last) = (window.first()?, window.last()?);
+
+        let mut earned = ObliviousTransferUnits::ZERO;
+        let mut supply_growth = ObliviousTransferUnits::ZERO;
+        let mut gaps = Vec::new();
+        for pair in window.windows(2) {
+            let (earlier, later) = (pair[0], pair[1]);
+            if let Some(delta) = later.owned_tokens.checked_sub(earlier.owned_tokens) {
+                earned = earned.saturating_add(delta);
+            }
+            if let Some(delta) = later
+                .confirmed_token_supply
+                .checked_sub(earlier.confirmed_token_supply)
+            {
+                supply_growth = supply_growth.saturating_add(delta);
+            }
+            let gap = SampleGap {
+                from: earlier.sampled_at,
+                to: later.sampled_at,
+            };
+            if gap.duration() > max_gap {
+                gaps.push(gap);
+            }
+        }
+
+        Some(Self {
+            first_sample_at: first.sampled_at,
+            last_sample_at: last.sampled_at,
+            samples: window.len(),
+            earned,
+            supply_growth,
+            gaps,
+        })
+    }
+
+    /// The time between the first and the last sample.
+    pub fn duration(&self) -> Duration {
+        (self.last_sample_at - self.first_sample_at)
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// The total time of the gaps.
+    pub fn offline_duration(&self) -> Duration {
+        self.gaps.iter().map(SampleGap::duration).sum()
+    }
+
+    /// The tokens earned per hour. `None` if the window has less than two samples.
+    pub fn earned_per_hour(&self) -> Option<ObliviousTransferUnits> {
+        self.projected(Duration::from_secs(60 * 60))
+    }
+
+    /// The tokens the node would earn in `period` at the rate of the window. `None` if the window
+    /// has less than two samples.
+    pub fn projected(&self, period: Duration) -> Option<ObliviousTransferUnits> {
+        rate(self.earned, self.duration(), period)
+    }
+
+    /// The tokens the node would earn in a day at the rate of the window.
+    pub fn projected_daily(&self) -> Option<ObliviousTransferUnits> {
+        self.projected(DAY)
+    }
+
+    /// The tokens the node would earn in 30 days at the rate of the window.
+    pub fn projected_monthly(&self) -> Option<ObliviousTransferUnits> {
+        self.projected(MONTH)
+    }
+
+    /// The share of the supply growth earned by the node, from 0 to 1. `None` if the supply
+    /// didn't grow.
+    pub fn supply_share(&self) -> Option<f64> {
+        if self.supply_growth == ObliviousTransferUnits::ZERO {
+            return None;
+        }
+        Some(f64::from(self.earned.units()) / f64::from(self.supply_growth.units()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ruint::aliases::U256;
+
+    use super::*;
+    use crate::test_utils::sample_time;
+
+    fn sample(minutes: i64, owned: u64, supply: u64) -> RewardSample {
+        RewardSample {
+            sampled_at: sample_time(chrono::Duration::minutes(minutes)),
+            owned_tokens: U256::from(owned).into(),
+            confirmed_token_supply: U256::from(supply).into(),
+        }
+    }
+
+    #[test]
+    fn report() {
+        let samples = [
+            sample(0, 100, 10_000),
+            sample(10, 110, 10_100),
+            // A transfer out of the node's address.
+            sample(20, 50, 10_200),
+            sample(30, 70, 10_300),
+            // The node was offline for 30 minutes.
+            sample(60, 80, 10_400),
+        ];
+        let from = samples[0].sampled_at;
+        let to = samples[4].sampled_at;
+        let report = RewardReport::new(&samples, from, to, Duration::from_secs(15 * 60)).unwrap();
+
+        assert_eq!(report.samples, 5);
+        assert_eq!(report.earned, U256::from(40).into());
+        assert_eq!(report.supply_growth, U256::from(400).into());
+        assert_eq!(report.duration(), Duration::from_secs(60 * 60));
+        assert_eq!(
+            report.gaps,
+            vec![SampleGap {
+                from: samples[3].sampled_at,
+                to: samples[4].sampled_at
+            }]
+        );
+        assert_eq!(report.offline_duration(), Duration::from_secs(30 * 60));
+        assert_eq!(report.earned_per_hour(), Some(U256::from(40).into()));
+        assert_eq!(report.projected_daily(), Some(U256::from(960).into()));
+        assert_eq!(report.projected_monthly(), Some(U256::from(28_800).into()));
+        assert_eq!(report.supply_share(), Some(0.1));
+    }
+
+    #[test]
+    fn window() {
+        let samples = [
+            sample(0, 100, 10_000),
+            sample(10, 110, 10_100),
+            sample(20, 130, 10_200),
+        ];
+        let report = RewardReport::new(
+            &samples,
+            samples[1].sampled_at,
+            samples[2].sampled_at + chrono::Duration::hours(1),
+            Duration::from_secs(15 * 60),
+        )
+        .unwrap();
+        assert_eq!(report.samples, 2);
+        assert_eq!(report.earned, U256::from(20).into());
+        assert_eq!(report.earned_per_hour(), Some(U256::from(120).into()));
+
+        let single = RewardReport::new(
+            &samples[..1],
+            samples[0].sampled_at,
+            samples[0].sampled_at,
+            Duration::from_secs(15 * 60),
+        )
+        .unwrap();
+        assert_eq!(single.earned_per_hour(), None);
+        assert_eq!(single.supply_share(), None);
+
+        let to = samples[0].sampled_at - chrono::Duration::minutes(1);
+        assert!(RewardReport::new(&samples, to, to, Duration::from_secs(60)).is_none());
+    }
+
+    #[test]
+    fn earned_saturates() {
+        let max = |minutes| RewardSample {
+            owned_tokens: U256::MAX.into(),
+            ..sample(minutes, 0, 10_000)
+        };
+        let samples = [
+            sample(0, 0, 10_000),
+            max(10),
+            sample(20, 0, 10_000),
+            max(30),
+        ];
+        let report = RewardReport::new(
+            &samples,
+            DateTime::<Utc>::MIN_UTC,
+            DateTime::<Utc>::MAX_UTC,
+            Duration::MAX,
+        )
+        .unwrap();
+        assert_eq!(report.earned, U256::MAX.into());
+    }
+}
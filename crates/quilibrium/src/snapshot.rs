@@ -0,0 +1,238 @@
+//! Snapshots of the peers a node knows about and the changes between them.
+
+use chrono::{DateTime, Utc};
+
+use crate::node::{NetworkInfoResponse, PeerId, PeerInfoResponse, PeerManifestsResponse};
+use crate::peer_directory::{PeerDirectory, PeerRecord};
+
+/// The network info, peer info and peer manifests of a node at a point in time.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkSnapshot {
+    /// The time when the snapshot was captured.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub captured_at: DateTime<Utc>,
+    /// The peers from the node's peer store.
+    pub network_info: NetworkInfoResponse,
+    /// The broadcasted sync info.
+    pub peer_info: PeerInfoResponse,
+    /// The self-reported peer manifests.
+    pub peer_manifests: PeerManifestsResponse,
+}
+
+impl NetworkSnapshot {
+    /// Join the data about the peers in the snapshot by peer ID.
+    pub fn peer_directory(&self) -> PeerDirectory {
+        PeerDirectory::new()
+            .with_network_info(self.network_info.clone())
+            .with_peer_info(self.peer_info.clone())
+            .with_peer_manifests(self.peer_manifests.clone())
+    }
+
+    /// The peers that were added, removed or changed between this snapshot and a later one.
+    pub fn diff(&self, later: &NetworkSnapshot) -> SnapshotDiff {
+        let before = self.peer_directory();
+        let after = later.peer_directory();
+
+        let added = after
+            .records()
+            .filter(|record| before.get(&record.peer_id).is_none())
+            .map(|record| record.peer_id)
+            .collect();
+        let removed = before
+            .records()
+            .filter(|record| after.get(&record.peer_id).is_none())
+            .map(|record| record.peer_id)
+            .collect();
+        let changed = before
+            .records()
+            .filter_map(|before| {
+                let after = after.get(&before.peer_id)?;
+                let changes = field_changes(before, after);
+                (!changes.is_empty()).then_some(PeerChange {
+                    peer_id: before.peer_id,
+                    changes,
+                })
+            })
+            .collect();
+
+        SnapshotDiff {
+            from: self.captured_at,
+            to: later.captured_at,
+            added,
+            removed,
+            changed,
+        }
+    }
+}
+
+/// The changes between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotDiff {
+    /// The time when the earlier snapshot was captured.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub from: DateTime<Utc>,
+    /// The time when the later snapshot was captured.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub to: DateTime<Utc>,
+    /// The peers that only appear in the later snapshot.
+    pub added: Vec<PeerId>,
+    /// The peers that only appear in the earlier snapshot.
+    pub removed: Vec<PeerId>,
+    /// The peers that appear in both snapshots, but some of their fields changed.
+    pub changed: Vec<PeerChange>,
+}
+
+/// The fields of a peer that changed between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PeerChange {
+    /// The libp2p peer ID of the peer.
+    pub peer_id: PeerId,
+    /// The changed fields.
+    pub changes: Vec<FieldChange>,
+}
+
+/// A field of a peer that changed between two snapshots. The values are `None` if the field
+/// wasn't known in the snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FieldChange {
+    /// The name of the field.
+    pub field: String,
+    /// The value in the earlier snapshot.
+    pub before: Option<String>,
+    /// The value in the later snapshot.
+    pub after: Option<String>,
+}
+
+/// The fields of a peer record that are compared between snapshots. Signatures and timestamps are
+/// left out as they change with every broadcast.
+fn comparable_fields(record: &PeerRecord) -> Vec<(&'static str, Option<String>)> {
+    let mut multiaddrs: Vec<String> = record
+        .multiaddrs()
+        .iter()
+        .map(|multiaddr| multiaddr.to_string())
+        .collect();
+    multiaddrs.sort();
+    let manifest = record.manifest.as_ref();
+
+    vec![
+        (
+            "multiaddrs",
+            (!multiaddrs.is_empty()).then(|| multiaddrs.join(" ")),
+        ),
+        ("peer_score", record.peer_score().map(|v| v.to_string())),
+        ("cooperative", record.cooperative.map(|v| v.to_string())),
+        ("version", record.version().map(|v| v.to_string())),
+        ("max_frame", record.max_frame().map(|v| v.to_string())),
+        ("cores", manifest.map(|m| m.cores.to_string())),
+        ("memory", manifest.map(|m| m.memory.to_string())),
+        ("storage", manifest.map(|m| m.storage.to_string())),
+        (
+            "master_head_frame",
+            manifest.map(|m| m.master_head_frame.to_string()),
+        ),
+    ]
+}
+
+fn field_changes(before: &PeerRecord, after: &PeerRecord) -> Vec<FieldChange> {
+    comparable_fields(before)
+        .into_iter()
+        .zip(comparable_fields(after))
+        .filter(|((_, before), (_, after))| before != after)
+        .map(|((field, before), (_, after))| FieldChange {
+            field: field.to_string(),
+            before,
+            after,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::node::{NetworkInfo, PeerInfo};
+    use crate::protocol_version::ProtocolVersion;
+    use crate::test_utils::{peer_info, random_peer_id};
+
+    fn snapshot(captured_at_secs: i64, peers: Vec<PeerInfo>) -> NetworkSnapshot {
+        NetworkSnapshot {
+            captured_at: Utc.timestamp_opt(captured_at_secs, 0).unwrap(),
+            network_info: NetworkInfoResponse {
+                network_info: vec![],
+            },
+            peer_info: PeerInfoResponse {
+                peers,
+                uncooperative_peers: vec![],
+            },
+            peer_manifests: PeerManifestsResponse {
+                peer_manifests: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn added_removed_and_changed() {
+        let (old, new) = (ProtocolVersion::new(1, 1, 8), ProtocolVersion::new(1, 2, 0));
+        let ids: Vec<PeerId> = (0..4).map(|_| random_peer_id()).collect();
+
+        let before = snapshot(
+            0,
+            vec![
+                peer_info(ids[0], old),
+                peer_info(ids[1], old),
+                peer_info(ids[2], old),
+            ],
+        );
+        let mut after = snapshot(
+            60,
+            vec![
+                peer_info(ids[0], new),
+                peer_info(ids[1], old),
+                peer_info(ids[3], new),
+            ],
+        );
+        after.network_info.network_info.push(NetworkInfo {
+            peer_id: ids[1],
+            multiaddrs: vec!["/ip4/1.2.3.4/udp/8336/quic".parse().unwrap()],
+            peer_score: 0.0,
+        });
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, vec![ids[3]]);
+        assert_eq!(diff.removed, vec![ids[2]]);
+
+        let mut changed = diff.changed;
+        changed.sort_by_key(|change| change.peer_id != ids[0]);
+        assert_eq!(changed.len(), 2);
+        assert_eq!(
+            changed[0].changes,
+            vec![FieldChange {
+                field: "version".to_string(),
+                before: Some("1.1.8".to_string()),
+                after: Some("1.2.0".to_string()),
+            }]
+        );
+        let fields: Vec<&str> = changed[1]
+            .changes
+            .iter()
+            .map(|change| change.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["multiaddrs", "peer_score"]);
+    }
+
+    #[test]
+    fn serde_roundtrip() -> anyhow::Result<()> {
+        let snapshot = snapshot(
+            1_700_000_000,
+            vec![peer_info(random_peer_id(), ProtocolVersion::new(1, 2, 0))],
+        );
+        let json = serde_json::to_string(&snapshot)?;
+        let mut parsed: NetworkSnapshot = serde_json::from_str(&json)?;
+        // Timestamps are serialized with millisecond precision.
+        parsed.peer_info.peers[0].timestamp = snapshot.peer_info.peers[0].timestamp;
+        assert_eq!(parsed, snapshot);
+
+        Ok(())
+    }
+}
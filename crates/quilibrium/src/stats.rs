@@ -0,0 +1,50 @@
+//! Descriptive statistics shared by the analytics modules.
+
+use std::time::Duration;
+
+use ruint::aliases::U256;
+
+use crate::oblivious_transfer_units::ObliviousTransferUnits;
+
+/// The `p`th percentile (0–100) of sorted values with linear interpolation between the closest
+/// ranks. Returns `None` if there are no values.
+pub(crate) fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p.clamp(0.0, 100.0) / 100.0 * last as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f64;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * fraction)
+}
+
+/// The amount per `period` of an amount accumulated over `elapsed`, rounded down. Returns `None`
+/// if `elapsed` is zero.
+pub(crate) fn rate(
+    amount: ObliviousTransferUnits,
+    elapsed: Duration,
+    period: Duration,
+) -> Option<ObliviousTransferUnits> {
+    let elapsed = elapsed.as_millis();
+    if elapsed == 0 {
+        return None;
+    }
+    let units = amount.units().checked_mul(U256::from(period.as_millis()))? / U256::from(elapsed);
+    Some(units.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_interpolate() {
+        assert_eq!(percentile(&[], 50.0), None);
+        assert_eq!(percentile(&[3.0], 90.0), Some(3.0));
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(percentile(&values, 0.0), Some(1.0));
+        assert_eq!(percentile(&values, 50.0), Some(3.0));
+        assert_eq!(percentile(&values, 100.0), Some(5.0));
+        assert!((percentile(&values, 90.0).unwrap() - 4.6).abs() < 1e-9);
+        assert_eq!(percentile(&[1.0, 2.0], 50.0), Some(1.5));
+    }
+}
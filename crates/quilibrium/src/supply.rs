@@ -0,0 +1,237 @@
+//! Track the growth of the token supply from periodic samples of the token info.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ruint::aliases::U256;
+
+use crate::node::{ClockFrame, TokenInfo};
+use crate::oblivious_transfer_units::ObliviousTransferUnits;
+use crate::stats::rate;
+use crate::units::{DAY, YEAR};
+
+/// The token supply at a point in time, aligned to the head frame of the node.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SupplySample {
+    /// The time the token info was fetched.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub sampled_at: DateTime<Utc>,
+    /// The number of the ceremony application head frame of the node found right before the token
+    /// info was fetched, if the node has any frames.
+    pub frame_number: Option<u64>,
+    /// The token supply from confirmed frame data.
+    #[serde(with = "crate::oblivious_transfer_units::decimal_units")]
+    pub confirmed_token_supply: ObliviousTransferUnits,
+    /// The token supply, including unconfirmed frame data.
+    #[serde(with = "crate::oblivious_transfer_units::decimal_units")]
+    pub unconfirmed_token_supply: ObliviousTransferUnits,
+}
+
+impl SupplySample {
+    /// Create a sample from the token info fetched at `sampled_at` and the ceremony application
+    /// head frame found right before.
+    pub fn new(
+        sampled_at: DateTime<Utc>,
+        token_info: &TokenInfo,
+        head_frame: Option<&ClockFrame>,
+    ) -> Self {
+        Self {
+            sampled_at,
+            frame_number: head_frame.map(|frame| frame.frame_number),
+            confirmed_token_supply: token_info.confirmed_token_supply,
+            unconfirmed_token_supply: token_info.unconfirmed_token_supply,
+        }
+    }
+
+    /// The supply from unconfirmed frame data that isn't confirmed yet.
+    pub fn pending(&self) -> ObliviousTransferUnits {
+        self.unconfirmed_token_supply
+            .checked_sub(self.confirmed_token_supply)
+            .unwrap_or_default()
+    }
+}
+
+/// The growth of the confirmed token supply between two samples.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SupplyGrowth {
+    /// The earlier sample.
+    pub from: SupplySample,
+    /// The later sample.
+    pub to: SupplySample,
+}
+
+impl SupplyGrowth {
+    /// The time between the samples.
+    pub fn duration(&self) -> Duration {
+        (self.to.sampled_at - self.from.sampled_at)
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// The number of frames between the samples. `None` if a sample has no frame number.
+    pub fn frames(&self) -> Option<u64> {
+        self.to.frame_number?.checked_sub(self.from.frame_number?)
+    }
+
+    /// The tokens issued between the samples. Zero if the confirmed supply shrank.
+    pub fn issued(&self) -> ObliviousTransferUnits {
+        self.to
+            .confirmed_token_supply
+            .checked_sub(self.from.confirmed_token_supply)
+            .unwrap_or_default()
+    }
+
+    /// The tokens issued per frame, rounded down. `None` if the frame numbers are unknown or
+    /// didn't advance.
+    pub fn issued_per_frame(&self) -> Option<ObliviousTransferUnits> {
+        let frames = self.frames().filter(|frames| *frames > 0)?;
+        Some((self.issued().units() / U256::from(frames)).into())
+    }
+
+    /// The tokens issued per day, rounded down. `None` if the samples were taken at the same time.
+    pub fn issued_per_day(&self) -> Option<ObliviousTransferUnits> {
+        rate(self.issued(), self.duration(), DAY)
+    }
+
+    /// The yearly growth of the confirmed supply at the issuance rate between the samples,
+    /// relative to the supply of the earlier sample, e.g. 0.05 for 5%. `None` if the earlier
+    /// supply is zero or the samples were taken at the same time.
+    pub fn annualized_inflation(&self) -> Option<f64> {
+        let supply = self.from.confirmed_token_supply;
+        if supply == ObliviousTransferUnits::ZERO {
+            return None;
+        }
+        let issued = rate(self.issued(), self.duration(), YEAR)?;
+        Some(f64::from(issued.units()) / f64::from(supply.units()))
+    }
+}
+
+/// Keeps supply samples in chronological order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SupplyTracker {
+    samples: Vec<SupplySample>,
+}
+
+impl SupplyTracker {
+    /// Create a tracker without samples.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sample. Samples can be added in any order.
+    pub fn add(&mut self, sample: SupplySample) {
+        let index = self
+            .samples
+            .partition_point(|existing| existing.sampled_at <= sample.sampled_at);
+        self.samples.insert(index, sample);
+    }
+
+    /// The samples in chronological order.
+    pub fn samples(&self) -> &[SupplySample] {
+        &self.samples
+    }
+
+    /// The latest sample.
+    pub fn latest(&self) -> Option<&SupplySample> {
+        self.samples.last()
+    }
+
+    /// The growth between each pair of consecutive samples.
+    pub fn intervals(&self) -> Vec<SupplyGrowth> {
+        self.samples
+            .windows(2)
+            .map(|pair| SupplyGrowth {
+                from: pair[0].clone(),
+                to: pair[1].clone(),
+            })
+            .collect()
+    }
+
+    /// The growth between the first and the latest sample. `None` if there are less than two
+    /// samples.
+    pub fn total(&self) -> Option<SupplyGrowth> {
+        match self.samples.as_slice() {
+            [first, .., last] => Some(SupplyGrowth {
+                from: first.clone(),
+                to: last.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl FromIterator<SupplySample> for SupplyTracker {
+    fn from_iter<T: IntoIterator<Item = SupplySample>>(iter: T) -> Self {
+        let mut tracker = Self::new();
+        for sample in iter {
+            tracker.add(sample);
+        }
+        tracker
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::sample_time;
+
+    fn sample(
+        hours: i64,
+        frame_number: Option<u64>,
+        confirmed: u64,
+        unconfirmed: u64,
+    ) -> SupplySample {
+        SupplySample {
+            sampled_at: sample_time(chrono::Duration::hours(hours)),
+            frame_number,
+            confirmed_token_supply: U256::from(confirmed).into(),
+            unconfirmed_token_supply: U256::from(unconfirmed).into(),
+        }
+    }
+
+    #[test]
+    fn growth() {
+        let tracker: SupplyTracker = [
+            sample(12, Some(1_300), 1_100, 1_150),
+            sample(0, Some(1_000), 1_000, 1_020),
+            sample(24, None, 1_200, 1_200),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(tracker.samples()[0].frame_number, Some(1_000));
+        assert_eq!(tracker.latest().unwrap().pending(), U256::ZERO.into());
+        assert_eq!(tracker.samples()[1].pending(), U256::from(50).into());
+
+        let intervals = tracker.intervals();
+        assert_eq!(intervals.len(), 2);
+        assert_eq!(intervals[0].frames(), Some(300));
+        assert_eq!(intervals[0].issued(), U256::from(100).into());
+        assert_eq!(intervals[0].issued_per_frame(), Some(U256::ZERO.into()));
+        assert_eq!(intervals[0].issued_per_day(), Some(U256::from(200).into()));
+        assert_eq!(intervals[1].frames(), None);
+        assert_eq!(intervals[1].issued_per_frame(), None);
+
+        let total = tracker.total().unwrap();
+        assert_eq!(total.duration(), DAY);
+        assert_eq!(total.issued(), U256::from(200).into());
+        assert_eq!(total.issued_per_day(), Some(U256::from(200).into()));
+        assert_eq!(total.annualized_inflation(), Some(73.0));
+    }
+
+    #[test]
+    fn issued_per_frame() {
+        let growth = SupplyGrowth {
+            from: sample(0, Some(10), 1_000, 1_000),
+            to: sample(1, Some(20), 1_500, 1_500),
+        };
+        assert_eq!(growth.issued_per_frame(), Some(U256::from(50).into()));
+
+        let stalled = SupplyGrowth {
+            from: sample(0, Some(10), 1_000, 1_000),
+            to: sample(0, Some(10), 1_000, 1_000),
+        };
+        assert_eq!(stalled.issued_per_frame(), None);
+        assert_eq!(stalled.issued_per_day(), None);
+        assert_eq!(SupplyTracker::new().total(), None);
+    }
+}
@@ -0,0 +1,222 @@
+//! Compare the frames a node has with the frames its peers report to have.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::node::{ClockFrame, FrameFilter, PeerInfoResponse, PeerManifestsResponse};
+
+/// Frame numbers reported more than this many frames above the median are ignored as the network
+/// max, so a single peer can't claim an arbitrary network head.
+pub const MAX_FRAMES_ABOVE_MEDIAN: u64 = 1_000;
+
+/// Sync status of a node compared to the max frames reported by its peers.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SyncStatus {
+    /// The time when the status was observed.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub observed_at: DateTime<Utc>,
+    /// The sync status for each frame filter.
+    pub filters: Vec<FilterSyncStatus>,
+}
+
+impl SyncStatus {
+    /// Compute the sync status from the head frames of the node and the peer info and peer
+    /// manifests the node knows about.
+    ///
+    /// The network frame numbers for the ceremony application filter are taken from the max frames
+    /// in the sync info of cooperative peers, while the network frame numbers for the master clock
+    /// filter are taken from the master head frames in the peer manifests.
+    pub fn new(
+        observed_at: DateTime<Utc>,
+        ceremony_head: Option<&ClockFrame>,
+        master_head: Option<&ClockFrame>,
+        peer_info: &PeerInfoResponse,
+        peer_manifests: &PeerManifestsResponse,
+    ) -> Self {
+        let ceremony = FilterSyncStatus::new(
+            FrameFilter::CeremonyApplication,
+            ceremony_head.map(|frame| frame.frame_number),
+            peer_info.peers.iter().map(|peer| peer.max_frame).collect(),
+        );
+        let master = FilterSyncStatus::new(
+            FrameFilter::MasterClock,
+            master_head.map(|frame| frame.frame_number),
+            peer_manifests
+                .peer_manifests
+                .iter()
+                .map(|manifest| manifest.master_head_frame)
+                .collect(),
+        );
+        Self {
+            observed_at,
+            filters: vec![ceremony, master],
+        }
+    }
+
+    /// Get the sync status for a frame filter.
+    pub fn filter(&self, filter: &FrameFilter) -> Option<&FilterSyncStatus> {
+        self.filters.iter().find(|status| &status.filter == filter)
+    }
+
+    /// Estimate how long it takes the node to catch up with the network for a frame filter based
+    /// on the progress made since an earlier status.
+    ///
+    /// Returns `Some(Duration::ZERO)` if the node is in sync and `None` if the node isn't closing
+    /// the gap or the estimate can't be made.
+    pub fn estimate_catch_up(
+        &self,
+        earlier: &SyncStatus,
+        filter: &FrameFilter,
+    ) -> Option<Duration> {
+        let current = self.filter(filter)?;
+        let lag = current.lag()?;
+        if lag == 0 {
+            return Some(Duration::ZERO);
+        }
+        let previous_lag = earlier.filter(filter)?.lag()?;
+        let elapsed = (self.observed_at - earlier.observed_at).to_std().ok()?;
+        if previous_lag <= lag || elapsed.is_zero() {
+            return None;
+        }
+
+        let frames_per_sec = (previous_lag - lag) as f64 / elapsed.as_secs_f64();
+        Duration::try_from_secs_f64(lag as f64 / frames_per_sec).ok()
+    }
+}
+
+/// Sync status of a node for a single frame filter.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FilterSyncStatus {
+    /// The frame filter.
+    pub filter: FrameFilter,
+    /// The highest frame number the node has. `None` if the node has no frames for the filter.
+    pub local_head: Option<u64>,
+    /// The highest frame number reported by a peer, ignoring frame numbers more than
+    /// [`MAX_FRAMES_ABOVE_MEDIAN`] above the median.
+    pub network_max: Option<u64>,
+    /// The median of the frame numbers reported by peers.
+    pub network_median: Option<u64>,
+    /// The number of peers that reported a frame number.
+    pub reporting_peers: usize,
+}
+
+impl FilterSyncStatus {
+    fn new(filter: FrameFilter, local_head: Option<u64>, mut reported: Vec<u64>) -> Self {
+        reported.sort_unstable();
+        let network_median = median(&reported);
+        let network_max = network_median.and_then(|median| {
+            let limit = median.saturating_add(MAX_FRAMES_ABOVE_MEDIAN);
+            reported
+                .iter()
+                .rev()
+                .find(|&&frame| frame <= limit)
+                .copied()
+        });
+        Self {
+            filter,
+            local_head,
+            network_max,
+            network_median,
+            reporting_peers: reported.len(),
+        }
+    }
+
+    /// The number of frames the node is behind the highest frame reported by a peer.
+    /// `None` if no peer reported a frame number.
+    pub fn lag(&self) -> Option<u64> {
+        let network_max = self.network_max?;
+        Some(network_max.saturating_sub(self.local_head.unwrap_or_default()))
+    }
+}
+
+/// Median of sorted values, the mean of the two middle values is floored.
+pub(crate) fn median(sorted: &[u64]) -> Option<u64> {
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(sorted[mid]),
+        _ => {
+            let (lower, upper) = (sorted[mid - 1], sorted[mid]);
+            Some(lower + (upper - lower) / 2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn status(observed_at_secs: i64, local_head: u64, network_max: u64) -> SyncStatus {
+        SyncStatus {
+            observed_at: Utc.timestamp_opt(observed_at_secs, 0).unwrap(),
+            filters: vec![FilterSyncStatus::new(
+                FrameFilter::CeremonyApplication,
+                Some(local_head),
+                vec![network_max],
+            )],
+        }
+    }
+
+    #[test]
+    fn median_odd_and_even() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[3]), Some(3));
+        assert_eq!(median(&[1, 3, 7]), Some(3));
+        assert_eq!(median(&[1, 4, 7, 10]), Some(5));
+        assert_eq!(median(&[u64::MAX - 1, u64::MAX]), Some(u64::MAX - 1));
+    }
+
+    #[test]
+    fn lag_saturates() {
+        let ahead = FilterSyncStatus::new(FrameFilter::MasterClock, Some(10), vec![5, 8]);
+        assert_eq!(ahead.lag(), Some(0));
+
+        let no_frames = FilterSyncStatus::new(FrameFilter::MasterClock, None, vec![5, 8]);
+        assert_eq!(no_frames.lag(), Some(8));
+
+        let no_peers = FilterSyncStatus::new(FrameFilter::MasterClock, Some(10), vec![]);
+        assert_eq!(no_peers.lag(), None);
+    }
+
+    #[test]
+    fn network_max_ignores_outliers() {
+        let reported = vec![100, 101, 102, 102 + MAX_FRAMES_ABOVE_MEDIAN, u64::MAX];
+        let status = FilterSyncStatus::new(FrameFilter::MasterClock, Some(100), reported);
+        assert_eq!(status.network_median, Some(102));
+        assert_eq!(status.network_max, Some(102 + MAX_FRAMES_ABOVE_MEDIAN));
+        assert_eq!(status.lag(), Some(2 + MAX_FRAMES_ABOVE_MEDIAN));
+    }
+
+    #[test]
+    fn estimate_catch_up_from_progress() {
+        let filter = FrameFilter::CeremonyApplication;
+        let earlier = status(0, 100, 200);
+        // Closed the gap by 10 frames in 10 seconds with 90 frames to go.
+        let later = status(10, 120, 210);
+        assert_eq!(
+            later.estimate_catch_up(&earlier, &filter),
+            Some(Duration::from_secs(90))
+        );
+
+        // Falling behind.
+        let behind = status(10, 105, 220);
+        assert_eq!(behind.estimate_catch_up(&earlier, &filter), None);
+
+        // Too slow to represent.
+        let crawling = status(u32::MAX.into(), 100, u64::MAX);
+        assert_eq!(
+            crawling.estimate_catch_up(&status(0, 0, u64::MAX), &filter),
+            None
+        );
+
+        // In sync.
+        let synced = status(10, 220, 220);
+        assert_eq!(
+            synced.estimate_catch_up(&earlier, &filter),
+            Some(Duration::ZERO)
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Helpers to construct node types and a fake node in tests. Enabled by the `test-utils`
+//! feature.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ruint::aliases::U256;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Server, Uri};
+use tonic::{Request, Response, Status};
+
+use crate::node::{FrameFilter, NetworkInfo, NetworkInfoResponse, PeerId, PeerInfo, PeerManifest};
+use crate::protocol_version::ProtocolVersion;
+use crate::quilibrium_pb::node::clock::pb as clock_pb;
+use crate::quilibrium_pb::node::node::pb::{
+    self as node_pb,
+    node_service_server::{NodeService, NodeServiceServer},
+};
+
+/// A random Ed25519 peer ID.
+pub fn random_peer_id() -> PeerId {
+    libp2p_identity::Keypair::generate_ed25519()
+        .public()
+        .to_peer_id()
+}
+
+/// Peer info of a peer without multiaddrs timestamped now.
+pub fn peer_info(peer_id: PeerId, version: ProtocolVersion) -> PeerInfo {
+    PeerInfo {
+        peer_id,
+        multiaddrs: vec![],
+        max_frame: 0,
+        timestamp: Utc::now(),
+        version,
+        signature: vec![],
+        public_key: vec![],
+    }
+}
+
+/// A fixed time plus `elapsed`, for the timestamps of token info samples.
+pub fn sample_time(elapsed: chrono::Duration) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2023-11-01T00:00:00Z")
+        .expect("valid timestamp")
+        .with_timezone(&Utc)
+        + elapsed
+}
+
+/// Network info of peers without multiaddrs with the given scores.
+pub fn network_info(scores: &[(PeerId, f64)]) -> NetworkInfoResponse {
+    NetworkInfoResponse {
+        network_info: scores
+            .iter()
+            .map(|&(peer_id, peer_score)| NetworkInfo {
+                peer_id,
+                multiaddrs: vec![],
+                peer_score,
+            })
+            .collect(),
+    }
+}
+
+/// A peer manifest of a peer with all metrics zero.
+pub fn peer_manifest(peer_id: PeerId) -> PeerManifest {
+    PeerManifest {
+        peer_id,
+        difficulty: 0,
+        difficulty_metric: 0,
+        commit_16_metric: 0,
+        commit_128_metric: 0,
+        commit_1024_metric: 0,
+        commit_65536_metric: 0,
+        proof_16_metric: 0,
+        proof_128_metric: 0,
+        proof_1024_metric: 0,
+        proof_65536_metric: 0,
+        cores: 0,
+        memory: 0,
+        storage: 0,
+        master_head_frame: 0,
+    }
+}
+
+/// A node that serves fixed responses over gRPC on a local port.
+#[derive(Debug, Clone, Default)]
+pub struct FakeNode {
+    peer_info: node_pb::PeerInfoResponse,
+    network_info: node_pb::NetworkInfoResponse,
+    peer_manifests: node_pb::PeerManifestsResponse,
+    token_info: node_pb::TokenInfoResponse,
+    ceremony_head: Option<u64>,
+    master_head: Option<u64>,
+    first_frame: u64,
+    delay: Duration,
+    failing: bool,
+    calls: Arc<AtomicUsize>,
+}
+
+impl FakeNode {
+    /// Create a node without peers, frames or tokens.
+    pub fn new() -> Self {
+        Self {
+            token_info: node_pb::TokenInfoResponse {
+                confirmed_token_supply: vec![0; 32],
+                unconfirmed_token_supply: vec![0; 32],
+                owned_tokens: vec![0; 32],
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Add a cooperative or uncooperative peer to the peer info.
+    pub fn with_peer_info(
+        mut self,
+        peer_id: PeerId,
+        cooperative: bool,
+        version: ProtocolVersion,
+        max_frame: u64,
+    ) -> Self {
+        let peer = node_pb::PeerInfo {
+            peer_id: peer_id.to_bytes(),
+            multiaddrs: vec![],
+            max_frame,
+            timestamp: Utc::now().timestamp_millis(),
+            version: <[u8; 3]>::from(version).to_vec(),
+            signature: vec![],
+            public_key: vec![],
+        };
+        if cooperative {
+            self.peer_info.peer_info.push(peer);
+        } else {
+            self.peer_info.uncooperative_peer_info.push(peer);
+        }
+        self
+    }
+
+    /// Add a peer with a score to the network info.
+    pub fn with_network_info(mut self, peer_id: PeerId, peer_score: f64) -> Self {
+        self.network_info.network_info.push(node_pb::NetworkInfo {
+            peer_id: peer_id.to_bytes(),
+            multiaddrs: vec![],
+            peer_score,
+        });
+        self
+    }
+
+    /// Add a peer manifest with a master head frame.
+    pub fn with_peer_manifest(mut self, peer_id: PeerId, master_head_frame: u64) -> Self {
+        self.peer_manifests
+            .peer_manifests
+            .push(node_pb::PeerManifest {
+                peer_id: peer_id.to_bytes(),
+                master_head_frame,
+                ..Default::default()
+            });
+        self
+    }
+
+    /// Set the token supply and balance in units.
+    pub fn with_token_info(mut self, confirmed: u64, unconfirmed: u64, owned: u64) -> Self {
+        let bytes = |units: u64| U256::from(units).to_be_bytes::<32>().to_vec();
+        self.token_info = node_pb::TokenInfoResponse {
+            confirmed_token_supply: bytes(confirmed),
+            unconfirmed_token_supply: bytes(unconfirmed),
+            owned_tokens: bytes(owned),
+        };
+        self
+    }
+
+    /// Set the head frame numbers. The node has every frame up to the heads that isn't culled.
+    pub fn with_head_frames(mut self, ceremony: Option<u64>, master: Option<u64>) -> Self {
+        self.ceremony_head = ceremony;
+        self.master_head = master;
+        self
+    }
+
+    /// Cull the frames below a frame number for every filter.
+    pub fn with_first_frame(mut self, first_frame: u64) -> Self {
+        self.first_frame = first_frame;
+        self
+    }
+
+    /// Delay every response.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Fail every call with an unavailable status.
+    pub fn failing(mut self) -> Self {
+        self.failing = true;
+        self
+    }
+
+    /// The number of calls the node received, shared by its clones.
+    pub fn calls(&self) -> Arc<AtomicUsize> {
+        self.calls.clone()
+    }
+
+    fn head(&self, filter: &FrameFilter) -> Option<u64> {
+        match filter {
+            FrameFilter::CeremonyApplication => self.ceremony_head,
+            FrameFilter::MasterClock => self.master_head,
+            FrameFilter::Unknown(_) => None,
+        }
+    }
+
+    /// Count the call, wait for the delay and fail if the node is failing.
+    async fn respond(&self) -> Result<(), Status> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(self.delay).await;
+        if self.failing {
+            return Err(Status::unavailable("fake node is failing"));
+        }
+        Ok(())
+    }
+
+    /// Serve the node on a random local port and return its URI.
+    pub async fn serve(self) -> Uri {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind to a local port");
+        let uri = format!("http://{}", listener.local_addr().expect("local address"))
+            .parse()
+            .expect("valid URI");
+        let incoming = TcpIncoming::from_listener(listener, true, None).expect("listener");
+        tokio::spawn(
+            Server::builder()
+                .add_service(NodeServiceServer::new(self))
+                .serve_with_incoming(incoming),
+        );
+        uri
+    }
+}
+
+#[tonic::async_trait]
+impl NodeService for FakeNode {
+    async fn get_peer_manifests(
+        &self,
+        _request: Request<node_pb::GetPeerManifestsRequest>,
+    ) -> Result<Response<node_pb::PeerManifestsResponse>, Status> {
+        self.respond().await?;
+        Ok(Response::new(self.peer_manifests.clone()))
+    }
+
+    async fn get_frames(
+        &self,
+        request: Request<node_pb::GetFramesRequest>,
+    ) -> Result<Response<node_pb::FramesResponse>, Status> {
+        self.respond().await?;
+        let request = request.into_inner();
+        let filter = FrameFilter::try_from(request.filter.clone())
+            .map_err(|error| Status::invalid_argument(error.to_string()))?;
+        let frames = match self.head(&filter) {
+            Some(head) => {
+                request.from_frame_number.max(self.first_frame)
+                    ..request.to_frame_number.min(head + 1)
+            }
+            None => 0..0,
+        };
+        Ok(Response::new(node_pb::FramesResponse {
+            truncated_clock_frames: frames
+                .map(|frame_number| clock_pb::ClockFrame {
+                    filter: request.filter.clone(),
+                    frame_number,
+                    timestamp: Utc::now().timestamp_millis(),
+                    ..Default::default()
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_frame_info(
+        &self,
+        request: Request<node_pb::GetFrameInfoRequest>,
+    ) -> Result<Response<node_pb::FrameInfoResponse>, Status> {
+        self.respond().await?;
+        let request = request.into_inner();
+        let filter = FrameFilter::try_from(request.filter.clone())
+            .map_err(|error| Status::invalid_argument(error.to_string()))?;
+        let exists = self
+            .head(&filter)
+            .is_some_and(|head| (self.first_frame..=head).contains(&request.frame_number));
+        Ok(Response::new(node_pb::FrameInfoResponse {
+            clock_frame: exists.then(|| clock_pb::ClockFrame {
+                filter: request.filter,
+                frame_number: request.frame_number,
+                timestamp: Utc::now().timestamp_millis(),
+                ..Default::default()
+            }),
+        }))
+    }
+
+    async fn get_peer_info(
+        &self,
+        _request: Request<node_pb::GetPeerInfoRequest>,
+    ) -> Result<Response<node_pb::PeerInfoResponse>, Status> {
+        self.respond().await?;
+        Ok(Response::new(self.peer_info.clone()))
+    }
+
+    async fn get_network_info(
+        &self,
+        _request: Request<node_pb::GetNetworkInfoRequest>,
+    ) -> Result<Response<node_pb::NetworkInfoResponse>, Status> {
+        self.respond().await?;
+        Ok(Response::new(self.network_info.clone()))
+    }
+
+    async fn get_token_info(
+        &self,
+        _request: Request<node_pb::GetTokenInfoRequest>,
+    ) -> Result<Response<node_pb::TokenInfoResponse>, Status> {
+        self.respond().await?;
+        Ok(Response::new(self.token_info.clone()))
+    }
+}
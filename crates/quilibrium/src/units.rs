@@ -0,0 +1,115 @@
+//! Units for the quantities reported by peers.
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+const BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// The length of a day used for rates and projections.
+pub const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+/// The length of a month used for projections.
+pub const MONTH: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// The length of a year used to annualize rates.
+pub const YEAR: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// A number of bytes. Displayed with binary units, e.g. "1.2 TiB". The precision defaults to one
+/// decimal and can be set with the formatter, e.g. `format!("{:.3}", size)`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    /// The number of bytes.
+    pub const fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl Display for ByteSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        let precision = f.precision().unwrap_or(1);
+        write!(f, "{value:.precision$} {}", BYTE_UNITS[unit])
+    }
+}
+
+/// A duration displayed in milliseconds below a second, in seconds below a minute and in minutes
+/// otherwise, e.g. "340 ms", "1.5 s" or "2.0 min".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanDuration(pub Duration);
+
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.0 < Duration::from_secs(1) {
+            write!(f, "{} ms", self.0.as_millis())
+        } else if self.0 < Duration::from_secs(60) {
+            write!(f, "{:.1} s", self.0.as_secs_f64())
+        } else {
+            write!(f, "{:.1} min", self.0.as_secs_f64() / 60.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_byte_size() {
+        assert_eq!(ByteSize(0).to_string(), "0 B");
+        assert_eq!(ByteSize(1023).to_string(), "1023 B");
+        assert_eq!(ByteSize(1024).to_string(), "1.0 KiB");
+        assert_eq!(ByteSize(1536 << 20).to_string(), "1.5 GiB");
+        assert_eq!(ByteSize(1_319_413_953_331).to_string(), "1.2 TiB");
+        assert_eq!(format!("{:.2}", ByteSize(1_319_413_953_331)), "1.20 TiB");
+        assert_eq!(ByteSize(u64::MAX).to_string(), "16.0 EiB");
+    }
+
+    #[test]
+    fn display_human_duration() {
+        assert_eq!(
+            HumanDuration(Duration::from_millis(340)).to_string(),
+            "340 ms"
+        );
+        assert_eq!(
+            HumanDuration(Duration::from_millis(1500)).to_string(),
+            "1.5 s"
+        );
+        assert_eq!(
+            HumanDuration(Duration::from_secs(120)).to_string(),
+            "2.0 min"
+        );
+    }
+}
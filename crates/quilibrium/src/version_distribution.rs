@@ -0,0 +1,235 @@
+//! Distribution of protocol versions across the peers a node knows about.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::node::{PeerId, PeerInfoResponse, PeerManifest, PeerManifestsResponse};
+use crate::protocol_version::ProtocolVersion;
+
+/// The number of peers per protocol version, optionally weighted by the hardware reported in the
+/// peer manifests.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VersionDistribution {
+    /// The versions ordered from the highest to the lowest.
+    pub versions: Vec<VersionShare>,
+    /// The sum of the weights of all versions.
+    pub total_weight: f64,
+}
+
+/// The share of the network that runs a protocol version.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VersionShare {
+    /// The protocol version.
+    pub version: ProtocolVersion,
+    /// The number of cooperative peers running the version.
+    pub cooperative_peers: usize,
+    /// The number of uncooperative peers running the version.
+    pub uncooperative_peers: usize,
+    /// The number of peers running the version or the sum of their hardware if weighted.
+    pub weight: f64,
+    /// The fraction of the total weight running the version.
+    pub share: f64,
+    /// The fraction of the total weight running at least the version.
+    pub cumulative_share: f64,
+}
+
+/// Hardware from the peer manifests to weight the peers by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareWeight {
+    /// The number of logical cores.
+    Cores,
+    /// The available memory in bytes.
+    Memory,
+    /// The available storage in bytes.
+    Storage,
+}
+
+impl HardwareWeight {
+    /// The weight of a peer based on its manifest.
+    pub fn weight(&self, manifest: &PeerManifest) -> f64 {
+        match self {
+            HardwareWeight::Cores => manifest.cores as f64,
+            HardwareWeight::Memory => manifest.memory as f64,
+            HardwareWeight::Storage => manifest.storage as f64,
+        }
+    }
+}
+
+impl VersionDistribution {
+    /// Count the cooperative and uncooperative peers per protocol version. Peers that appear
+    /// multiple times are only counted once.
+    pub fn new(peer_info: &PeerInfoResponse) -> Self {
+        Self::with_weights(peer_info, |_| 1.0)
+    }
+
+    /// Weight the peers per protocol version by the hardware reported in their peer manifests.
+    /// Peers without a manifest have zero weight, but they're still counted.
+    pub fn weighted(
+        peer_info: &PeerInfoResponse,
+        peer_manifests: &PeerManifestsResponse,
+        weight: HardwareWeight,
+    ) -> Self {
+        let manifests: HashMap<&PeerId, &PeerManifest> = peer_manifests
+            .peer_manifests
+            .iter()
+            .map(|manifest| (&manifest.peer_id, manifest))
+            .collect();
+        Self::with_weights(peer_info, |peer_id| {
+            manifests
+                .get(peer_id)
+                .map_or(0.0, |manifest| weight.weight(manifest))
+        })
+    }
+
+    fn with_weights(peer_info: &PeerInfoResponse, weight: impl Fn(&PeerId) -> f64) -> Self {
+        let mut by_version: BTreeMap<ProtocolVersion, (usize, usize, f64)> = BTreeMap::new();
+        let mut seen = HashSet::new();
+
+        let peers = peer_info.peers.iter().map(|peer| (peer, true)).chain(
+            peer_info
+                .uncooperative_peers
+                .iter()
+                .map(|peer| (peer, false)),
+        );
+        for (peer, cooperative) in peers {
+            if !seen.insert(peer.peer_id) {
+                continue;
+            }
+            let entry = by_version.entry(peer.version).or_default();
+            if cooperative {
+                entry.0 += 1;
+            } else {
+                entry.1 += 1;
+            }
+            entry.2 += weight(&peer.peer_id);
+        }
+
+        let total_weight: f64 = by_version.values().map(|(_, _, weight)| weight).sum();
+        let fraction = |weight: f64| {
+            if total_weight > 0.0 {
+                weight / total_weight
+            } else {
+                0.0
+            }
+        };
+
+        let mut cumulative_weight = 0.0;
+        let versions = by_version
+            .into_iter()
+            .rev()
+            .map(
+                |(version, (cooperative_peers, uncooperative_peers, weight))| {
+                    cumulative_weight += weight;
+                    VersionShare {
+                        version,
+                        cooperative_peers,
+                        uncooperative_peers,
+                        weight,
+                        share: fraction(weight),
+                        cumulative_share: fraction(cumulative_weight),
+                    }
+                },
+            )
+            .collect();
+
+        Self {
+            versions,
+            total_weight,
+        }
+    }
+
+    /// The fraction of the total weight running at least the version.
+    pub fn share_at_least(&self, version: &ProtocolVersion) -> f64 {
+        self.versions
+            .iter()
+            .filter(|share| share.version >= *version)
+            .map(|share| share.share)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{peer_info, peer_manifest, random_peer_id};
+
+    #[test]
+    fn counts_and_cumulative_shares() {
+        let (old, new) = (ProtocolVersion::new(1, 1, 8), ProtocolVersion::new(1, 2, 0));
+        let ids: Vec<PeerId> = (0..4).map(|_| random_peer_id()).collect();
+        let response = PeerInfoResponse {
+            peers: vec![
+                peer_info(ids[0], new),
+                peer_info(ids[1], new),
+                peer_info(ids[2], old),
+            ],
+            // Duplicates are only counted once.
+            uncooperative_peers: vec![peer_info(ids[3], old), peer_info(ids[0], new)],
+        };
+
+        let distribution = VersionDistribution::new(&response);
+        assert_eq!(distribution.total_weight, 4.0);
+        assert_eq!(distribution.versions.len(), 2);
+
+        let newest = &distribution.versions[0];
+        assert_eq!(newest.version, new);
+        assert_eq!(
+            (newest.cooperative_peers, newest.uncooperative_peers),
+            (2, 0)
+        );
+        assert_eq!((newest.share, newest.cumulative_share), (0.5, 0.5));
+
+        let oldest = &distribution.versions[1];
+        assert_eq!(oldest.version, old);
+        assert_eq!(
+            (oldest.cooperative_peers, oldest.uncooperative_peers),
+            (1, 1)
+        );
+        assert_eq!((oldest.share, oldest.cumulative_share), (0.5, 1.0));
+
+        assert_eq!(
+            distribution.share_at_least(&ProtocolVersion::new(1, 2, 0)),
+            0.5
+        );
+        assert_eq!(
+            distribution.share_at_least(&ProtocolVersion::new(1, 0, 0)),
+            1.0
+        );
+        assert_eq!(
+            distribution.share_at_least(&ProtocolVersion::new(2, 0, 0)),
+            0.0
+        );
+    }
+
+    fn manifest(peer_id: PeerId, cores: u32) -> PeerManifest {
+        PeerManifest {
+            cores,
+            ..peer_manifest(peer_id)
+        }
+    }
+
+    #[test]
+    fn weighted_by_cores() {
+        let (old, new) = (ProtocolVersion::new(1, 1, 8), ProtocolVersion::new(1, 2, 0));
+        let ids: Vec<PeerId> = (0..3).map(|_| random_peer_id()).collect();
+        let response = PeerInfoResponse {
+            peers: vec![
+                peer_info(ids[0], new),
+                peer_info(ids[1], old),
+                peer_info(ids[2], old),
+            ],
+            uncooperative_peers: vec![],
+        };
+        let manifests = PeerManifestsResponse {
+            // The third peer has no manifest.
+            peer_manifests: vec![manifest(ids[0], 24), manifest(ids[1], 8)],
+        };
+
+        let distribution =
+            VersionDistribution::weighted(&response, &manifests, HardwareWeight::Cores);
+        assert_eq!(distribution.total_weight, 32.0);
+        assert_eq!(distribution.versions[0].weight, 24.0);
+        assert_eq!(distribution.versions[0].share, 0.75);
+        assert_eq!(distribution.versions[1].weight, 8.0);
+        assert_eq!(distribution.versions[1].cooperative_peers, 2);
+    }
+}